@@ -1,5 +1,8 @@
+use crate::cli::commands::misc::{GroupBy, ListFormat};
+use crate::cli::commands::play::ChangeSource;
 use crate::provider::ProviderKind;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 /// grit - Git-like version control for playlists
 ///
@@ -37,6 +40,13 @@ pub struct Cli {
     )]
     pub playlist: Option<String>,
 
+    #[arg(
+        long,
+        global = true,
+        help = "Account to use for provider credentials (default account if not specified)"
+    )]
+    pub account: Option<String>,
+
     #[arg(
         short,
         long,
@@ -46,6 +56,30 @@ pub struct Cli {
     )]
     pub verbose: bool,
 
+    #[arg(
+        long,
+        global = true,
+        default_value_t = false,
+        help = "Disable colored output (also respects NO_COLOR)"
+    )]
+    pub no_color: bool,
+
+    #[arg(
+        long,
+        global = true,
+        default_value_t = false,
+        help = "Print long output directly instead of through $PAGER"
+    )]
+    pub no_pager: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Directory holding grit's state (also GRIT_DIR; defaults to ./.grit if present, else the user data dir)"
+    )]
+    pub grit_dir: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -56,34 +90,83 @@ pub enum Commands {
     #[command(visible_alias = "i")]
     Init {
         #[arg(
+            required_unless_present = "from_file",
             help = "Playlist URL or ID\n                       Example: https://open.spotify.com/playlist/37i9..."
         )]
-        playlist: String,
+        playlist: Option<String>,
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with = "playlist",
+            requires = "name",
+            help = "Create a local playlist from a YAML or M3U list of track IDs"
+        )]
+        from_file: Option<String>,
+        #[arg(long, requires = "from_file", help = "Name for the local playlist")]
+        name: Option<String>,
         #[arg(
             short,
             long,
             help = "Provider (auto-detected from URL if not specified, defaults to Spotify)"
         )]
         provider: Option<ProviderKind>,
+        #[arg(
+            long,
+            value_name = "N",
+            conflicts_with = "from_file",
+            value_parser = clap::value_parser!(u64).range(1..),
+            help = "Only track the first N tracks (push is disabled for such playlists)"
+        )]
+        depth: Option<u64>,
     },
 
     /// Pull latest changes from remote (like 'git pull')
-    Pull,
+    Pull {
+        #[arg(long, help = "Ignore the cached remote state and fetch again")]
+        refresh: bool,
+    },
 
     /// Show sync status (like 'git status')
     #[command(visible_alias = "st")]
     Status {
         #[arg(short = 'l', long, help = "Playlist ID or use --playlist")]
         playlist: Option<String>,
+        #[arg(long, help = "Ignore the cached remote state and fetch again")]
+        refresh: bool,
+        #[arg(long, help = "Exit with an error if the remote can't be checked")]
+        strict: bool,
     },
 
     /// Show commit history (like 'git log')
-    Log,
+    Log {
+        #[arg(long, help = "Show oldest entries first")]
+        reverse: bool,
+    },
+
+    /// Show recent activity across all tracked playlists
+    Activity {
+        #[arg(
+            short = 'n',
+            long,
+            default_value_t = 20,
+            help = "Maximum number of entries to show"
+        )]
+        limit: usize,
+    },
 
     /// Apply a playlist state from file
     Apply {
-        #[arg(help = "Path to the YAML file")]
-        file: String,
+        #[arg(
+            required_unless_present = "stdin",
+            help = "Path to the YAML file, or '-' for standard input"
+        )]
+        file: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "file",
+            help = "Read the YAML from standard input"
+        )]
+        stdin: bool,
     },
 
     /// Start playback with TUI player
@@ -93,12 +176,58 @@ pub enum Commands {
         playlist: Option<String>,
         #[arg(short, long, help = "Start with shuffle enabled")]
         shuffle: bool,
+        #[arg(long, help = "Spotify Connect device name to play on")]
+        device: Option<String>,
+        #[arg(long, help = "Start Spotify playback and exit without the TUI")]
+        detach: bool,
+        #[arg(
+            long,
+            conflicts_with = "detach",
+            help = "Cue up the playlist without starting audio"
+        )]
+        paused: bool,
+        #[arg(
+            long,
+            value_parser = clap::value_parser!(u8).range(0..=100),
+            help = "Initial volume (0-100)"
+        )]
+        volume: Option<u8>,
+        #[arg(
+            long,
+            help = "With repeat-all, re-fetch the remote playlist each time it wraps (YouTube)"
+        )]
+        loop_playlist: bool,
+        #[arg(
+            long,
+            value_name = "POSITION",
+            help = "Start at this track position (as shown by 'grit list')"
+        )]
+        start_at: Option<usize>,
+        #[arg(
+            long,
+            value_name = "SOURCE",
+            num_args = 0..=1,
+            default_missing_value = "remote",
+            help = "Play only the tracks a diff adds: 'remote' (default) or 'staged'"
+        )]
+        changes: Option<ChangeSource>,
+        #[arg(
+            long,
+            help = "Draw the player as a single line (title, artists, progress) for small panes"
+        )]
+        mini: bool,
     },
 
     /// Authenticate with Spotify or YouTube
     Auth {
         #[arg(help = "Provider: 'spotify' or 'youtube'")]
         provider: ProviderKind,
+        #[arg(
+            long = "as",
+            value_name = "ACCOUNT",
+            help = "Store the credentials under a named account"
+        )]
+        account: Option<String>,
     },
 
     /// Search for tracks to add
@@ -108,13 +237,51 @@ pub enum Commands {
         query: String,
         #[arg(short, long, help = "Interactive mode to add tracks by index")]
         add: bool,
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Preview result N (30 seconds) before listing"
+        )]
+        play: Option<usize>,
+        #[arg(
+            long,
+            default_value_t = 10,
+            help = "Number of results to fetch (max 50)"
+        )]
+        limit: usize,
+        #[arg(long, overrides_with = "no_ids", help = "Show track ids")]
+        ids: bool,
+        #[arg(long, overrides_with = "ids", help = "Hide track ids")]
+        no_ids: bool,
     },
 
-    /// Stage a track for addition (like 'git add')
+    /// Stage tracks for addition (like 'git add')
     #[command(visible_alias = "a")]
     Add {
-        #[arg(help = "Track ID from search results")]
-        track_id: String,
+        #[arg(
+            required = true,
+            help = "Track IDs from search results, added in order"
+        )]
+        track_ids: Vec<String>,
+        #[arg(
+            long,
+            value_name = "TRACK_ID",
+            conflicts_with = "before",
+            help = "Insert right after this track instead of at the end"
+        )]
+        after: Option<String>,
+        #[arg(
+            long,
+            value_name = "TRACK_ID",
+            help = "Insert right before this track instead of at the end"
+        )]
+        before: Option<String>,
+    },
+
+    /// Stage the tracks from an Exportify CSV or YouTube Takeout playlist CSV
+    Import {
+        #[arg(help = "Export file to read")]
+        file: PathBuf,
     },
 
     /// Stage a track for removal (like 'git rm')
@@ -131,19 +298,51 @@ pub enum Commands {
         track_id: String,
         #[arg(help = "New position (0-based index)")]
         new_index: usize,
+        #[arg(long, help = "Show the resulting order without staging the move")]
+        preview: bool,
+    },
+
+    /// Reorder or prune the playlist by editing its track list in $EDITOR
+    Reorder {
+        #[arg(short = 'l', long, help = "Playlist ID or use --playlist")]
+        playlist: Option<String>,
+    },
+
+    /// Stage a copy of a track into another tracked playlist
+    Cp {
+        #[arg(help = "Track ID to copy")]
+        track_id: String,
+        #[arg(
+            long,
+            help = "Source playlist ID (uses working playlist if not specified)"
+        )]
+        from: Option<String>,
+        #[arg(long, help = "Destination playlist ID")]
+        to: String,
+        #[arg(
+            long = "move",
+            help = "Also stage the removal from the source playlist"
+        )]
+        move_track: bool,
     },
 
     /// Commit staged changes (like 'git commit')
     #[command(visible_alias = "c")]
     Commit {
-        #[arg(short, long, help = "Commit message")]
-        message: String,
+        #[arg(short, long, help = "Commit message (opens $EDITOR when omitted)")]
+        message: Option<String>,
     },
 
     /// Push local changes to remote (like 'git push')
     Push {
         #[arg(short = 'l', long, help = "Playlist ID to push")]
         playlist: Option<String>,
+
+        #[arg(
+            long,
+            help = "Make the remote match local exactly, verifying and retrying until it does"
+        )]
+        force: bool,
     },
 
     /// Show differences between versions (like 'git diff')
@@ -153,6 +352,10 @@ pub enum Commands {
         staged: bool,
         #[arg(long, help = "Show only remote changes")]
         remote: bool,
+        #[arg(long, help = "Ignore the cached remote state and fetch again")]
+        refresh: bool,
+        #[arg(long, help = "Show the old and new order side by side")]
+        visual: bool,
     },
 
     /// Clear staged changes (like 'git reset')
@@ -166,6 +369,17 @@ pub enum Commands {
     List {
         #[arg(short = 'l', long, help = "Playlist ID")]
         playlist: Option<String>,
+
+        #[arg(long, value_enum, help = "Group tracks under artist or album headers")]
+        group_by: Option<GroupBy>,
+
+        #[arg(long, value_enum, default_value_t, help = "Track layout")]
+        format: ListFormat,
+
+        #[arg(long, overrides_with = "no_ids", help = "Show track ids")]
+        ids: bool,
+        #[arg(long, overrides_with = "ids", help = "Hide track ids")]
+        no_ids: bool,
     },
 
     /// Search within local playlist tracks
@@ -174,6 +388,19 @@ pub enum Commands {
         query: String,
         #[arg(short = 'l', long, help = "Playlist ID")]
         playlist: Option<String>,
+        #[arg(long, overrides_with = "no_ids", help = "Show track ids")]
+        ids: bool,
+        #[arg(long, overrides_with = "ids", help = "Hide track ids")]
+        no_ids: bool,
+        #[arg(long, help = "Start playing at the first match")]
+        play: bool,
+        #[arg(
+            long,
+            value_name = "N",
+            value_parser = clap::value_parser!(u64).range(1..),
+            help = "Start playing at the Nth match"
+        )]
+        play_nth: Option<u64>,
     },
 
     /// Delete credentials for a provider
@@ -188,12 +415,50 @@ pub enum Commands {
         provider: ProviderKind,
     },
 
+    /// Check the environment for common setup problems
+    Doctor,
+
+    /// Back up every tracked playlist to a .tar.gz archive
+    Export {
+        #[arg(help = "Archive to write, e.g. grit-backup.tar.gz")]
+        output: PathBuf,
+        #[arg(long, help = "Also include login tokens and their encryption key")]
+        include_credentials: bool,
+    },
+
+    /// Restore a library archive made by 'grit export'
+    ImportArchive {
+        #[arg(help = "Archive to restore")]
+        archive: PathBuf,
+        #[arg(
+            long,
+            help = "Restore even if playlists are already tracked, overwriting them"
+        )]
+        force: bool,
+    },
+
+    /// Manage settings in .grit/config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
     /// List all tracked playlists
     Playlists {
         #[arg(help = "Optional search query to filter")]
         query: Option<String>,
     },
 
+    /// Stop tracking a playlist and delete its local data
+    Drop {
+        #[arg(short = 'l', long, help = "Playlist ID to drop")]
+        playlist: Option<String>,
+        #[arg(long, help = "Also delete the playlist on the provider")]
+        remote: bool,
+        #[arg(short, long, help = "Skip the confirmation prompt")]
+        yes: bool,
+    },
+
     /// Switch the working playlist
     Switch {
         #[arg(help = "Playlist ID to set as working playlist")]
@@ -203,6 +468,12 @@ pub enum Commands {
     /// Show current working playlist info
     Curr,
 
+    /// List every stored snapshot, including unreferenced ones
+    Reflog {
+        #[arg(short = 'l', long, help = "Playlist ID")]
+        playlist: Option<String>,
+    },
+
     /// Revert playlist to a previous commit
     Revert {
         #[arg(help = "Commit hash (defaults to previous commit)")]
@@ -211,3 +482,14 @@ pub enum Commands {
         playlist: Option<String>,
     },
 }
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Open the config in $EDITOR (created with defaults if missing)
+    Edit,
+    /// Save a provider's client id and secret (the secret encrypted)
+    SetClient {
+        #[arg(help = "Provider: 'spotify' or 'youtube'")]
+        provider: ProviderKind,
+    },
+}
@@ -1,128 +1,221 @@
+use crate::cli::commands::utils::client_credentials;
 use crate::provider::{Provider, ProviderKind, SpotifyProvider, YoutubeProvider};
-use crate::state::credentials;
+use crate::state::{config, credentials};
 use anyhow::{Context, Result};
 use std::io::{BufRead, BufReader, Write};
-use std::net::TcpListener;
+use std::net::{TcpListener, TcpStream};
 use std::path::Path;
+use std::time::Duration;
 
-const REDIRECT_URI: &str = "http://127.0.0.1:8888/callback";
+/// Ports tried after the configured one, for providers that accept any
+/// loopback port
+const FALLBACK_PORTS: u16 = 10;
+
+/// How long a connection to the callback server may take to send its
+/// request line. Browsers open speculative connections that never send one.
+const CALLBACK_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Local address the OAuth callback listener binds to
+pub(crate) fn callback_addr(port: u16) -> String {
+    format!("127.0.0.1:{}", port)
+}
 
 /// Run the authentication flow for the given provider.
 pub async fn run(provider: ProviderKind, grit_dir: &Path) -> Result<()> {
+    let port = config::load(grit_dir)?.auth.callback_port;
     match provider {
-        ProviderKind::Spotify => auth_spotify(grit_dir).await,
-        ProviderKind::Youtube => auth_youtube(grit_dir).await,
+        ProviderKind::Spotify => auth_spotify(port, grit_dir).await,
+        ProviderKind::Youtube => auth_youtube(port, grit_dir).await,
     }
 }
 
-async fn auth_spotify(grit_dir: &Path) -> Result<()> {
+/// Bind the callback listener and return it with the redirect URI to use.
+///
+/// Spotify only redirects to URIs registered for the app, so it gets exactly
+/// the configured port. Google accepts any loopback port for desktop
+/// clients, so YouTube moves on to the next few ports if it's taken.
+fn bind_callback(port: u16, provider: ProviderKind) -> Result<(TcpListener, String)> {
+    let last = match provider {
+        ProviderKind::Spotify => port,
+        ProviderKind::Youtube => port.saturating_add(FALLBACK_PORTS),
+    };
+
+    for candidate in port..=last {
+        if let Ok(listener) = TcpListener::bind(callback_addr(candidate)) {
+            if candidate != port {
+                println!(
+                    "Port {} is in use, listening on {} instead",
+                    port, candidate
+                );
+            }
+            let redirect_uri = format!("http://{}/callback", callback_addr(candidate));
+            return Ok((listener, redirect_uri));
+        }
+    }
+
+    anyhow::bail!(
+        "Failed to bind the auth callback to port {}. Is another instance running? \
+         Set a different [auth] callback_port in .grit/config.toml (and register \
+         that redirect URI with {}).",
+        port,
+        provider
+    )
+}
+
+async fn auth_spotify(port: u16, grit_dir: &Path) -> Result<()> {
+    let (client_id, client_secret) = client_credentials(ProviderKind::Spotify, grit_dir);
     let client_id =
-        std::env::var("SPOTIFY_CLIENT_ID").context("Set SPOTIFY_CLIENT_ID environment variable")?;
-    let client_secret = std::env::var("SPOTIFY_CLIENT_SECRET")
-        .context("Set SPOTIFY_CLIENT_SECRET environment variable")?;
+        client_id.context("Set SPOTIFY_CLIENT_ID, or run 'grit config set-client spotify'")?;
+    let client_secret = client_secret
+        .context("Set SPOTIFY_CLIENT_SECRET, or run 'grit config set-client spotify'")?;
 
     let provider = SpotifyProvider::new(client_id, client_secret);
+    let (listener, redirect_uri) = bind_callback(port, ProviderKind::Spotify)?;
 
     let state = format!("{:016x}", rand::random::<u64>());
-    let auth_url = provider.oauth_url(REDIRECT_URI, &state);
+    let auth_url = provider.oauth_url(&redirect_uri, &state);
 
     println!("Opening browser for Spotify authorization...\n");
     println!("If it doesn't open, visit:\n{}\n", auth_url);
 
     let _ = open::that(auth_url.clone());
 
-    let code = wait_for_callback(&state)?;
+    let code = wait_for_callback(&listener, &state)?;
 
     println!("Exchanging code for token...");
-    let token = provider.exchange_code(&code, REDIRECT_URI).await?;
+    let token = provider.exchange_code(&code, &redirect_uri).await?;
 
     credentials::save(grit_dir, ProviderKind::Spotify, &token)?;
 
-    println!("\nSuccessfully authenticated with Spotify!");
+    println!(
+        "\nSuccessfully authenticated with Spotify{}!",
+        account_suffix()
+    );
     println!(
         "  Token saved to {:?}",
-        grit_dir.join("credentials/spotify.json")
+        credentials::credentials_path(grit_dir, ProviderKind::Spotify)
     );
 
     Ok(())
 }
 
-async fn auth_youtube(grit_dir: &Path) -> Result<()> {
+async fn auth_youtube(port: u16, grit_dir: &Path) -> Result<()> {
+    let (client_id, client_secret) = client_credentials(ProviderKind::Youtube, grit_dir);
     let client_id =
-        std::env::var("YOUTUBE_CLIENT_ID").context("Set YOUTUBE_CLIENT_ID environment variable")?;
-    let client_secret = std::env::var("YOUTUBE_CLIENT_SECRET")
-        .context("Set YOUTUBE_CLIENT_SECRET environment variable")?;
+        client_id.context("Set YOUTUBE_CLIENT_ID, or run 'grit config set-client youtube'")?;
+    let client_secret = client_secret
+        .context("Set YOUTUBE_CLIENT_SECRET, or run 'grit config set-client youtube'")?;
 
     let provider = YoutubeProvider::new(client_id, client_secret);
+    let (listener, redirect_uri) = bind_callback(port, ProviderKind::Youtube)?;
 
     let state = format!("{:016x}", rand::random::<u64>());
-    let auth_url = provider.oauth_url(REDIRECT_URI, &state);
+    let auth_url = provider.oauth_url(&redirect_uri, &state);
 
     println!("Opening browser for YouTube authorization...\n");
     println!("If it doesn't open, visit:\n{}\n", auth_url);
 
     let _ = open::that(auth_url.clone());
 
-    let code = wait_for_callback(&state)?;
+    let code = wait_for_callback(&listener, &state)?;
 
     println!("Exchanging code for token...");
-    let token = provider.exchange_code(&code, REDIRECT_URI).await?;
+    let token = provider.exchange_code(&code, &redirect_uri).await?;
 
     credentials::save(grit_dir, ProviderKind::Youtube, &token)?;
 
-    println!("\nSuccessfully authenticated with YouTube!");
+    println!(
+        "\nSuccessfully authenticated with YouTube{}!",
+        account_suffix()
+    );
     println!(
         "  Token saved to {:?}",
-        grit_dir.join("credentials/youtube.json")
+        credentials::credentials_path(grit_dir, ProviderKind::Youtube)
     );
 
     Ok(())
 }
 
-fn wait_for_callback(expected_state: &str) -> Result<String> {
-    let listener = TcpListener::bind("127.0.0.1:8888")
-        .context("Failed to bind to port 8888. Is another instance running?")?;
+/// " (account 'name')" for a non-default account, empty otherwise
+fn account_suffix() -> String {
+    match credentials::account() {
+        credentials::DEFAULT_ACCOUNT => String::new(),
+        account => format!(" (account '{}')", account),
+    }
+}
 
+fn wait_for_callback(listener: &TcpListener, expected_state: &str) -> Result<String> {
     println!("Waiting for callback...");
 
     for stream in listener.incoming() {
-        let mut stream = stream?;
-        let mut reader = BufReader::new(&stream);
-        let mut request_line = String::new();
-        reader.read_line(&mut request_line)?;
-
-        // Parse GET /callback?code=xxx&state=yyy HTTP/1.1
-        if let Some(path) = request_line.split_whitespace().nth(1) {
-            if path.starts_with("/callback?") {
-                let query = path.trim_start_matches("/callback?");
-                let params: std::collections::HashMap<_, _> =
-                    query.split('&').filter_map(|p| p.split_once('=')).collect();
-
-                if params.get("state") != Some(&expected_state) {
-                    send_response(&mut stream, "400", "State mismatch - possible CSRF")?;
-                    continue;
-                }
+        // A stray or broken connection (favicon, refresh, preconnect) must
+        // never end the wait for the real callback
+        let outcome = stream
+            .map_err(anyhow::Error::from)
+            .and_then(|stream| handle_callback_request(stream, expected_state));
+        match outcome {
+            Ok(Some(Callback::Code(code))) => return Ok(code),
+            Ok(Some(Callback::Denied(error))) => {
+                anyhow::bail!("Authorization denied: {}", error)
+            }
+            Ok(None) => {}
+            Err(e) => tracing::debug!("Ignoring callback connection: {:#}", e),
+        }
+    }
 
-                if let Some(&code) = params.get("code") {
-                    send_response(
-                        &mut stream,
-                        "200",
-                        "<html><body><h1>Success!</h1><p>You can close this tab.</p></body></html>",
-                    )?;
-                    return Ok(code.to_string());
-                }
+    anyhow::bail!("No valid callback received")
+}
 
-                if let Some(&error) = params.get("error") {
-                    send_response(&mut stream, "400", &format!("Auth failed: {}", error))?;
-                    anyhow::bail!("Authorization denied: {}", error);
-                }
+/// Result of a request that reached `/callback` with the right state
+enum Callback {
+    Code(String),
+    Denied(String),
+}
+
+/// Answer one connection to the callback server. `None` for anything that
+/// isn't the provider's redirect, so the caller keeps waiting.
+fn handle_callback_request(
+    mut stream: TcpStream,
+    expected_state: &str,
+) -> Result<Option<Callback>> {
+    stream.set_read_timeout(Some(CALLBACK_READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Parse GET /callback?code=xxx&state=yyy HTTP/1.1
+    if let Some(path) = request_line.split_whitespace().nth(1) {
+        if path.starts_with("/callback?") {
+            let query = path.trim_start_matches("/callback?");
+            let params: std::collections::HashMap<_, _> =
+                query.split('&').filter_map(|p| p.split_once('=')).collect();
+
+            if params.get("state") != Some(&expected_state) {
+                send_response(&mut stream, "400", "State mismatch - possible CSRF")?;
+                return Ok(None);
             }
-        }
 
-        send_response(&mut stream, "404", "Not Found")?;
+            if let Some(&code) = params.get("code") {
+                // The code is already ours; a browser that hung up early
+                // doesn't change that
+                let _ = send_response(
+                    &mut stream,
+                    "200",
+                    "<html><body><h1>Success!</h1><p>You can close this tab.</p></body></html>",
+                );
+                return Ok(Some(Callback::Code(code.to_string())));
+            }
+
+            if let Some(&error) = params.get("error") {
+                let _ = send_response(&mut stream, "400", &format!("Auth failed: {}", error));
+                return Ok(Some(Callback::Denied(error.to_string())));
+            }
+        }
     }
 
-    anyhow::bail!("No valid callback received")
+    // Includes empty request lines from connections closed without a request
+    send_response(&mut stream, "404", "Not Found")?;
+    Ok(None)
 }
 
 fn send_response(stream: &mut impl Write, status: &str, body: &str) -> Result<()> {
@@ -142,14 +235,14 @@ pub async fn logout(provider: ProviderKind, grit_dir: &Path) -> Result<()> {
     let token = credentials::load(grit_dir, provider)?;
 
     if token.is_none() {
-        println!("Not logged in to {:?}", provider);
+        println!("Not logged in to {:?}{}", provider, account_suffix());
         return Ok(());
     }
 
     // Delete credentials
     credentials::delete(grit_dir, provider)?;
 
-    println!("Logged out from {:?}", provider);
+    println!("Logged out from {:?}{}", provider, account_suffix());
     println!("Run 'grit auth {:?}' to login again", provider);
 
     Ok(())
@@ -161,7 +254,7 @@ pub async fn whoami(provider: ProviderKind, grit_dir: &Path) -> Result<()> {
 
     match provider {
         ProviderKind::Spotify => {
-            println!("Logged in to Spotify");
+            println!("Logged in to Spotify{}", account_suffix());
             println!("Token type: {}", token.token_type);
             if let Some(scope) = &token.scope {
                 println!("Scopes: {}", scope);
@@ -180,7 +273,7 @@ pub async fn whoami(provider: ProviderKind, grit_dir: &Path) -> Result<()> {
             }
         }
         ProviderKind::Youtube => {
-            println!("Logged in to YouTube");
+            println!("Logged in to YouTube{}", account_suffix());
             println!("Token type: {}", token.token_type);
             if let Some(scope) = &token.scope {
                 println!("Scopes: {}", scope);
@@ -202,3 +295,37 @@ pub async fn whoami(provider: ProviderKind, grit_dir: &Path) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stray_requests_do_not_end_the_callback_wait() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = std::thread::spawn(move || {
+            // Connects and hangs up without a request line
+            drop(TcpStream::connect(addr).unwrap());
+            // Sends garbage
+            let mut garbage = TcpStream::connect(addr).unwrap();
+            garbage.write_all(b"\xff\xfe\r\n").unwrap();
+            drop(garbage);
+            // The browser's favicon request
+            let mut favicon = TcpStream::connect(addr).unwrap();
+            favicon
+                .write_all(b"GET /favicon.ico HTTP/1.1\r\n\r\n")
+                .unwrap();
+            drop(favicon);
+
+            let mut callback = TcpStream::connect(addr).unwrap();
+            callback
+                .write_all(b"GET /callback?code=abc&state=xyz HTTP/1.1\r\n\r\n")
+                .unwrap();
+        });
+
+        assert_eq!(wait_for_callback(&listener, "xyz").unwrap(), "abc");
+        client.join().unwrap();
+    }
+}
@@ -0,0 +1,192 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use crate::state::snapshot;
+
+/// Top-level entries that hold secrets: the encrypted tokens and the key
+/// that decrypts them. Only archived with `--include-credentials`.
+const SECRET_ENTRIES: &[&str] = &["credentials", "encryption.key"];
+/// Top-level entries that are never archived
+const SKIPPED_ENTRIES: &[&str] = &["grit.log", "cache"];
+
+/// Write every tracked playlist (snapshots, history, staged changes) plus the
+/// working playlist and config to a gzipped tar archive.
+pub fn export_all(output: &Path, include_credentials: bool, grit_dir: &Path) -> Result<()> {
+    let playlists = snapshot::tracked_playlist_ids(grit_dir)?;
+    if playlists.is_empty() {
+        bail!(
+            "Nothing to export: no playlists are tracked in {:?}",
+            grit_dir
+        );
+    }
+
+    if include_credentials {
+        eprintln!(
+            "Warning: the archive will contain your login tokens and the key that decrypts \
+             them. Keep it private."
+        );
+    }
+
+    let file = File::create(output).with_context(|| format!("Failed to create {:?}", output))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    // The archive may be written inside `grit_dir`; it mustn't archive itself
+    let output =
+        fs::canonicalize(output).with_context(|| format!("Failed to resolve {:?}", output))?;
+    let root =
+        fs::canonicalize(grit_dir).with_context(|| format!("Failed to resolve {:?}", grit_dir))?;
+    for entry in sorted_entries(&root)? {
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if SKIPPED_ENTRIES.contains(&name)
+            || (!include_credentials && SECRET_ENTRIES.contains(&name))
+        {
+            continue;
+        }
+
+        append(&mut archive, &entry.path(), Path::new(name), &output)?;
+    }
+
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .with_context(|| format!("Failed to write {:?}", output))?;
+
+    println!(
+        "Exported {} playlist(s) to {}",
+        playlists.len(),
+        output.display()
+    );
+    if !include_credentials {
+        println!("Credentials were left out; run 'grit auth' after restoring.");
+    }
+
+    Ok(())
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<fs::DirEntry>> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {:?}", dir))?
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    Ok(entries)
+}
+
+/// Add `path` to the archive as `name`, recursing into directories and
+/// leaving out `skip`
+fn append<W: Write>(
+    archive: &mut tar::Builder<W>,
+    path: &Path,
+    name: &Path,
+    skip: &Path,
+) -> Result<()> {
+    if path == skip {
+        return Ok(());
+    }
+    if !path.is_dir() {
+        return archive
+            .append_path_with_name(path, name)
+            .with_context(|| format!("Failed to archive {:?}", path));
+    }
+
+    archive
+        .append_dir(name, path)
+        .with_context(|| format!("Failed to archive {:?}", path))?;
+    for entry in sorted_entries(path)? {
+        append(archive, &entry.path(), &name.join(entry.file_name()), skip)?;
+    }
+    Ok(())
+}
+
+/// Restore an archive made by `export_all`. Refuses to mix it into a
+/// directory that already tracks playlists unless `force` is set, in which
+/// case files from the archive overwrite their local copies.
+pub fn import_archive(archive_path: &Path, force: bool, grit_dir: &Path) -> Result<()> {
+    let existing = snapshot::tracked_playlist_ids(grit_dir)?;
+    if !existing.is_empty() && !force {
+        bail!(
+            "{:?} already tracks {} playlist(s). Restore into an empty directory \
+             (see --grit-dir) or pass --force to overwrite.",
+            grit_dir,
+            existing.len()
+        );
+    }
+
+    let file =
+        File::open(archive_path).with_context(|| format!("Failed to open {:?}", archive_path))?;
+    fs::create_dir_all(grit_dir).with_context(|| format!("Failed to create {:?}", grit_dir))?;
+
+    // `unpack` skips entries that would land outside `grit_dir`
+    tar::Archive::new(GzDecoder::new(file))
+        .unpack(grit_dir)
+        .with_context(|| format!("Failed to restore {:?}", archive_path))?;
+
+    let restored = snapshot::tracked_playlist_ids(grit_dir)?;
+    println!(
+        "Restored {} playlist(s) into {:?}",
+        restored.len(),
+        grit_dir
+    );
+    if !grit_dir.join("credentials").exists() {
+        println!("Run 'grit auth <provider>' to log in on this machine.");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_and_import_leave_out_credentials() {
+        let source = TempDir::new().unwrap();
+        let playlist_dir = snapshot::playlist_dir(source.path(), "abc");
+        fs::create_dir_all(playlist_dir.join("snapshots")).unwrap();
+        fs::write(playlist_dir.join("playlist.yaml"), "name: test").unwrap();
+        fs::write(playlist_dir.join("journal.log"), "{}").unwrap();
+        fs::create_dir_all(source.path().join("credentials")).unwrap();
+        fs::write(source.path().join("credentials/spotify.json"), "secret").unwrap();
+        fs::write(source.path().join("encryption.key"), "key").unwrap();
+        fs::write(source.path().join("grit.log"), "log").unwrap();
+
+        let out = TempDir::new().unwrap();
+        let archive = out.path().join("backup.tar.gz");
+        export_all(&archive, false, source.path()).unwrap();
+
+        let target = TempDir::new().unwrap();
+        import_archive(&archive, false, target.path()).unwrap();
+
+        let restored = snapshot::playlist_dir(target.path(), "abc");
+        assert_eq!(
+            fs::read_to_string(restored.join("playlist.yaml")).unwrap(),
+            "name: test"
+        );
+        assert!(restored.join("journal.log").exists());
+        assert!(!target.path().join("credentials").exists());
+        assert!(!target.path().join("encryption.key").exists());
+        assert!(!target.path().join("grit.log").exists());
+
+        // Exporting into the directory being archived leaves the archive out
+        let inside = playlist_dir.join("backup.tar.gz");
+        export_all(&inside, false, source.path()).unwrap();
+        let names: Vec<String> = tar::Archive::new(GzDecoder::new(File::open(&inside).unwrap()))
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+            .collect();
+        assert!(names.iter().any(|name| name.ends_with("playlist.yaml")));
+        assert!(!names.iter().any(|name| name.ends_with("backup.tar.gz")));
+
+        // A second restore would clobber what's now tracked
+        assert!(import_archive(&archive, false, target.path()).is_err());
+        import_archive(&archive, true, target.path()).unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::cli::commands::utils::client_env_vars;
+use crate::provider::ProviderKind;
+use crate::state::config::{self, Config};
+use crate::state::credentials;
+use crate::utils::editor;
+
+/// Open `.grit/config.toml` in the editor, starting from the defaults when
+/// there is none yet. The edit happens on a draft that only replaces the
+/// config once it's valid, so a typo can't break every other command.
+pub fn edit(grit_dir: &Path) -> Result<()> {
+    let path = config::config_path(grit_dir);
+    fs::create_dir_all(grit_dir).with_context(|| format!("Failed to create {:?}", grit_dir))?;
+
+    let current = if path.exists() {
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?
+    } else {
+        toml::to_string_pretty(&Config::default()).context("Failed to serialize default config")?
+    };
+
+    let draft = path.with_extension("edit.toml");
+    fs::write(&draft, current).with_context(|| format!("Failed to write {:?}", draft))?;
+    let result = edit_draft(grit_dir, &draft);
+    let _ = fs::remove_file(&draft);
+    result
+}
+
+/// Re-open `draft` until it holds a valid config, then save it
+fn edit_draft(grit_dir: &Path, draft: &Path) -> Result<()> {
+    loop {
+        editor::edit_file(draft)?;
+
+        let content =
+            fs::read_to_string(draft).with_context(|| format!("Failed to read {:?}", draft))?;
+        let err = match config::parse(&content) {
+            Ok(_) => {
+                config::save(grit_dir, &content)?;
+                println!("Config saved");
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        println!("\nConfig is invalid:\n{:#}", err);
+        print!("Re-open the editor? [Y/n] ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if input.trim().eq_ignore_ascii_case("n") {
+            bail!("Config edit discarded, previous settings kept");
+        }
+    }
+}
+
+/// Prompt for a provider's client id and secret and save them under
+/// `[credentials.<provider>]`, so grit works without exporting them in every
+/// shell. The secret is encrypted like the stored tokens.
+pub fn set_client(provider: ProviderKind, grit_dir: &Path) -> Result<()> {
+    let client_id = prompt("Client ID: ")?;
+    let client_secret =
+        rpassword::prompt_password("Client secret: ").context("Failed to read client secret")?;
+    let client_secret = client_secret.trim();
+    if client_id.is_empty() || client_secret.is_empty() {
+        bail!("Client ID and secret are both required");
+    }
+
+    let path = config::config_path(grit_dir);
+    let current = if path.exists() {
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?
+    } else {
+        String::new()
+    };
+    let encrypted = credentials::encrypt_secret(client_secret, grit_dir)?;
+    let content = with_client(&current, provider, &client_id, &encrypted)
+        .with_context(|| format!("Failed to update config {:?}", path))?;
+    config::save(grit_dir, &content)?;
+
+    println!("Saved {} client credentials to {:?}", provider, path);
+    let (id_var, secret_var) = client_env_vars(provider);
+    if std::env::var_os(id_var).is_some() || std::env::var_os(secret_var).is_some() {
+        println!(
+            "Note: {} / {} in the environment still take precedence",
+            id_var, secret_var
+        );
+    }
+    Ok(())
+}
+
+/// `content` with `[credentials.<provider>]` set to the given client. The
+/// rest of the file, comments and key order included, is left as it was.
+fn with_client(
+    content: &str,
+    provider: ProviderKind,
+    client_id: &str,
+    client_secret: &str,
+) -> Result<String> {
+    let mut doc: DocumentMut = content.parse()?;
+
+    let credentials = doc
+        .entry("credentials")
+        .or_insert_with(|| {
+            // Only `[credentials.<provider>]` headers, no empty `[credentials]`
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_like_mut()
+        .context("[credentials] is not a table")?;
+    let client = credentials
+        .entry(&provider.to_string())
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+        .with_context(|| format!("[credentials.{}] is not a table", provider))?;
+    client.insert("client_id", toml_edit::value(client_id));
+    client.insert("client_secret", toml_edit::value(client_secret));
+
+    Ok(doc.to_string())
+}
+
+fn prompt(label: &str) -> Result<String> {
+    print!("{}", label);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_client_keeps_the_rest_of_the_file() {
+        let content = "# my settings\n\
+                       [tui]\n\
+                       ascii_symbols = true # no nerd font here\n\
+                       \n\
+                       [credentials.youtube]\n\
+                       client_id = \"yt\"\n";
+
+        let updated = with_client(content, ProviderKind::Spotify, "id", "secret").unwrap();
+        assert!(
+            updated.starts_with("# my settings\n[tui]\nascii_symbols = true # no nerd font here\n")
+        );
+        assert!(!updated.contains("[credentials]\n"));
+
+        let config = config::parse(&updated).unwrap();
+        assert_eq!(config.credentials.spotify.client_id.as_deref(), Some("id"));
+        assert_eq!(
+            config.credentials.spotify.client_secret.as_deref(),
+            Some("secret")
+        );
+        assert_eq!(config.credentials.youtube.client_id.as_deref(), Some("yt"));
+        assert!(config.tui.ascii_symbols);
+    }
+}
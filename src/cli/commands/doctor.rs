@@ -0,0 +1,325 @@
+use std::collections::HashSet;
+use std::net::TcpListener;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use super::auth::callback_addr;
+use super::utils::{client_credentials, create_provider};
+use crate::playback::{mpv, SpotifyPlayer};
+use crate::provider::ProviderKind;
+use crate::state::{config, credentials, snapshot};
+use crate::utils::http;
+
+const LRCLIB_URL: &str = "https://lrclib.net/api/search?q=grit";
+
+enum Status {
+    Pass,
+    Skip,
+    Warn,
+    Fail,
+}
+
+#[derive(Default)]
+struct Report {
+    failures: usize,
+    warnings: usize,
+}
+
+impl Report {
+    fn check(&mut self, status: Status, label: &str, hint: Option<&str>) {
+        let tag = match status {
+            Status::Pass => "[ok]  ",
+            Status::Skip => "[skip]",
+            Status::Warn => {
+                self.warnings += 1;
+                "[warn]"
+            }
+            Status::Fail => {
+                self.failures += 1;
+                "[fail]"
+            }
+        };
+        println!("{} {}", tag, label);
+        if let (Some(hint), true) = (hint, matches!(status, Status::Warn | Status::Fail)) {
+            for line in hint.lines() {
+                println!("         {}", line.trim());
+            }
+        }
+    }
+}
+
+/// Check the local environment for common setup problems.
+pub async fn run(grit_dir: &Path) -> Result<()> {
+    let mut report = Report::default();
+    let tracked = tracked_providers(grit_dir);
+
+    println!("grit doctor\n");
+
+    check_grit_dir(&mut report, grit_dir);
+    check_config(&mut report, grit_dir);
+    check_playback_dependencies(&mut report, tracked.contains(&ProviderKind::Youtube));
+    check_callback_port(&mut report, grit_dir);
+    check_lrclib(&mut report).await;
+
+    for provider in [ProviderKind::Spotify, ProviderKind::Youtube] {
+        check_provider(&mut report, provider, grit_dir, tracked.contains(&provider)).await;
+    }
+
+    println!();
+    if report.failures > 0 {
+        anyhow::bail!(
+            "{} critical check(s) failed, {} warning(s)",
+            report.failures,
+            report.warnings
+        );
+    }
+
+    if report.warnings > 0 {
+        println!(
+            "All critical checks passed ({} warning(s))",
+            report.warnings
+        );
+    } else {
+        println!("All checks passed");
+    }
+
+    Ok(())
+}
+
+/// Providers of all locally tracked playlists.
+fn tracked_providers(grit_dir: &Path) -> HashSet<ProviderKind> {
+    let Ok(entries) = std::fs::read_dir(grit_dir.join("playlists")) else {
+        return HashSet::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let id = snapshot::playlist_id_from_dir(&entry.file_name().to_string_lossy());
+            snapshot::load(&snapshot::snapshot_path(grit_dir, &id)).ok()
+        })
+        .map(|snap| snap.provider)
+        .collect()
+}
+
+/// Unknown keys are rejected, so a typo shows up here instead of an option
+/// silently doing nothing
+fn check_config(report: &mut Report, grit_dir: &Path) {
+    let path = config::config_path(grit_dir);
+    if !path.exists() {
+        report.check(Status::Skip, "No config.toml, using defaults", None);
+        return;
+    }
+
+    match config::load(grit_dir) {
+        Ok(_) => report.check(Status::Pass, "config.toml is valid", None),
+        Err(e) => report.check(
+            Status::Fail,
+            &format!("config.toml is invalid: {:#}", e),
+            Some("Run 'grit config edit' to fix it"),
+        ),
+    }
+}
+
+fn check_grit_dir(report: &mut Report, grit_dir: &Path) {
+    if !grit_dir.exists() {
+        report.check(
+            Status::Skip,
+            &format!("{} does not exist yet", grit_dir.display()),
+            None,
+        );
+        return;
+    }
+
+    let probe = grit_dir.join(".doctor-probe");
+    let writable = std::fs::write(&probe, b"ok").and_then(|_| std::fs::remove_file(&probe));
+
+    match writable {
+        Ok(()) => report.check(
+            Status::Pass,
+            &format!("{} is writable", grit_dir.display()),
+            None,
+        ),
+        Err(e) => report.check(
+            Status::Fail,
+            &format!("{} is not writable: {}", grit_dir.display(), e),
+            Some("Check the directory permissions or run grit from a directory you own"),
+        ),
+    }
+}
+
+fn check_playback_dependencies(report: &mut Report, youtube_tracked: bool) {
+    match mpv::check_dependencies() {
+        Ok(()) => report.check(Status::Pass, "mpv and yt-dlp are installed", None),
+        Err(e) => {
+            let message = e.to_string();
+            let (label, hint) = message.split_once('\n').unwrap_or((&message, ""));
+            // Only critical when there is a YouTube playlist to play
+            let status = if youtube_tracked {
+                Status::Fail
+            } else {
+                Status::Warn
+            };
+            report.check(
+                status,
+                label.trim_end_matches(" Install it:"),
+                Some(hint.trim()),
+            );
+        }
+    }
+}
+
+fn check_callback_port(report: &mut Report, grit_dir: &Path) {
+    let port = config::load(grit_dir)
+        .map(|c| c.auth.callback_port)
+        .unwrap_or_else(|_| config::AuthConfig::default().callback_port);
+    let addr = callback_addr(port);
+
+    match TcpListener::bind(&addr) {
+        Ok(_) => report.check(
+            Status::Pass,
+            &format!("{} is free for the auth callback", addr),
+            None,
+        ),
+        Err(e) => report.check(
+            Status::Warn,
+            &format!("{} is unavailable: {}", addr, e),
+            Some("Free the port or set [auth] callback_port in .grit/config.toml"),
+        ),
+    }
+}
+
+async fn check_lrclib(report: &mut Report) {
+    let reachable = http::client()
+        .get(LRCLIB_URL)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .map(|resp| resp.status().is_success())
+        .unwrap_or(false);
+
+    if reachable {
+        report.check(Status::Pass, "lrclib.net is reachable", None);
+    } else {
+        report.check(
+            Status::Warn,
+            "lrclib.net is unreachable",
+            Some("Lyrics will not be available; check your network connection"),
+        );
+    }
+}
+
+async fn check_provider(
+    report: &mut Report,
+    provider: ProviderKind,
+    grit_dir: &Path,
+    tracked: bool,
+) {
+    let token = match credentials::load(grit_dir, provider) {
+        Ok(token) => token,
+        Err(e) => {
+            report.check(
+                Status::Fail,
+                &format!("{} credentials are unreadable: {}", provider, e),
+                Some(&format!(
+                    "Run 'grit logout {0}' and 'grit auth {0}'",
+                    provider
+                )),
+            );
+            return;
+        }
+    };
+
+    let (client_id, client_secret) = client_credentials(provider, grit_dir);
+    let env_prefix = provider.to_string().to_uppercase();
+    let missing: Vec<String> = [("CLIENT_ID", &client_id), ("CLIENT_SECRET", &client_secret)]
+        .into_iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(name, _)| format!("{}_{}", env_prefix, name))
+        .collect();
+
+    let Some(token) = token else {
+        if tracked {
+            report.check(
+                Status::Fail,
+                &format!("{} playlists are tracked but not authenticated", provider),
+                Some(&format!("Run 'grit auth {}'", provider)),
+            );
+        } else if missing.is_empty() {
+            report.check(
+                Status::Pass,
+                &format!("{} client credentials are set", provider),
+                None,
+            );
+        } else {
+            report.check(
+                Status::Skip,
+                &format!("{} is not configured", provider),
+                None,
+            );
+        }
+        return;
+    };
+
+    if missing.is_empty() {
+        report.check(
+            Status::Pass,
+            &format!("{} client credentials are set", provider),
+            None,
+        );
+    } else {
+        // Only needed to refresh an expired token, so not critical
+        report.check(
+            Status::Warn,
+            &format!("{} not set", missing.join(", ")),
+            Some(&format!(
+                "Set it in your environment or .env, or run 'grit config set-client {}', \
+                 before the stored token expires",
+                provider
+            )),
+        );
+    }
+
+    let validated = match create_provider(provider, grit_dir) {
+        Ok(p) => p.current_user().await,
+        Err(e) => Err(e),
+    };
+    match validated {
+        Ok(user) => report.check(
+            Status::Pass,
+            &format!("{} token is valid (logged in as {})", provider, user),
+            None,
+        ),
+        Err(e) => {
+            report.check(
+                Status::Fail,
+                &format!("{} token is invalid: {}", provider, e),
+                Some(&format!("Run 'grit auth {}' to log in again", provider)),
+            );
+            return;
+        }
+    }
+
+    if provider == ProviderKind::Spotify {
+        let player = SpotifyPlayer::new(token, client_id, client_secret, grit_dir);
+        match player.get_devices().await {
+            Ok(devices) if !devices.is_empty() => report.check(
+                Status::Pass,
+                &format!("{} Spotify device(s) available", devices.len()),
+                None,
+            ),
+            Ok(_) => report.check(
+                Status::Warn,
+                "No Spotify devices available",
+                Some("Open Spotify on a desktop, phone or web player before 'grit play'"),
+            ),
+            Err(e) => report.check(
+                Status::Warn,
+                &format!("Could not list Spotify devices: {}", e),
+                None,
+            ),
+        }
+    }
+}
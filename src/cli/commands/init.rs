@@ -1,10 +1,25 @@
-use crate::provider::{Provider, ProviderKind, SpotifyProvider, YoutubeProvider};
+use crate::cli::commands::utils::{client_credentials, create_provider, vcs_fetch_options};
+use crate::provider::{
+    PlaylistSnapshot, Provider, ProviderKind, SpotifyProvider, YoutubeProvider, LIKED_PLAYLIST_ID,
+    SCHEMA_VERSION,
+};
 use crate::state::{
-    clear_staged, credentials, snapshot, working_playlist, JournalEntry, Operation,
+    clear_staged, config, credentials, snapshot, working_playlist, JournalEntry, Operation,
 };
 use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::io::{self, IsTerminal, Write};
 use std::path::Path;
 
+/// Track list file for `grit init --from-file`: either a bare list of ids or
+/// a mapping with a `tracks` list.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TrackListFile {
+    Ids(Vec<String>),
+    Playlist { tracks: Vec<String> },
+}
+
 /// Extract playlist/album ID from URL or return as-is if already an ID
 fn extract_id(input: &str) -> String {
     // Handle Spotify playlist URLs
@@ -38,10 +53,134 @@ fn extract_id(input: &str) -> String {
     input.to_string()
 }
 
+/// Extract a track/video ID from a Spotify or YouTube URL, or return as-is
+fn extract_track_id(input: &str) -> String {
+    if input.contains("spotify.com/track/") {
+        return input
+            .split("track/")
+            .nth(1)
+            .and_then(|s| s.split('?').next())
+            .unwrap_or(input)
+            .to_string();
+    }
+
+    if let Some(start) = input.find("v=") {
+        return input[start + 2..]
+            .split('&')
+            .next()
+            .unwrap_or(input)
+            .to_string();
+    }
+
+    if input.contains("youtu.be/") {
+        return input
+            .split("youtu.be/")
+            .nth(1)
+            .and_then(|s| s.split('?').next())
+            .unwrap_or(input)
+            .to_string();
+    }
+
+    if let Some(id) = input.strip_prefix("spotify:track:") {
+        return id.to_string();
+    }
+
+    input.to_string()
+}
+
+/// Read track entries (ids or URLs) from a YAML list or an M3U file
+fn read_track_list(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read track list {:?}", path))?;
+
+    let is_m3u = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("m3u") || e.eq_ignore_ascii_case("m3u8"));
+
+    let entries = if is_m3u {
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(str::to_string)
+            .collect()
+    } else {
+        match serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse track list {:?}", path))?
+        {
+            TrackListFile::Ids(ids) => ids,
+            TrackListFile::Playlist { tracks } => tracks,
+        }
+    };
+
+    Ok(entries)
+}
+
+/// Directory key for a playlist that has no remote id yet
+fn local_key(name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    format!("local-{}", slug)
+}
+
 fn is_album_url(input: &str) -> bool {
     input.contains("spotify.com/album/")
 }
 
+/// Local key another tracked snapshot of the same remote playlist lives under,
+/// e.g. one initialized from a differently written id
+fn tracked_elsewhere(grit_dir: &Path, playlist: &PlaylistSnapshot, key: &str) -> Option<String> {
+    if playlist.id.is_empty() {
+        return None;
+    }
+    snapshot::tracked_playlist_ids(grit_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|other| other != key)
+        .find(|other| {
+            snapshot::load(&snapshot::snapshot_path(grit_dir, other)).is_ok_and(|existing| {
+                existing.provider == playlist.provider && existing.id == playlist.id
+            })
+        })
+}
+
+/// Offer to switch to the existing tracking dir instead of starting a second
+/// history for the same remote playlist
+fn reuse_existing(existing: &str, name: &str, grit_dir: &Path) -> Result<()> {
+    if !io::stdin().is_terminal() {
+        anyhow::bail!(
+            "'{}' is already tracked as {}. Use 'grit switch {}' and 'grit pull' instead.",
+            name,
+            existing,
+            existing
+        );
+    }
+
+    print!(
+        "'{}' is already tracked as {}. Switch to it instead? [Y/n] ",
+        name, existing
+    );
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().eq_ignore_ascii_case("n") {
+        anyhow::bail!("Not initialized: a second copy would split the playlist's history");
+    }
+
+    working_playlist::save(grit_dir, existing)?;
+    println!("Switched to {}. Use 'grit pull' to update.", existing);
+    Ok(())
+}
+
 /// Detect provider from playlist URL
 pub fn detect_provider(input: &str) -> Option<ProviderKind> {
     if input.contains("spotify.com") {
@@ -53,7 +192,14 @@ pub fn detect_provider(input: &str) -> Option<ProviderKind> {
     }
 }
 
-pub async fn run(provider: ProviderKind, input: &str, grit_dir: &Path) -> Result<()> {
+/// Start tracking a remote playlist or album. With `depth`, only its first
+/// `depth` tracks are tracked.
+pub async fn run(
+    provider: ProviderKind,
+    input: &str,
+    depth: Option<usize>,
+    grit_dir: &Path,
+) -> Result<()> {
     let id = extract_id(input);
     let is_album = is_album_url(input);
 
@@ -70,39 +216,49 @@ pub async fn run(provider: ProviderKind, input: &str, grit_dir: &Path) -> Result
         "No credentials found. Please run 'grit auth {provider}' first."
     ))?;
 
+    let (client_id, client_secret) = client_credentials(provider, grit_dir);
+    let options = vcs_fetch_options(depth, grit_dir)?;
+
     let playlist = match provider {
         ProviderKind::Spotify => {
-            let client_id =
-                std::env::var("SPOTIFY_CLIENT_ID").context("SPOTIFY_CLIENT_ID not set")?;
-            let client_secret =
-                std::env::var("SPOTIFY_CLIENT_SECRET").context("SPOTIFY_CLIENT_SECRET not set")?;
-
-            let spotify =
-                SpotifyProvider::new(client_id, client_secret).with_token(&token, grit_dir);
+            let spotify = SpotifyProvider::with_credentials(client_id, client_secret)
+                .with_token(&token, grit_dir)
+                .with_market(config::load(grit_dir)?.remote.spotify_market);
 
             if is_album {
                 println!("Fetching album {}...", id);
-                spotify.fetch_album(&id).await?
+                let mut album = spotify.fetch_album(&id).await?;
+                album.limit_to(depth, false);
+                album
             } else {
                 println!("Fetching playlist {}...", id);
-                spotify.fetch(&id).await?
+                spotify.fetch_with(&id, &options).await?
             }
         }
         ProviderKind::Youtube => {
-            let client_id =
-                std::env::var("YOUTUBE_CLIENT_ID").context("YOUTUBE_CLIENT_ID not set")?;
-            let client_secret =
-                std::env::var("YOUTUBE_CLIENT_SECRET").context("YOUTUBE_CLIENT_SECRET not set")?;
-
-            let youtube =
-                YoutubeProvider::new(client_id, client_secret).with_token(&token, grit_dir);
+            let youtube = YoutubeProvider::with_credentials(client_id, client_secret)
+                .with_token(&token, grit_dir);
             println!("Fetching playlist {}...", id);
-            youtube.fetch(&id).await?
+            youtube.fetch_with(&id, &options).await?
         }
     };
 
+    if let Some(existing) = tracked_elsewhere(grit_dir, &playlist, &id) {
+        return reuse_existing(&existing, &playlist.name, grit_dir);
+    }
+
+    if id == LIKED_PLAYLIST_ID {
+        println!("  (read-only: liked songs can be pulled and played, not pushed)");
+    }
+
     println!("  Name: {}", playlist.name);
     println!("  Tracks: {}", playlist.tracks.len());
+    if let Some(depth) = playlist.depth() {
+        println!(
+            "  (first {} tracks only: pull and diff compare just these, and push is disabled)",
+            depth
+        );
+    }
 
     snapshot::save(&playlist, &snapshot_path)?;
     let hash = snapshot::compute_hash(&playlist)?;
@@ -126,3 +282,67 @@ pub async fn run(provider: ProviderKind, input: &str, grit_dir: &Path) -> Result
 
     Ok(())
 }
+
+/// Start tracking a playlist defined in a local file. The snapshot has an
+/// empty id until the playlist exists remotely.
+pub async fn run_from_file(
+    provider: Option<ProviderKind>,
+    path: &Path,
+    name: &str,
+    grit_dir: &Path,
+) -> Result<()> {
+    let key = local_key(name);
+    let snapshot_path = snapshot::snapshot_path(grit_dir, &key);
+    if snapshot_path.exists() {
+        anyhow::bail!("Local playlist {} already initialized.", key);
+    }
+
+    let entries = read_track_list(path)?;
+    if entries.is_empty() {
+        anyhow::bail!("No tracks found in {:?}", path);
+    }
+
+    let provider = provider
+        .or_else(|| entries.iter().find_map(|e| detect_provider(e)))
+        .unwrap_or(ProviderKind::Spotify);
+    let ids: Vec<String> = entries.iter().map(|e| extract_track_id(e)).collect();
+
+    let provider_instance = create_provider(provider, grit_dir)?;
+
+    println!("Resolving {} track(s)...", ids.len());
+    let tracks = provider_instance
+        .fetch_tracks(&ids)
+        .await
+        .context("Failed to resolve tracks")?;
+    for track in &tracks {
+        println!("  {} - {}", track.name, track.artists.join(", "));
+    }
+
+    let playlist = PlaylistSnapshot {
+        id: String::new(),
+        name: name.to_string(),
+        description: None,
+        tracks,
+        provider,
+        snapshot_hash: String::new(),
+        metadata: None,
+        schema_version: SCHEMA_VERSION,
+    };
+
+    snapshot::save(&playlist, &snapshot_path)?;
+    let hash = snapshot::compute_hash(&playlist)?;
+    snapshot::save_by_hash(&playlist, &hash, grit_dir, &key)?;
+
+    let journal_path = JournalEntry::journal_path(grit_dir, &key);
+    let entry = JournalEntry::new(Operation::Init, hash, playlist.tracks.len(), 0, 0);
+    JournalEntry::append(&journal_path, &entry)?;
+
+    clear_staged(grit_dir, &key)?;
+    working_playlist::save(grit_dir, &key)?;
+
+    println!("\nLocal playlist initialized as '{}'", key);
+    println!("  Snapshot: {:?}", snapshot_path);
+    println!("  It has no remote yet; 'grit push' creates it.");
+
+    Ok(())
+}
@@ -1,11 +1,175 @@
+use std::fmt::Write as _;
 use std::fs;
+use std::io::{self, IsTerminal, Write};
 use std::path::Path;
 
 use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
 
+use crate::cli::commands::{play, utils::create_provider};
+use crate::provider::{youtube, Track, LIKED_PLAYLIST_ID};
 use crate::state::{snapshot, working_playlist};
+use crate::utils::{pager, text};
+
+/// How `grit list --group-by` groups tracks
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GroupBy {
+    /// First listed artist
+    Artist,
+    /// Album from track metadata, when the provider recorded it
+    Album,
+}
+
+impl GroupBy {
+    fn key(&self, track: &Track) -> String {
+        match self {
+            GroupBy::Artist => track
+                .artists
+                .first()
+                .cloned()
+                .unwrap_or_else(|| "Unknown artist".to_string()),
+            GroupBy::Album => track
+                .metadata
+                .as_ref()
+                .and_then(|m| m.get("album"))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| "Unknown album".to_string()),
+        }
+    }
+}
+
+/// How `grit list` lays out each track
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum ListFormat {
+    /// `index. [mm:ss] name - artists`
+    #[default]
+    Numbered,
+    /// `name - artists`, without position or duration
+    Compact,
+    /// Aligned columns, with long names truncated
+    Table,
+}
+
+/// Widest title and artist cells in `--format table`, in terminal columns
+const TABLE_TITLE_WIDTH: usize = 40;
+const TABLE_ARTISTS_WIDTH: usize = 30;
+
+fn format_tracks(tracks: &[(usize, &Track)], format: ListFormat, show_ids: bool) -> Vec<String> {
+    match format {
+        ListFormat::Numbered => tracks
+            .iter()
+            .map(|(i, track)| format_track_line(*i, track, show_ids))
+            .collect(),
+        ListFormat::Compact => tracks
+            .iter()
+            .map(|(_, track)| {
+                let line = format!(
+                    "{} - {}{}",
+                    track.name,
+                    track.artists.join(", "),
+                    explicit_badge(track)
+                );
+                if show_ids {
+                    format!("{}  ({})", line, track.id)
+                } else {
+                    line
+                }
+            })
+            .collect(),
+        ListFormat::Table => format_table(tracks, show_ids),
+    }
+}
+
+/// Rows sized to the widest cell in each column, measured in terminal
+/// columns so wide characters still line up
+fn format_table(tracks: &[(usize, &Track)], show_ids: bool) -> Vec<String> {
+    let mut header = vec!["#", "Title", "Artists", "Time"];
+    if show_ids {
+        header.push("ID");
+    }
+    let mut rows: Vec<Vec<String>> = vec![header.into_iter().map(str::to_string).collect()];
+
+    for (i, track) in tracks {
+        let duration_sec = track.duration_ms / 1000;
+        let mut row = vec![
+            i.to_string(),
+            format!(
+                "{}{}",
+                text::truncate(&track.name, TABLE_TITLE_WIDTH),
+                explicit_badge(track)
+            ),
+            text::truncate(&track.artists.join(", "), TABLE_ARTISTS_WIDTH),
+            format!("{}:{:02}", duration_sec / 60, duration_sec % 60),
+        ];
+        if show_ids {
+            row.push(track.id.clone());
+        }
+        rows.push(row);
+    }
+
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|col| {
+            rows.iter()
+                .map(|row| text::width(&row[col]))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(col, (cell, &width))| {
+                    // Numbers read better right-aligned
+                    if col == 0 || col == 3 {
+                        format!("{:>width$}", cell, width = width)
+                    } else {
+                        text::pad(cell, width)
+                    }
+                })
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        })
+        .collect()
+}
+
+fn explicit_badge(track: &Track) -> &'static str {
+    if track.is_explicit() {
+        " [E]"
+    } else {
+        ""
+    }
+}
+
+fn format_track_line(index: usize, track: &Track, show_id: bool) -> String {
+    let duration_sec = track.duration_ms / 1000;
+    let line = format!(
+        "{}. [{:02}:{:02}] {} - {}{}",
+        index,
+        duration_sec / 60,
+        duration_sec % 60,
+        track.name,
+        track.artists.join(", "),
+        explicit_badge(track)
+    );
+    if show_id {
+        format!("{}  ({})", line, track.id)
+    } else {
+        line
+    }
+}
 
-pub async fn list(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
+pub async fn list(
+    playlist: Option<&str>,
+    group_by: Option<GroupBy>,
+    format: ListFormat,
+    show_ids: bool,
+    grit_dir: &Path,
+) -> Result<()> {
     let playlist_id = playlist.context("Playlist required (use --playlist)")?;
 
     let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
@@ -15,30 +179,67 @@ pub async fn list(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
 
     let snapshot = snapshot::load(&snapshot_path)?;
 
-    println!("\nPlaylist: {}", snapshot.name);
+    let mut out = String::new();
+    writeln!(out, "\nPlaylist: {}", snapshot.name)?;
     if let Some(desc) = &snapshot.description {
-        println!("Description: {}", desc);
+        writeln!(out, "Description: {}", desc)?;
+    }
+    if snapshot.tracks.is_empty() {
+        writeln!(out, "\nThis playlist has no tracks.")?;
+        return pager::page(&out);
     }
-    println!("Tracks: {}\n", snapshot.tracks.len());
+    writeln!(out, "Tracks: {}\n", snapshot.tracks.len())?;
 
-    for (i, track) in snapshot.tracks.iter().enumerate() {
-        let duration_sec = track.duration_ms / 1000;
-        let min = duration_sec / 60;
-        let sec = duration_sec % 60;
-        let artists = track.artists.join(", ");
+    match group_by {
+        None => {
+            let tracks: Vec<(usize, &Track)> = snapshot.tracks.iter().enumerate().collect();
+            for line in format_tracks(&tracks, format, show_ids) {
+                writeln!(out, "{}", line)?;
+            }
+        }
+        Some(group_by) => {
+            // Keyed case-insensitively so "the xx" and "The xx" share a group;
+            // tracks keep playlist order inside each group
+            let mut groups = std::collections::BTreeMap::new();
+            for (i, track) in snapshot.tracks.iter().enumerate() {
+                let name = group_by.key(track);
+                groups
+                    .entry(name.to_lowercase())
+                    .or_insert_with(|| (name, Vec::<(usize, &Track)>::new()))
+                    .1
+                    .push((i, track));
+            }
 
-        println!(
-            "{}. [{:02}:{:02}] {} - {}",
-            i, min, sec, track.name, artists
-        );
+            for (name, tracks) in groups.values() {
+                writeln!(out, "{} ({})", name, tracks.len())?;
+                for line in format_tracks(tracks, format, show_ids) {
+                    writeln!(out, "  {}", line)?;
+                }
+                writeln!(out)?;
+            }
+        }
     }
 
-    println!("\nTotal duration: {} tracks", snapshot.tracks.len());
-
-    Ok(())
+    let total_sec = snapshot.tracks.iter().map(|t| t.duration_ms).sum::<u64>() / 1000;
+    writeln!(
+        out,
+        "\nTotal duration: {}:{:02}:{:02} ({} tracks)",
+        total_sec / 3600,
+        total_sec / 60 % 60,
+        total_sec % 60,
+        snapshot.tracks.len()
+    )?;
+
+    pager::page(&out)
 }
 
-pub async fn find(query: &str, playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
+pub async fn find(
+    query: &str,
+    playlist: Option<&str>,
+    show_ids: bool,
+    play_nth: Option<usize>,
+    grit_dir: &Path,
+) -> Result<()> {
     let playlist_id = playlist.context("Playlist required (use --playlist)")?;
 
     let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
@@ -74,7 +275,7 @@ pub async fn find(query: &str, playlist: Option<&str>, grit_dir: &Path) -> Resul
         snapshot.name
     );
 
-    for (i, track) in matches {
+    for &(i, track) in &matches {
         let duration_sec = track.duration_ms / 1000;
         let min = duration_sec / 60;
         let sec = duration_sec % 60;
@@ -84,10 +285,27 @@ pub async fn find(query: &str, playlist: Option<&str>, grit_dir: &Path) -> Resul
             "{}. [{:02}:{:02}] {} - {}",
             i, min, sec, track.name, artists
         );
-        println!("   ID: {}", track.id);
+        if show_ids {
+            println!("   ID: {}", track.id);
+        }
         println!();
     }
 
+    if let Some(n) = play_nth {
+        let Some(&(index, _)) = matches.get(n - 1) else {
+            bail!(
+                "--play-nth {} is out of range: only {} match(es)",
+                n,
+                matches.len()
+            );
+        };
+        let options = play::PlayOptions {
+            start_at: Some(index),
+            ..Default::default()
+        };
+        play::run(Some(playlist_id), &options, grit_dir).await?;
+    }
+
     Ok(())
 }
 
@@ -112,7 +330,9 @@ pub async fn playlists(query: Option<&str>, grit_dir: &Path) -> Result<()> {
             let playlist_id = path
                 .file_name()
                 .and_then(|n| n.to_str())
-                .unwrap_or("unknown");
+                .map(snapshot::playlist_id_from_dir)
+                .unwrap_or_else(|| "unknown".to_string());
+            let playlist_id = playlist_id.as_str();
 
             let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
             if snapshot_path.exists() {
@@ -121,7 +341,7 @@ pub async fn playlists(query: Option<&str>, grit_dir: &Path) -> Result<()> {
                         playlists_info.push((playlist_id.to_string(), snapshot));
                     }
                     Err(e) => {
-                        eprintln!("Warning: Failed to load playlist {}: {}", playlist_id, e);
+                        tracing::warn!("Failed to load playlist {}: {}", playlist_id, e);
                     }
                 }
             }
@@ -168,12 +388,7 @@ pub async fn playlists(query: Option<&str>, grit_dir: &Path) -> Result<()> {
         println!("  Provider: {:?}", snapshot.provider);
         println!("  Tracks: {}", snapshot.tracks.len());
         if let Some(desc) = &snapshot.description {
-            let desc_short = if desc.len() > 80 {
-                format!("{}...", &desc[..77])
-            } else {
-                desc.clone()
-            };
-            println!("  Description: {}", desc_short);
+            println!("  Description: {}", text::truncate(desc, 80));
         }
         println!();
     }
@@ -181,6 +396,75 @@ pub async fn playlists(query: Option<&str>, grit_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Stop tracking a playlist and delete its local data, optionally deleting
+/// it on the provider too
+pub async fn drop_playlist(
+    playlist_id: &str,
+    remote: bool,
+    yes: bool,
+    grit_dir: &Path,
+) -> Result<()> {
+    let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
+    if !snapshot_path.exists() {
+        bail!("Playlist '{}' is not tracked.", playlist_id);
+    }
+
+    let snap = snapshot::load(&snapshot_path)?;
+    if remote && (snap.id.is_empty() || snap.id == LIKED_PLAYLIST_ID) {
+        bail!("'{}' has no remote playlist to delete.", snap.name);
+    }
+
+    if !yes {
+        if !io::stdin().is_terminal() {
+            bail!(
+                "Refusing to drop '{}' without confirmation. Pass --yes.",
+                snap.name
+            );
+        }
+
+        let target = if remote {
+            format!("locally and on {}", snap.provider)
+        } else {
+            "locally".to_string()
+        };
+        print!(
+            "Drop '{}' ({} tracks) {}? [y/N] ",
+            snap.name,
+            snap.tracks.len(),
+            target
+        );
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
+    // Remote first, so a failed delete leaves the local copy to retry from
+    if remote {
+        let provider = create_provider(snap.provider, grit_dir)?;
+        provider.delete_playlist(&snap.id).await?;
+        println!("Deleted '{}' on {}", snap.name, snap.provider);
+    }
+
+    let dir = snapshot::playlist_dir(grit_dir, playlist_id);
+    fs::remove_dir_all(&dir).with_context(|| format!("Failed to remove {:?}", dir))?;
+    if let Err(e) = youtube::prune_response_cache(grit_dir) {
+        tracing::warn!("Failed to prune the YouTube response cache: {}", e);
+    }
+
+    if working_playlist::load(grit_dir).ok().as_deref() == Some(playlist_id) {
+        working_playlist::clear(grit_dir)?;
+    }
+
+    println!("Dropped '{}' ({})", snap.name, playlist_id);
+
+    Ok(())
+}
+
 pub async fn switch(playlist_id: &str, grit_dir: &Path) -> Result<()> {
     let current = working_playlist::load(grit_dir).ok();
 
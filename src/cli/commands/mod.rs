@@ -1,4 +1,7 @@
 pub mod auth;
+pub mod backup;
+pub mod config;
+pub mod doctor;
 pub mod init;
 pub mod misc;
 pub mod play;
@@ -1,13 +1,67 @@
 use anyhow::{bail, Context, Result};
-use crossterm::event::{KeyCode, KeyModifiers};
+use clap::ValueEnum;
 use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::task::JoinHandle;
 
-use crate::playback::{fetch_audio_url, LyricsFetcher, MpvPlayer, Queue, SpotifyPlayer};
-use crate::provider::ProviderKind;
-use crate::state::{credentials, snapshot, working_playlist};
-use crate::tui::{App, PlayerBackend, Tui};
+use crate::cli::commands::staging::stage_copy;
+use crate::cli::commands::utils::{create_provider, fetch_remote};
+use crate::playback::{
+    fetch_audio_url, lyrics, LyricsFetcher, MediaControls, MpvPlayer, NowPlayingFile,
+    NowPlayingNotifier, PlaybackState, Queue, SpotifyPlayer,
+};
+use crate::provider::{
+    spotify, Marker, PlaylistSnapshot, Provider, ProviderKind, Track, TrackChange,
+};
+use crate::state::config::{PlaylistEnd, SpotifyShuffle};
+use crate::state::{config, credentials, diff, load_staged, snapshot, working_playlist};
+use crate::tui::{Action, App, PlayerBackend, Scrub, Tui};
 
-pub async fn run(playlist: Option<&str>, shuffle: bool, grit_dir: &Path) -> Result<()> {
+/// How `grit play` starts playback
+#[derive(Default)]
+pub struct PlayOptions<'a> {
+    pub shuffle: bool,
+    /// Spotify Connect device name
+    pub device: Option<&'a str>,
+    pub detach: bool,
+    pub paused: bool,
+    /// Initial volume, 0-100
+    pub volume: Option<u8>,
+    /// Re-fetch the remote playlist each time repeat-all wraps around
+    pub loop_playlist: bool,
+    /// Track position to start at instead of the last played track
+    pub start_at: Option<usize>,
+    /// Play only the tracks a diff would add instead of the whole playlist
+    pub changes: Option<ChangeSource>,
+    /// Draw the player as a single line instead of full screen
+    pub mini: bool,
+}
+
+/// Which diff `grit play --changes` takes its tracks from
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ChangeSource {
+    /// Tracks on the remote that a pull would bring in
+    Remote,
+    /// Tracks staged for the next commit
+    Staged,
+}
+
+/// Upcoming tracks whose lyrics are looked up while the lyrics panel is open
+const LYRICS_PREFETCH: usize = 2;
+
+/// How long after a seek or track change Spotify is asked for its position,
+/// giving the command time to land
+const SPOTIFY_RESYNC_DELAY: Duration = Duration::from_millis(500);
+
+/// How long after grit pauses or resumes Spotify a poll still reporting the
+/// old state is put down to lag rather than another client
+const PAUSE_SETTLE: Duration = Duration::from_secs(2);
+
+/// Minimum time between `--loop-playlist` re-fetches, so a short or
+/// unchanged playlist doesn't hit the API on every wrap
+const LOOP_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
+
+pub async fn run(playlist: Option<&str>, options: &PlayOptions<'_>, grit_dir: &Path) -> Result<()> {
     let playlist_id = playlist.context("Playlist required (use --playlist or -l)")?;
 
     let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
@@ -15,31 +69,148 @@ pub async fn run(playlist: Option<&str>, shuffle: bool, grit_dir: &Path) -> Resu
         bail!("Playlist not tracked. Run 'grit init <playlist>' first.");
     }
 
-    let snap = snapshot::load(&snapshot_path)?;
+    let mut snap = snapshot::load(&snapshot_path)?;
+    let mut start_at = options.start_at;
+    if let Some(source) = options.changes {
+        if options.loop_playlist {
+            bail!("--loop-playlist can't be combined with --changes");
+        }
+        snap = preview_snapshot(&snap, playlist_id, source, grit_dir).await?;
+    } else if let Some(index) = start_at.filter(|&i| i < snap.tracks.len()) {
+        // `grit list` positions count placeholders, which aren't played
+        start_at = Some(
+            snap.tracks[..index]
+                .iter()
+                .filter(|t| !t.is_unavailable())
+                .count(),
+        );
+    }
+    snap.drop_unavailable();
     if snap.tracks.is_empty() {
-        bail!("Playlist is empty");
+        bail!(
+            "'{}' has no tracks to play. Add some with 'grit search' and 'grit add'.",
+            snap.name
+        );
     }
 
-    // Load last played track index
-    let start_index = working_playlist::load_state(grit_dir)
-        .ok()
-        .and_then(|s| s.last_track_index)
-        .unwrap_or(0)
-        .min(snap.tracks.len().saturating_sub(1));
+    let start_index = match start_at {
+        Some(index) if index >= snap.tracks.len() => bail!(
+            "'{}' has {} tracks; --start-at takes a position from 0 to {}",
+            snap.name,
+            snap.tracks.len(),
+            snap.tracks.len() - 1
+        ),
+        Some(index) => index,
+        // The saved position belongs to the full playlist
+        None if options.changes.is_some() => 0,
+        // Load last played track index
+        None => working_playlist::load_state(grit_dir)
+            .ok()
+            .and_then(|s| s.last_track_index)
+            .unwrap_or(0)
+            .min(snap.tracks.len().saturating_sub(1)),
+    };
 
     match snap.provider {
         ProviderKind::Spotify => {
-            play_spotify(&snap, shuffle, grit_dir, &snapshot_path, start_index).await
+            if options.loop_playlist {
+                bail!("--loop-playlist is only supported for YouTube playlists");
+            }
+            if options.detach {
+                return play_spotify_detached(&snap, options, grit_dir, start_index).await;
+            }
+            play_spotify(&snap, options, grit_dir, &snapshot_path, start_index).await
         }
         ProviderKind::Youtube => {
-            play_mpv(&snap, shuffle, grit_dir, &snapshot_path, start_index).await
+            if options.detach {
+                bail!("--detach is only supported for Spotify playlists (mpv needs the TUI to control it)");
+            }
+            if options.device.is_some() {
+                bail!("--device is only supported for Spotify playlists");
+            }
+            play_mpv(&snap, options, grit_dir, &snapshot_path, start_index).await
         }
     }
 }
 
+/// An ephemeral copy of `snap` holding only the tracks the chosen diff adds,
+/// in the order they would land
+async fn preview_snapshot(
+    snap: &PlaylistSnapshot,
+    playlist_id: &str,
+    source: ChangeSource,
+    grit_dir: &Path,
+) -> Result<PlaylistSnapshot> {
+    let (patch, label) = match source {
+        ChangeSource::Staged => (load_staged(grit_dir, playlist_id)?, "staged"),
+        ChangeSource::Remote => {
+            let provider = create_provider(snap.provider, grit_dir)?;
+            let remote =
+                fetch_remote(provider.as_ref(), snap, playlist_id, grit_dir, false).await?;
+            (diff(snap, &remote), "incoming")
+        }
+    };
+
+    let tracks: Vec<Track> = patch
+        .changes
+        .into_iter()
+        .filter_map(|change| match change {
+            TrackChange::Added { track, .. } => Some(track),
+            _ => None,
+        })
+        .collect();
+    if tracks.is_empty() {
+        bail!("No {} tracks to play for '{}'", label, snap.name);
+    }
+
+    Ok(PlaylistSnapshot {
+        name: format!("{} ({} {})", snap.name, tracks.len(), label),
+        tracks,
+        ..snap.clone()
+    })
+}
+
+/// `--volume` if given, else the backend's own volume from config, so a
+/// level picked for one backend doesn't carry over to the other
+fn start_volume(requested: Option<u8>, stored: Option<u8>) -> Option<u8> {
+    requested.or(stored.map(|v| v.min(100)))
+}
+
+/// Start Spotify playback and return without the TUI.
+async fn play_spotify_detached(
+    snap: &crate::provider::PlaylistSnapshot,
+    options: &PlayOptions<'_>,
+    grit_dir: &Path,
+    start_index: usize,
+) -> Result<()> {
+    let token = credentials::load(grit_dir, ProviderKind::Spotify)?
+        .context("No Spotify credentials. Run 'grit auth spotify' first.")?;
+
+    let (client_id, client_secret) =
+        crate::cli::commands::utils::client_credentials(ProviderKind::Spotify, grit_dir);
+
+    let mut player = SpotifyPlayer::new(token, client_id, client_secret, grit_dir);
+    let device_name = player.select_device(options.device).await?;
+    let stored_volume = config::load(grit_dir)?.playback.spotify_volume;
+    if let Some(volume) = start_volume(options.volume, stored_volume) {
+        player.set_volume(volume).await?;
+    }
+
+    let uris: Vec<String> = snap.tracks.iter().map(spotify::track_uri).collect();
+
+    player.play(uris, start_index).await?;
+    player.set_shuffle(options.shuffle).await?;
+    player
+        .set_repeat(crate::playback::events::RepeatMode::None)
+        .await?;
+
+    println!("Playing on {}: {}", device_name, snap.name);
+    Ok(())
+}
+
 async fn play_spotify(
     snap: &crate::provider::PlaylistSnapshot,
-    shuffle: bool,
+    options: &PlayOptions<'_>,
     grit_dir: &Path,
     snapshot_path: &Path,
     start_index: usize,
@@ -47,321 +218,693 @@ async fn play_spotify(
     let token = credentials::load(grit_dir, ProviderKind::Spotify)?
         .context("No Spotify credentials. Run 'grit auth spotify' first.")?;
 
-    let client_id = std::env::var("SPOTIFY_CLIENT_ID").context("SPOTIFY_CLIENT_ID not set")?;
-    let client_secret =
-        std::env::var("SPOTIFY_CLIENT_SECRET").context("SPOTIFY_CLIENT_SECRET not set")?;
+    let (client_id, client_secret) =
+        crate::cli::commands::utils::client_credentials(ProviderKind::Spotify, grit_dir);
+
+    let config = config::load(grit_dir)?;
+    let volume = start_volume(options.volume, config.playback.spotify_volume);
 
     let mut player = SpotifyPlayer::new(token, client_id, client_secret, grit_dir);
-    player.select_device().await?;
+    player.select_device(options.device).await?;
+    if let Some(volume) = volume {
+        player.set_volume(volume).await?;
+    }
 
-    let uris: Vec<String> = snap
-        .tracks
-        .iter()
-        .map(|t| format!("spotify:track:{}", t.id))
-        .collect();
+    let mut shuffle_mode = config.playback.spotify_shuffle;
+    // Only decides the order Spotify is given; Spotify tracks the position
+    let mut queue = Queue::new(snap.tracks.clone());
+    if options.shuffle && shuffle_mode == SpotifyShuffle::Grit {
+        queue.toggle_shuffle();
+    }
 
-    player.set_shuffle(shuffle).await?;
-    player.play(uris, start_index).await?;
+    player
+        .set_shuffle(options.shuffle && shuffle_mode == SpotifyShuffle::Native)
+        .await?;
+    spotify_play(&player, &queue, &snap.tracks, start_index).await?;
+    if options.paused {
+        player.pause().await?;
+    }
 
     let mut app = App::new(
         snap.name.clone(),
         snap.tracks.clone(),
         PlayerBackend::Spotify,
     );
-    app.shuffle = shuffle;
+    app.shuffle = options.shuffle;
+    app.is_paused = options.paused;
+    app.volume = volume;
     app.current_index = start_index;
     app.selected_index = start_index;
+    app.wrap_navigation = config.tui.wrap_navigation;
+    app.ascii_symbols = config.tui.ascii_symbols;
+    app.skip_explicit = config.playback.skip_explicit;
+    let on_end = config.playback.on_playlist_end;
+    let mut notifier = NowPlayingNotifier::new(config.playback.notifications);
+    let mut now_playing = NowPlayingFile::new(config.playback.now_playing_file.clone());
 
-    let mut tui = Tui::new()?;
-    let mut poll_counter = 0u8;
-    let mut last_update = std::time::Instant::now();
+    let mut tui = Tui::new(options.mini)?;
+    tui.set_refresh(Duration::from_millis(config.tui.refresh_ms));
+    tui.set_media(MediaControls::start().await);
+    let poll_interval = Duration::from_secs(config.playback.spotify_poll_secs);
+    let file_watch_interval = Duration::from_secs(config.playback.file_watch_secs);
+    let mut last_poll = Instant::now();
+    let mut last_file_check = Instant::now();
+    let mut last_update = Instant::now();
+    let mut resync_at: Option<Instant> = None;
+    // Pause state grit last asked Spotify for, and when
+    let mut pause_sent: Option<(bool, Instant)> = None;
     let mut last_modified = std::fs::metadata(snapshot_path)
         .and_then(|m| m.modified())
         .ok();
 
-    let mut lyrics_fetcher = LyricsFetcher::new();
+    let mut lyrics_fetcher = LyricsFetcher::new(lyrics::sources(&config.playback.lyrics_sources));
+    let mut refresh_queue = true;
+    // Track position an explicit-skip was last tried from, so a failed skip
+    // isn't retried every frame
+    let mut skipped_from: Option<usize> = None;
 
     loop {
         if let Some(lyrics) = lyrics_fetcher.try_recv() {
             app.lyrics = Some(lyrics);
             app.lyrics_loading = false;
+            tui.mark_dirty();
         }
 
-        tui.draw(&app)?;
-        poll_counter = poll_counter.wrapping_add(1);
+        notifier.update(app.current_track());
+        now_playing.update(app.current_track(), app.position_secs, app.is_paused);
+        tui.draw_if_needed(&app)?;
 
-        if !app.is_paused {
-            let now = std::time::Instant::now();
-            let elapsed = now.duration_since(last_update).as_secs_f64();
-            last_update = now;
-            app.position_secs = (app.position_secs + elapsed).min(app.duration_secs);
+        // Ticked while paused too, so resuming doesn't count the pause
+        let now = Instant::now();
+        app.tick(now.duration_since(last_update).as_secs_f64());
+        last_update = now;
 
-            let should_poll = poll_counter.is_multiple_of(30)
-                || (app.position_secs >= app.duration_secs && app.duration_secs > 0.0);
+        // Polled while paused too, so playback resumed from another client
+        // shows up. A seek or track change is checked shortly after instead
+        // of waiting for the next regular poll.
+        let resync_due = resync_at.is_some_and(|at| now >= at);
+        let should_poll = resync_due
+            || now.duration_since(last_poll) >= poll_interval
+            || (!app.is_paused
+                && app.position_secs >= app.duration_secs
+                && app.duration_secs > 0.0);
 
-            if should_poll {
-                use crate::playback::events::RepeatMode;
+        if should_poll {
+            last_poll = now;
+            resync_at = None;
+            use crate::playback::events::RepeatMode;
+            let (index_before, shuffle_before) = (app.current_index, app.shuffle);
 
-                if let Ok(Some((name, _))) = player.get_currently_playing().await {
-                    if app.current_track().map(|t| &t.name) != Some(&name) {
-                        if let Some(idx) = app.tracks.iter().position(|t| t.name == name) {
+            // A failed poll says nothing about the player, so it's left to
+            // the next one rather than read as playback having stopped
+            let polled = match player.get_playback_state().await {
+                Ok(state) => Some(state),
+                Err(e) => {
+                    tracing::warn!("Failed to read Spotify playback state: {}", e);
+                    None
+                }
+            };
+            if let Some(state) = polled {
+                // The end-of-playlist checks below only apply to playback grit
+                // saw running, not to a player paused all along
+                let was_playing = !app.is_paused;
+                let progress = state.as_ref().and_then(|s| s.progress_secs);
+                let duration = state.as_ref().and_then(|s| s.duration_secs);
+                if let Some(state) = &state {
+                    // Shuffle can be toggled from any Spotify client; the
+                    // up-next list is re-read below when it changes. Under
+                    // grit's shuffle Spotify's own setting stays off.
+                    if shuffle_mode == SpotifyShuffle::Native {
+                        app.shuffle = state.shuffle;
+                    }
+                    // Paused or resumed from the Spotify app, a hardware key or
+                    // another device
+                    if reported_pause_applies(&mut pause_sent, !state.is_playing, now) {
+                        app.is_paused = !state.is_playing;
+                    }
+                }
+                // Under Spotify's shuffle any track can be the last one, so
+                // Spotify stopping at the end of a track is the only sign
+                // the playlist ran out
+                let at_end = is_last_in_order(&queue, &app)
+                    || (app.shuffle
+                        && shuffle_mode == SpotifyShuffle::Native
+                        && app.position_secs >= app.duration_secs);
+                let ended = was_playing
+                    && at_end
+                    && app
+                        .current_track()
+                        .is_some_and(|last| playlist_ended(state.as_ref(), last));
+
+                if ended {
+                    let restart = if app.repeat_mode == RepeatMode::One {
+                        Some(app.current_index)
+                    } else if app.repeat_mode == RepeatMode::All || on_end == PlaylistEnd::Loop {
+                        Some(first_in_order(&queue))
+                    } else if on_end == PlaylistEnd::Quit {
+                        break;
+                    } else {
+                        // Stop: stay on the last track
+                        None
+                    };
+                    if let Some(index) = restart {
+                        let _ = spotify_play(&player, &queue, &app.tracks, index).await;
+                        app.start_track(index);
+                        app.is_paused = false;
+                        pause_sent = Some((false, now));
+                        lyrics_fetcher.reset();
+                        resync_at = Some(now + SPOTIFY_RESYNC_DELAY);
+                    }
+                } else if let Some(playing) = state.and_then(|s| s.track) {
+                    if let Some(idx) = app.position_of_playing(&playing) {
+                        if idx != app.current_index {
                             if app.repeat_mode == RepeatMode::One {
-                                let current_idx = app.current_index;
-                                let uris: Vec<String> = app
-                                    .tracks
-                                    .iter()
-                                    .map(|t| format!("spotify:track:{}", t.id))
-                                    .collect();
-                                let _ = player.play(uris, current_idx).await;
+                                let _ =
+                                    spotify_play(&player, &queue, &app.tracks, app.current_index)
+                                        .await;
                                 app.position_secs = 0.0;
+                                resync_at = Some(now + SPOTIFY_RESYNC_DELAY);
                             } else {
-                                app.current_index = idx;
-                                app.position_secs = 0.0;
-                                app.duration_secs = app.tracks[idx].duration_ms as f64 / 1000.0;
-                                // Clear lyrics for new track
-                                app.lyrics = None;
+                                app.start_track(idx);
+                                lyrics_fetcher.reset();
+                                sync_progress(&mut app, progress, duration);
                             }
+                        } else {
+                            sync_progress(&mut app, progress, duration);
                         }
                     }
-                } else if app.repeat_mode == RepeatMode::All
-                    && app.current_index == app.tracks.len() - 1
-                {
-                    let uris: Vec<String> = app
-                        .tracks
-                        .iter()
-                        .map(|t| format!("spotify:track:{}", t.id))
-                        .collect();
-                    let _ = player.play(uris, 0).await;
-                    app.current_index = 0;
-                    app.position_secs = 0.0;
-                    app.duration_secs = app.tracks[0].duration_ms as f64 / 1000.0;
                 }
             }
+
+            // Up next only moves while playing; a paused player keeps it
+            // until the track or shuffle changes
+            if !app.is_paused || app.current_index != index_before || app.shuffle != shuffle_before
+            {
+                refresh_queue = true;
+            }
+        }
+
+        if !app.explicit_to_skip() {
+            skipped_from = None;
+        } else if !app.is_paused && skipped_from != Some(app.current_index) {
+            skipped_from = Some(app.current_index);
+            match spotify_next(&player, &mut app).await {
+                Ok(()) => {
+                    lyrics_fetcher.reset();
+                    refresh_queue = true;
+                    resync_at = Some(Instant::now() + SPOTIFY_RESYNC_DELAY);
+                }
+                Err(e) => app.set_error(e.to_string()),
+            }
+        }
+
+        if refresh_queue {
+            refresh_queue = false;
+            // Endpoint unavailable: fall back to guessing from playlist order
+            app.remote_queue = player.get_queue().await.ok();
         }
 
-        if poll_counter.is_multiple_of(50) {
+        // A --changes preview isn't backed by the snapshot file
+        if options.changes.is_none() && last_file_check.elapsed() >= file_watch_interval {
+            last_file_check = Instant::now();
             let current_modified = std::fs::metadata(snapshot_path)
                 .and_then(|m| m.modified())
                 .ok();
             if current_modified != last_modified {
-                if let Ok(new_snap) = snapshot::load(snapshot_path) {
-                    app.tracks = new_snap.tracks;
+                if let Ok(mut new_snap) = snapshot::load(snapshot_path) {
+                    new_snap.drop_unavailable();
+                    queue = Queue::new(new_snap.tracks.clone());
+                    if app.shuffle && shuffle_mode == SpotifyShuffle::Grit {
+                        queue.toggle_shuffle();
+                    }
+                    app.replace_tracks(new_snap.tracks);
                     last_modified = current_modified;
+                    tui.mark_dirty();
                 }
             }
         }
 
-        if let Some(key) = tui.poll_key()? {
-            if app.is_searching() {
-                match (key.code, key.modifiers) {
-                    (KeyCode::Esc, _) => app.cancel_search(),
-                    (KeyCode::Enter, _) => {
-                        let idx = app.selected_index;
-                        app.cancel_search();
-                        if idx != app.current_index && idx < app.tracks.len() {
-                            let uris: Vec<String> = app
-                                .tracks
-                                .iter()
-                                .map(|t| format!("spotify:track:{}", t.id))
-                                .collect();
-                            if let Err(e) = player.play(uris, idx).await {
-                                app.set_error(e.to_string());
-                            } else {
-                                app.current_index = idx;
-                                app.position_secs = 0.0;
-                                app.duration_secs = app.tracks[idx].duration_ms as f64 / 1000.0;
-                                app.lyrics = None;
-                                app.reset_lyrics_scroll();
-                            }
-                        }
-                    }
-                    (KeyCode::Char('n'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        app.next_search_match()
-                    }
-                    (KeyCode::Char('p'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        app.prev_search_match()
-                    }
-                    (KeyCode::Up, _) => app.select_prev(),
-                    (KeyCode::Down, _) => app.select_next(),
-                    (KeyCode::Backspace, _) => app.pop_search_char(),
-                    (KeyCode::Char(c), _) => app.push_search_char(c),
-                    _ => {}
+        match tui.take_scrub() {
+            Some(Scrub::Move(ratio)) => app.scrub_to(ratio),
+            Some(Scrub::Release(ratio)) => {
+                // Only seek on release; the API is too slow to follow a drag
+                let secs = app.finish_scrub(ratio);
+                match player.seek(secs as u64).await {
+                    Ok(()) => resync_at = Some(Instant::now() + SPOTIFY_RESYNC_DELAY),
+                    Err(e) => app.set_error(e.to_string()),
                 }
-                continue;
             }
+            None => {}
+        }
 
-            if app.is_seeking() {
-                match key.code {
-                    KeyCode::Esc => app.cancel_seeking(),
-                    KeyCode::Enter => {
-                        if let Some(secs) = app.get_seek_position() {
-                            if let Err(e) = player.seek(secs as u64).await {
-                                app.set_error(e.to_string());
-                            } else {
-                                app.position_secs = secs;
-                            }
-                        }
-                        app.cancel_seeking();
-                    }
-                    KeyCode::Left => app.seek_backward(5.0),
-                    KeyCode::Right => app.seek_forward(5.0),
-                    _ => {}
+        let page_size = tui.playlist_page_size();
+        let action = match tui.poll_media(&app) {
+            Some(command) => app.media_action(command),
+            None => match tui.poll_key(&app)? {
+                Some(key) => app.handle_key(key, page_size),
+                None => None,
+            },
+        };
+        let action = action.and_then(|action| {
+            local_action(
+                action,
+                &mut app,
+                grit_dir,
+                snapshot_path,
+                &mut last_modified,
+            )
+        });
+        if matches!(
+            action,
+            Some(
+                Action::Pause(_)
+                    | Action::Seek(_)
+                    | Action::SeekBy(_)
+                    | Action::Play(_)
+                    | Action::Next
+                    | Action::Previous
+            )
+        ) {
+            resync_at = Some(Instant::now() + SPOTIFY_RESYNC_DELAY);
+        }
+        match action {
+            Some(Action::Quit) => break,
+            Some(Action::Pause(paused)) => {
+                let res = if paused {
+                    player.pause().await
+                } else {
+                    player.resume().await
+                };
+                match res {
+                    Ok(()) => pause_sent = Some((paused, Instant::now())),
+                    Err(e) => app.set_error(e.to_string()),
                 }
-                continue;
             }
+            Some(Action::Next) => {
+                use crate::playback::events::RepeatMode;
 
-            match key.code {
-                KeyCode::Char('/') if app.show_lyrics => {
-                    app.search_blocked = true;
-                }
-                _ => {
-                    app.search_blocked = false;
-                    app.clear_error();
-                }
-            }
-            match key.code {
-                KeyCode::Char('q') => break,
-                KeyCode::Char('/') => {
-                    if !app.show_lyrics {
-                        app.start_search();
-                    }
-                }
-                KeyCode::Char('g') => app.start_seeking(),
-                KeyCode::Char(' ') => {
-                    app.is_paused = !app.is_paused;
-                    let res = if app.is_paused {
-                        player.pause().await
-                    } else {
-                        player.resume().await
-                    };
-                    if let Err(e) = res {
-                        app.set_error(e.to_string());
-                    }
-                }
-                KeyCode::Char('n') => {
-                    if let Err(e) = player.next().await {
-                        app.set_error(e.to_string());
-                    } else {
-                        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-                        if let Ok(Some((name, _))) = player.get_currently_playing().await {
-                            if let Some(idx) = app.tracks.iter().position(|t| t.name == name) {
-                                app.current_index = idx;
-                                app.position_secs = 0.0;
-                                app.duration_secs = app.tracks[idx].duration_ms as f64 / 1000.0;
-                                app.lyrics = None;
-                                app.reset_lyrics_scroll();
-                            }
+                // Spotify's own next just stops at the end of the context
+                let at_end = is_last_in_order(&queue, &app)
+                    && !(app.shuffle && shuffle_mode == SpotifyShuffle::Native)
+                    && app.repeat_mode == RepeatMode::None;
+                let res = match on_end {
+                    PlaylistEnd::Quit if at_end => break,
+                    PlaylistEnd::Loop if at_end => {
+                        let first = first_in_order(&queue);
+                        let res = spotify_play(&player, &queue, &app.tracks, first).await;
+                        if res.is_ok() {
+                            app.start_track(first);
                         }
+                        res
                     }
-                }
-                KeyCode::Char('p') => {
-                    if let Err(e) = player.previous().await {
-                        app.set_error(e.to_string());
-                    } else {
-                        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-                        if let Ok(Some((name, _))) = player.get_currently_playing().await {
-                            if let Some(idx) = app.tracks.iter().position(|t| t.name == name) {
-                                app.current_index = idx;
-                                app.position_secs = 0.0;
-                                app.duration_secs = app.tracks[idx].duration_ms as f64 / 1000.0;
-                                app.lyrics = None;
-                                app.reset_lyrics_scroll();
-                            }
-                        }
+                    _ => spotify_next(&player, &mut app).await,
+                };
+                match res {
+                    Ok(()) => {
+                        lyrics_fetcher.reset();
+                        refresh_queue = true;
                     }
+                    Err(e) => app.set_error(e.to_string()),
                 }
-                KeyCode::Char('s') => {
-                    app.shuffle = !app.shuffle;
-                    if let Err(e) = player.set_shuffle(app.shuffle).await {
-                        app.set_error(e.to_string());
+            }
+            Some(Action::Previous) => {
+                if let Err(e) = player.previous().await {
+                    app.set_error(e.to_string());
+                } else {
+                    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+                    if let Ok(Some(playing)) = player.get_currently_playing().await {
+                        if let Some(idx) = app.position_of_playing(&playing) {
+                            app.start_track(idx);
+                            lyrics_fetcher.reset();
+                        }
                     }
+                    refresh_queue = true;
                 }
-                KeyCode::Char('r') => {
-                    app.cycle_repeat();
-                    if let Err(e) = player.set_repeat(app.repeat_mode).await {
-                        app.set_error(e.to_string());
+            }
+            Some(Action::Play(idx)) => {
+                match spotify_play(&player, &queue, &app.tracks, idx).await {
+                    Ok(()) => {
+                        app.start_track(idx);
+                        lyrics_fetcher.reset();
+                        refresh_queue = true;
                     }
+                    Err(e) => app.set_error(e.to_string()),
                 }
-                KeyCode::Char('l') => {
-                    app.toggle_lyrics();
-                }
-                KeyCode::Char('a') if app.show_lyrics => {
-                    app.lyrics_toggle_auto_scroll();
-                }
-                KeyCode::Left => {
-                    let new_pos = (app.position_secs - 5.0).max(0.0);
-                    if let Err(e) = player.seek(new_pos as u64).await {
-                        app.set_error(e.to_string());
-                    } else {
-                        app.position_secs = new_pos;
+            }
+            Some(Action::Seek(secs)) => match player.seek(secs as u64).await {
+                Ok(()) => app.position_secs = secs,
+                Err(e) => app.set_error(e.to_string()),
+            },
+            Some(Action::SeekBy(delta)) => {
+                let new_pos = (app.position_secs + delta).max(0.0);
+                if new_pos < app.duration_secs {
+                    match player.seek(new_pos as u64).await {
+                        Ok(()) => app.position_secs = new_pos,
+                        Err(e) => app.set_error(e.to_string()),
                     }
                 }
-                KeyCode::Right => {
-                    let new_pos = app.position_secs + 5.0;
-                    if new_pos < app.duration_secs {
-                        if let Err(e) = player.seek(new_pos as u64).await {
-                            app.set_error(e.to_string());
-                        } else {
-                            app.position_secs = new_pos;
-                        }
-                    }
+            }
+            Some(Action::Shuffle(_)) => {
+                if let Err(e) = sync_spotify_shuffle(&player, &mut queue, &app, shuffle_mode).await
+                {
+                    app.set_error(e.to_string());
                 }
-                KeyCode::Up => {
-                    if app.show_lyrics {
-                        app.lyrics_scroll_up();
-                    } else {
-                        app.select_prev();
+                refresh_queue = true;
+            }
+            Some(Action::SwitchShuffleMode) => {
+                shuffle_mode = match shuffle_mode {
+                    SpotifyShuffle::Native => SpotifyShuffle::Grit,
+                    SpotifyShuffle::Grit => SpotifyShuffle::Native,
+                };
+                match sync_spotify_shuffle(&player, &mut queue, &app, shuffle_mode).await {
+                    Ok(()) => {
+                        app.notice = Some(match shuffle_mode {
+                            SpotifyShuffle::Native => "shuffle: spotify".to_string(),
+                            SpotifyShuffle::Grit => "shuffle: grit".to_string(),
+                        })
                     }
+                    Err(e) => app.set_error(e.to_string()),
                 }
-                KeyCode::Down => {
-                    if app.show_lyrics {
-                        let max_lines = app.lyrics_line_count();
-                        app.lyrics_scroll_down(max_lines);
-                    } else {
-                        app.select_next();
-                    }
+                refresh_queue = true;
+            }
+            Some(Action::Repeat(mode)) => {
+                if let Err(e) = player.set_repeat(mode).await {
+                    app.set_error(e.to_string());
                 }
-                KeyCode::Enter => {
-                    let idx = app.selected_index;
-                    if idx != app.current_index && idx < app.tracks.len() {
-                        let uris: Vec<String> = app
-                            .tracks
-                            .iter()
-                            .map(|t| format!("spotify:track:{}", t.id))
-                            .collect();
-                        if let Err(e) = player.play(uris, idx).await {
-                            app.set_error(e.to_string());
-                        } else {
-                            app.current_index = idx;
-                            app.position_secs = 0.0;
-                            app.duration_secs = app.tracks[idx].duration_ms as f64 / 1000.0;
-                            app.lyrics = None;
-                        }
-                    }
+            }
+            Some(Action::FetchLyrics(artist, title)) => {
+                if let Some(track) = app.current_track() {
+                    lyrics_fetcher.fetch_manual(
+                        &track.id,
+                        &title,
+                        &artist,
+                        track.duration_ms / 1000,
+                    );
                 }
-                _ => {}
             }
+            // Explicit tracks are skipped above as they come up, and
+            // Spotify never loads
+            Some(_) | None => {}
         }
 
-        if app.show_lyrics && app.lyrics.is_none() && !app.lyrics_loading {
-            if let Some(track) = app.current_track() {
+        if let Some(track) = app.lyrics_wanted() {
+            let artist = track.artists.first().map(|s| s.as_str()).unwrap_or("");
+            let duration = track.duration_ms / 1000;
+            lyrics_fetcher.fetch_for_track(&track.id, &track.name, artist, duration);
+            app.lyrics_loading = true;
+        }
+
+        if app.show_lyrics && app.lyrics.is_some() {
+            for track in app.upcoming_tracks(LYRICS_PREFETCH) {
                 let artist = track.artists.first().map(|s| s.as_str()).unwrap_or("");
-                let duration = track.duration_ms / 1000;
-                lyrics_fetcher.fetch_for_track(&track.id, &track.name, artist, duration);
-                app.lyrics_loading = true;
+                lyrics_fetcher.prefetch_track(
+                    &track.id,
+                    &track.name,
+                    artist,
+                    track.duration_ms / 1000,
+                );
             }
         }
     }
 
     tui.restore()?;
+    now_playing.clear();
     let _ = player.pause().await;
-    let _ = working_playlist::save_last_track(grit_dir, app.current_index);
+    if options.changes.is_none() {
+        let _ = working_playlist::save_last_track(grit_dir, app.current_index);
+    }
+    Ok(())
+}
+
+/// Carry out the actions that only touch grit's own files, returning the
+/// rest for the backend
+fn local_action(
+    action: Action,
+    app: &mut App,
+    grit_dir: &Path,
+    snapshot_path: &Path,
+    last_modified: &mut Option<SystemTime>,
+) -> Option<Action> {
+    match action {
+        Action::ChooseCopyTarget => {
+            app.start_copy_target(copy_candidates(grit_dir, snapshot_path));
+        }
+        Action::StageCopy(target) => {
+            let track = app.current_track().cloned()?;
+            app.notice = Some(match stage_copy(&track, &target, grit_dir) {
+                Ok((name, _)) => format!("staged '{}' for {}", track.name, name),
+                Err(e) => format!("couldn't stage: {}", e),
+            });
+        }
+        Action::SaveMarker(marker) => {
+            let saved = app
+                .current_track()
+                .context("No track playing")
+                .and_then(|track| save_marker(snapshot_path, track, &marker));
+            app.notice = Some(match saved {
+                Ok(()) => format!(
+                    "marked '{}' at {}",
+                    marker.label,
+                    App::format_time(marker.secs)
+                ),
+                Err(e) => format!("couldn't save marker: {}", e),
+            });
+            // Our own write, no need to reload the playlist
+            *last_modified = std::fs::metadata(snapshot_path)
+                .and_then(|m| m.modified())
+                .ok();
+        }
+        action => return Some(action),
+    }
+    None
+}
+
+/// Persist a marker into the playlist snapshot so it's there next session
+fn save_marker(snapshot_path: &Path, track: &Track, marker: &Marker) -> Result<()> {
+    let mut snap = snapshot::load(snapshot_path)?;
+    snap.tracks
+        .iter_mut()
+        .find(|t| t.same_as(track))
+        .context("track isn't in the saved playlist")?
+        .add_marker(marker.clone());
+    snapshot::save(&snap, snapshot_path)
+}
+
+/// Tracked playlists other than the one playing, as (id, name), offered as
+/// destinations for `+`
+fn copy_candidates(grit_dir: &Path, playing: &Path) -> Vec<(String, String)> {
+    snapshot::tracked_playlist_ids(grit_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|id| {
+            let path = snapshot::snapshot_path(grit_dir, &id);
+            if path == playing {
+                return None;
+            }
+            let snap = snapshot::load(&path).ok()?;
+            Some((id, snap.name))
+        })
+        .collect()
+}
+
+/// Whether a polled pause state should replace grit's. A poll that hasn't
+/// caught up with a pause or resume grit just sent is ignored for a moment;
+/// once Spotify agrees, or the moment passes, polls are trusted again.
+fn reported_pause_applies(
+    sent: &mut Option<(bool, Instant)>,
+    reported_paused: bool,
+    now: Instant,
+) -> bool {
+    match *sent {
+        Some((paused, at)) if paused != reported_paused && now - at < PAUSE_SETTLE => false,
+        _ => {
+            *sent = None;
+            true
+        }
+    }
+}
+
+/// Progress within this much of either end of a track counts as that end
+const END_SLACK_SECS: f64 = 1.0;
+
+/// Whether a poll shows Spotify stopped after `last`, the playlist's last
+/// track. `None` is a 204: no device is active any more. Otherwise a natural
+/// end leaves `last` reported, paused, with its progress back at the start
+/// or at its full length.
+fn playlist_ended(state: Option<&PlaybackState>, last: &Track) -> bool {
+    let Some(state) = state else {
+        return true;
+    };
+    let progress = state.progress_secs.unwrap_or(0.0);
+    let at_edge = progress <= END_SLACK_SECS
+        || state
+            .duration_secs
+            .is_some_and(|secs| progress >= secs - END_SLACK_SECS);
+    !state.is_playing && state.track.as_ref().is_some_and(|t| t.is(last)) && at_edge
+}
+
+/// Take Spotify's position and track length over the local estimate
+fn sync_progress(app: &mut App, progress: Option<f64>, duration: Option<f64>) {
+    if let Some(secs) = duration.filter(|&secs| secs > 0.0) {
+        app.duration_secs = secs;
+    }
+    if let Some(secs) = progress {
+        app.position_secs = secs.min(app.duration_secs);
+    }
+}
+
+/// Start Spotify on track `index`, handing it the tracks in the queue's
+/// order: shuffled when grit manages shuffle, else playlist order
+async fn spotify_play(
+    player: &SpotifyPlayer,
+    queue: &Queue,
+    tracks: &[Track],
+    index: usize,
+) -> Result<()> {
+    let order = queue.play_order();
+    let uris = order
+        .iter()
+        .map(|&i| spotify::track_uri(&tracks[i]))
+        .collect();
+    let offset = order.iter().position(|&i| i == index).unwrap_or(0);
+    player.play(uris, offset).await
+}
+
+/// Track the queue's order starts with
+fn first_in_order(queue: &Queue) -> usize {
+    queue.play_order().first().copied().unwrap_or(0)
+}
+
+/// Whether the current track is the last one Spotify was handed
+fn is_last_in_order(queue: &Queue, app: &App) -> bool {
+    if queue.shuffle {
+        queue.play_order().last() == Some(&app.current_index)
+    } else {
+        app.is_last_track()
+    }
+}
+
+/// Bring Spotify's shuffle setting and grit's queue in line with
+/// `app.shuffle` under `mode`. When grit's order changes, the current track
+/// is restarted in place so Spotify picks up the new order.
+async fn sync_spotify_shuffle(
+    player: &SpotifyPlayer,
+    queue: &mut Queue,
+    app: &App,
+    mode: SpotifyShuffle,
+) -> Result<()> {
+    player
+        .set_shuffle(app.shuffle && mode == SpotifyShuffle::Native)
+        .await?;
+
+    let grit_shuffle = app.shuffle && mode == SpotifyShuffle::Grit;
+    if queue.shuffle != grit_shuffle {
+        queue.toggle_shuffle();
+        spotify_play(player, queue, &app.tracks, app.current_index).await?;
+        player.seek(app.position_secs as u64).await?;
+        if app.is_paused {
+            player.pause().await?;
+        }
+    }
     Ok(())
 }
 
+/// Skip to the next track on Spotify and move the app to wherever it landed
+async fn spotify_next(player: &SpotifyPlayer, app: &mut App) -> Result<()> {
+    player.next().await?;
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    if let Ok(Some(playing)) = player.get_currently_playing().await {
+        if let Some(idx) = app.position_of_playing(&playing) {
+            app.start_track(idx);
+        }
+    }
+    Ok(())
+}
+
+/// Move the app to `track` at playlist position `index` and start loading
+/// its audio. Load errors are shown in the player rather than returned.
+async fn load_track(
+    provider: &dyn Provider,
+    track: &Track,
+    index: usize,
+    app: &mut App,
+    tui: &mut Tui,
+    lyrics_fetcher: &mut LyricsFetcher,
+    pending: &mut Option<JoinHandle<Result<String>>>,
+) -> Result<()> {
+    app.loading = true;
+    app.start_track(index);
+    // `index` is only a best guess when the queue's track left the playlist,
+    // so the length comes from the track being loaded
+    app.duration_secs = track.duration_ms as f64 / 1000.0;
+    lyrics_fetcher.reset();
+    tui.draw(app)?;
+    if let Err(e) = start_load(provider, track, pending).await {
+        app.set_error(e.to_string());
+        app.loading = false;
+    }
+    Ok(())
+}
+
+/// Resolve the stream URL for `track` on a background task so the TUI keeps
+/// handling keys while yt-dlp runs. Replaces any resolution still in flight.
+async fn start_load(
+    provider: &dyn Provider,
+    track: &Track,
+    pending: &mut Option<JoinHandle<Result<String>>>,
+) -> Result<()> {
+    if let Some(handle) = pending.take() {
+        handle.abort();
+    }
+    let yt_url = provider.playable_url(track).await?;
+    *pending = Some(tokio::spawn(async move { fetch_audio_url(&yt_url).await }));
+    Ok(())
+}
+
+/// Restart the queue from the top for repeat-all. With `--loop-playlist`,
+/// first swaps in the remote playlist if tracks were added or removed.
+async fn wrap_queue(
+    queue: &mut Queue,
+    app: &mut App,
+    provider: &dyn Provider,
+    playlist_id: &str,
+    loop_playlist: bool,
+    last_refetch: &mut Option<Instant>,
+) -> Option<Track> {
+    let due = last_refetch.is_none_or(|at| at.elapsed() >= LOOP_REFETCH_INTERVAL);
+    if loop_playlist && due && !playlist_id.is_empty() {
+        *last_refetch = Some(Instant::now());
+        match provider.fetch(playlist_id).await {
+            Ok(remote) => {
+                let unchanged = remote.tracks.len() == app.tracks.len()
+                    && remote
+                        .tracks
+                        .iter()
+                        .zip(&app.tracks)
+                        .all(|(a, b)| a.same_as(b));
+                if !unchanged && !remote.tracks.is_empty() {
+                    app.replace_tracks(remote.tracks.clone());
+                    *queue = Queue::new(remote.tracks);
+                    queue.skip_explicit = app.skip_explicit;
+                    if app.shuffle {
+                        queue.toggle_shuffle();
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Failed to refresh playlist on wrap: {}", e),
+        }
+    }
+
+    queue.jump_to(0);
+    if queue.skips_current() {
+        return queue.next().cloned();
+    }
+    queue.current_track().cloned()
+}
+
 async fn play_mpv(
     snap: &crate::provider::PlaylistSnapshot,
-    shuffle: bool,
+    options: &PlayOptions<'_>,
     grit_dir: &Path,
     snapshot_path: &Path,
     start_index: usize,
@@ -370,62 +913,104 @@ async fn play_mpv(
 
     let provider = create_provider(snap.provider, grit_dir)?;
     let mut queue = Queue::new(snap.tracks.clone());
+    let mut last_refetch: Option<Instant> = None;
 
-    if shuffle {
+    if options.shuffle {
         queue.toggle_shuffle();
     }
 
     queue.jump_to(start_index);
 
+    let config = config::load(grit_dir)?;
+    let volume = start_volume(options.volume, config.playback.mpv_volume);
+
     let mut player = MpvPlayer::spawn().await?;
     player.observe_eof_reached().await?;
+    if let Some(volume) = volume {
+        player.set_volume(volume).await?;
+    }
 
     let mut app = App::new(snap.name.clone(), snap.tracks.clone(), PlayerBackend::Mpv);
-    app.shuffle = shuffle;
+    app.shuffle = options.shuffle;
+    app.is_paused = options.paused;
+    app.volume = volume;
     app.loading = true;
     app.current_index = start_index;
     app.selected_index = start_index;
+    app.wrap_navigation = config.tui.wrap_navigation;
+    app.ascii_symbols = config.tui.ascii_symbols;
+    app.skip_explicit = config.playback.skip_explicit;
+    queue.skip_explicit = app.skip_explicit;
+    let on_end = config.playback.on_playlist_end;
+    let mut notifier = NowPlayingNotifier::new(config.playback.notifications);
+    let mut now_playing = NowPlayingFile::new(config.playback.now_playing_file.clone());
     let mut skip_position = 0u8;
-    let mut last_seek = std::time::Instant::now();
+    let mut last_seek = Instant::now();
     let mut last_modified = std::fs::metadata(snapshot_path)
         .and_then(|m| m.modified())
         .ok();
-    let mut file_check_counter = 0u8;
+    let file_watch_interval = Duration::from_secs(config.playback.file_watch_secs);
+    let mut last_file_check = Instant::now();
 
-    let mut tui = Tui::new()?;
+    let mut tui = Tui::new(options.mini)?;
+    tui.set_refresh(Duration::from_millis(config.tui.refresh_ms));
+    tui.set_media(MediaControls::start().await);
     tui.draw(&app)?;
 
-    let mut lyrics_fetcher = LyricsFetcher::new();
+    let mut lyrics_fetcher = LyricsFetcher::new(lyrics::sources(&config.playback.lyrics_sources));
+
+    let mut pending: Option<JoinHandle<Result<String>>> = None;
+    // Track position and time to reload from after the audio device failed
+    let mut resume_from: Option<(usize, f64)> = None;
 
     if let Some(track) = queue.current_track().cloned() {
-        let yt_url = provider.playable_url(&track).await?;
-        match fetch_audio_url(&yt_url).await {
-            Ok(audio_url) => {
-                if let Err(e) = player.load(&audio_url).await {
-                    app.set_error(format!("Failed to load: {}", e));
-                }
-            }
-            Err(e) => {
-                app.set_error(format!("Failed to load: {}", e));
-            }
-        }
+        start_load(provider.as_ref(), &track, &mut pending).await?;
         app.duration_secs = track.duration_ms as f64 / 1000.0;
-        if let Some(idx) = app.tracks.iter().position(|t| t.id == track.id) {
+        if let Some(idx) = app.position_of(&track) {
             app.current_index = idx;
         }
-        skip_position = 5;
+    } else {
+        app.loading = false;
     }
-    app.loading = false;
 
     loop {
         if let Some(lyrics) = lyrics_fetcher.try_recv() {
             app.lyrics = Some(lyrics);
             app.lyrics_loading = false;
+            tui.mark_dirty();
+        }
+
+        if pending.as_ref().is_some_and(|h| h.is_finished()) {
+            if let Some(handle) = pending.take() {
+                match handle.await {
+                    Ok(Ok(audio_url)) => {
+                        while player.try_recv_event().is_some() {}
+                        let start_secs = resume_from
+                            .take()
+                            .filter(|&(idx, _)| idx == app.current_index)
+                            .map_or(0.0, |(_, secs)| secs);
+                        if let Err(e) = player.load_from(&audio_url, start_secs).await {
+                            app.set_error(format!("Failed to load: {}", e));
+                        } else if app.is_paused {
+                            // Started with --paused, or paused while loading
+                            let _ = player.pause().await;
+                        }
+                    }
+                    Ok(Err(e)) => app.set_error(format!("Failed to load: {}", e)),
+                    Err(_) => {} // aborted
+                }
+                app.loading = false;
+                skip_position = 5;
+            }
         }
 
-        tui.draw(&app)?;
+        if !app.loading {
+            notifier.update(app.current_track());
+            now_playing.update(app.current_track(), app.position_secs, app.is_paused);
+        }
+        tui.draw_if_needed(&app)?;
 
-        if !app.is_paused && skip_position == 0 {
+        if !app.is_paused && !app.loading && skip_position == 0 {
             if let Ok(Some(pos)) = player.get_position().await {
                 app.position_secs = pos.min(app.duration_secs);
             }
@@ -433,286 +1018,230 @@ async fn play_mpv(
             skip_position = skip_position.saturating_sub(1);
         }
 
-        file_check_counter = file_check_counter.wrapping_add(1);
-        if file_check_counter.is_multiple_of(100) {
+        // A --changes preview isn't backed by the snapshot file
+        if options.changes.is_none() && last_file_check.elapsed() >= file_watch_interval {
+            last_file_check = Instant::now();
             let current_modified = std::fs::metadata(snapshot_path)
                 .and_then(|m| m.modified())
                 .ok();
             if current_modified != last_modified {
-                if let Ok(new_snap) = snapshot::load(snapshot_path) {
-                    app.tracks = new_snap.tracks.clone();
+                if let Ok(mut new_snap) = snapshot::load(snapshot_path) {
+                    new_snap.drop_unavailable();
+                    app.replace_tracks(new_snap.tracks.clone());
+                    // Rebuilt unshuffled, so the track index is also its
+                    // queue position until shuffle is reapplied
                     queue = Queue::new(new_snap.tracks);
+                    queue.jump_to(app.current_index);
+                    queue.skip_explicit = app.skip_explicit;
+                    if app.shuffle {
+                        queue.toggle_shuffle();
+                    }
                     last_modified = current_modified;
+                    tui.mark_dirty();
                 }
             }
         }
 
-        if let Some(key) = tui.poll_key()? {
-            if app.is_searching() {
-                match (key.code, key.modifiers) {
-                    (KeyCode::Esc, _) => app.cancel_search(),
-                    (KeyCode::Enter, _) => {
-                        let idx = app.selected_index;
-                        app.cancel_search();
-                        if idx != app.current_index && idx < app.tracks.len() {
-                            if let Some(track) = app.tracks.get(idx).cloned() {
-                                app.loading = true;
-                                app.current_index = idx;
-                                app.position_secs = 0.0;
-                                app.duration_secs = track.duration_ms as f64 / 1000.0;
-                                app.lyrics = None;
-                                app.lyrics_loading = false;
-                                app.reset_lyrics_scroll();
-                                lyrics_fetcher.reset();
-                                queue.jump_to(idx);
-                                tui.draw(&app)?;
-                                match provider.playable_url(&track).await {
-                                    Ok(yt_url) => match fetch_audio_url(&yt_url).await {
-                                        Ok(audio_url) => {
-                                            while player.try_recv_event().is_some() {}
-                                            if let Err(e) = player.load(&audio_url).await {
-                                                app.set_error(e.to_string());
-                                            }
-                                        }
-                                        Err(e) => app.set_error(e.to_string()),
-                                    },
-                                    Err(e) => app.set_error(e.to_string()),
-                                }
-                                app.loading = false;
-                                skip_position = 5;
-                            }
-                        }
-                    }
-                    (KeyCode::Char('n'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        app.next_search_match()
-                    }
-                    (KeyCode::Char('p'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        app.prev_search_match()
-                    }
-                    (KeyCode::Up, _) => app.select_prev(),
-                    (KeyCode::Down, _) => app.select_next(),
-                    (KeyCode::Backspace, _) => app.pop_search_char(),
-                    (KeyCode::Char(c), _) => app.push_search_char(c),
-                    _ => {}
+        match tui.take_scrub() {
+            Some(Scrub::Move(ratio)) if !app.loading => {
+                app.scrub_to(ratio);
+                if let Some(secs) = app.get_seek_position() {
+                    let _ = player.seek_absolute(secs).await;
                 }
-                continue;
             }
-
-            if app.is_seeking() {
-                match key.code {
-                    KeyCode::Esc => app.cancel_seeking(),
-                    KeyCode::Enter => {
-                        if let Some(secs) = app.get_seek_position() {
-                            if let Err(e) = player.seek_absolute(secs).await {
-                                app.set_error(e.to_string());
-                            } else {
-                                app.position_secs = secs;
-                                skip_position = 3;
-                            }
-                        }
-                        app.cancel_seeking();
-                    }
-                    KeyCode::Left => app.seek_backward(5.0),
-                    KeyCode::Right => app.seek_forward(5.0),
-                    _ => {}
+            Some(Scrub::Release(ratio)) if !app.loading => {
+                let secs = app.finish_scrub(ratio);
+                if let Err(e) = player.seek_absolute(secs).await {
+                    app.set_error(e.to_string());
                 }
-                continue;
+                skip_position = 3;
             }
+            _ => {}
+        }
 
-            match key.code {
-                KeyCode::Char('/') if app.show_lyrics => {
-                    app.search_blocked = true;
-                }
-                _ => {
-                    app.search_blocked = false;
-                    app.clear_error();
+        let page_size = tui.playlist_page_size();
+        let action = match tui.poll_media(&app) {
+            Some(command) => app.media_action(command),
+            None => match tui.poll_key(&app)? {
+                Some(key) => app.handle_key(key, page_size),
+                None => None,
+            },
+        };
+        let action = action.and_then(|action| {
+            local_action(
+                action,
+                &mut app,
+                grit_dir,
+                snapshot_path,
+                &mut last_modified,
+            )
+        });
+        match action {
+            Some(Action::Quit) => break,
+            Some(Action::CancelLoad) => {
+                if let Some(handle) = pending.take() {
+                    handle.abort();
                 }
             }
-            match key.code {
-                KeyCode::Char('q') => break,
-                KeyCode::Char('/') => {
-                    if !app.show_lyrics {
-                        app.start_search();
-                    }
-                }
-                KeyCode::Char('g') => app.start_seeking(),
-                KeyCode::Char(' ') => {
-                    app.is_paused = !app.is_paused;
-                    let res = if app.is_paused {
-                        player.pause().await
-                    } else {
-                        player.resume().await
-                    };
-                    if let Err(e) = res {
+            Some(Action::Pause(false)) if resume_from.is_some() => {
+                // mpv dropped the file when the device failed; reload it
+                if let Some(track) = app.current_track().cloned() {
+                    app.loading = true;
+                    tui.draw(&app)?;
+                    if let Err(e) = start_load(provider.as_ref(), &track, &mut pending).await {
                         app.set_error(e.to_string());
-                    }
-                }
-                KeyCode::Char('n') => {
-                    use crate::playback::events::RepeatMode;
-
-                    let track = match queue.next() {
-                        Some(track) => Some(track.clone()),
-                        None if app.repeat_mode == RepeatMode::All => {
-                            queue.jump_to(0);
-                            queue.current_track().cloned()
-                        }
-                        None => None,
-                    };
-
-                    if let Some(track) = track {
-                        app.loading = true;
-                        if let Some(idx) = app.tracks.iter().position(|t| t.id == track.id) {
-                            app.current_index = idx;
-                        }
-                        app.position_secs = 0.0;
-                        app.duration_secs = track.duration_ms as f64 / 1000.0;
-                        app.lyrics = None;
-                        app.lyrics_loading = false;
-                        app.reset_lyrics_scroll();
-                        lyrics_fetcher.reset();
-                        tui.draw(&app)?;
-                        match provider.playable_url(&track).await {
-                            Ok(yt_url) => match fetch_audio_url(&yt_url).await {
-                                Ok(audio_url) => {
-                                    while player.try_recv_event().is_some() {}
-                                    if let Err(e) = player.load(&audio_url).await {
-                                        app.set_error(e.to_string());
-                                    }
-                                }
-                                Err(e) => app.set_error(e.to_string()),
-                            },
-                            Err(e) => app.set_error(e.to_string()),
-                        }
                         app.loading = false;
-                        skip_position = 5;
                     }
                 }
-                KeyCode::Char('p') => {
-                    if let Some(track) = queue.previous().cloned() {
-                        app.loading = true;
-                        if let Some(idx) = app.tracks.iter().position(|t| t.id == track.id) {
-                            app.current_index = idx;
-                        }
-                        app.position_secs = 0.0;
-                        app.duration_secs = track.duration_ms as f64 / 1000.0;
-                        app.lyrics = None;
-                        app.lyrics_loading = false;
-                        app.reset_lyrics_scroll();
-                        lyrics_fetcher.reset();
-                        tui.draw(&app)?;
-                        match provider.playable_url(&track).await {
-                            Ok(yt_url) => match fetch_audio_url(&yt_url).await {
-                                Ok(audio_url) => {
-                                    while player.try_recv_event().is_some() {}
-                                    if let Err(e) = player.load(&audio_url).await {
-                                        app.set_error(e.to_string());
-                                    }
-                                }
-                                Err(e) => app.set_error(e.to_string()),
-                            },
-                            Err(e) => app.set_error(e.to_string()),
-                        }
-                        app.loading = false;
-                        skip_position = 5;
+            }
+            Some(Action::Pause(paused)) => {
+                let res = if paused {
+                    player.pause().await
+                } else {
+                    player.resume().await
+                };
+                if let Err(e) = res {
+                    app.set_error(e.to_string());
+                }
+            }
+            Some(Action::Next) => {
+                use crate::playback::events::RepeatMode;
+
+                let track = match queue.next() {
+                    Some(track) => Some(track.clone()),
+                    None if app.repeat_mode == RepeatMode::All || on_end == PlaylistEnd::Loop => {
+                        wrap_queue(
+                            &mut queue,
+                            &mut app,
+                            provider.as_ref(),
+                            &snap.id,
+                            options.loop_playlist,
+                            &mut last_refetch,
+                        )
+                        .await
                     }
+                    None if on_end == PlaylistEnd::Quit => break,
+                    None => None,
+                };
+
+                if let Some(track) = track {
+                    let idx = app.position_of(&track).unwrap_or(app.current_index);
+                    load_track(
+                        provider.as_ref(),
+                        &track,
+                        idx,
+                        &mut app,
+                        &mut tui,
+                        &mut lyrics_fetcher,
+                        &mut pending,
+                    )
+                    .await?;
                 }
-                KeyCode::Char('s') => {
-                    queue.toggle_shuffle();
-                    app.shuffle = !app.shuffle;
+            }
+            Some(Action::Previous) => {
+                if let Some(track) = queue.previous().cloned() {
+                    let idx = app.position_of(&track).unwrap_or(app.current_index);
+                    load_track(
+                        provider.as_ref(),
+                        &track,
+                        idx,
+                        &mut app,
+                        &mut tui,
+                        &mut lyrics_fetcher,
+                        &mut pending,
+                    )
+                    .await?;
                 }
-                KeyCode::Char('r') => {
-                    app.cycle_repeat();
+            }
+            Some(Action::Play(idx)) => {
+                if let Some(track) = app.tracks.get(idx).cloned() {
+                    queue.jump_to(idx);
+                    load_track(
+                        provider.as_ref(),
+                        &track,
+                        idx,
+                        &mut app,
+                        &mut tui,
+                        &mut lyrics_fetcher,
+                        &mut pending,
+                    )
+                    .await?;
                 }
-                KeyCode::Left => {
-                    let now = std::time::Instant::now();
-                    if now.duration_since(last_seek).as_millis() >= 150 {
-                        if let Err(e) = player.seek(-5).await {
-                            app.set_error(e.to_string());
-                        } else {
-                            app.position_secs = (app.position_secs - 5.0).max(0.0);
-                            skip_position = 3;
-                            last_seek = now;
-                        }
-                    }
+            }
+            Some(Action::Seek(secs)) => match player.seek_absolute(secs).await {
+                Ok(()) => {
+                    app.position_secs = secs;
+                    skip_position = 3;
                 }
-                KeyCode::Right => {
-                    let now = std::time::Instant::now();
-                    if now.duration_since(last_seek).as_millis() >= 150 {
-                        if let Err(e) = player.seek(5).await {
-                            app.set_error(e.to_string());
-                        } else {
-                            app.position_secs = (app.position_secs + 5.0).min(app.duration_secs);
+                Err(e) => app.set_error(e.to_string()),
+            },
+            Some(Action::SeekBy(delta)) => {
+                // Held arrow keys repeat faster than mpv settles
+                let now = Instant::now();
+                if now.duration_since(last_seek).as_millis() >= 150 {
+                    match player.seek(delta as i64).await {
+                        Ok(()) => {
+                            app.position_secs =
+                                (app.position_secs + delta).clamp(0.0, app.duration_secs);
                             skip_position = 3;
                             last_seek = now;
                         }
+                        Err(e) => app.set_error(e.to_string()),
                     }
                 }
-                KeyCode::Char('l') => {
-                    app.toggle_lyrics();
-                }
-                KeyCode::Char('a') if app.show_lyrics => {
-                    app.lyrics_toggle_auto_scroll();
-                }
-                KeyCode::Up => {
-                    if app.show_lyrics {
-                        app.lyrics_scroll_up();
-                    } else {
-                        app.select_prev();
-                    }
-                }
-                KeyCode::Down => {
-                    if app.show_lyrics {
-                        let max_lines = app.lyrics_line_count();
-                        app.lyrics_scroll_down(max_lines);
-                    } else {
-                        app.select_next();
-                    }
+            }
+            Some(Action::Shuffle(on)) => {
+                if queue.shuffle != on {
+                    queue.toggle_shuffle();
                 }
-                KeyCode::Enter => {
-                    let idx = app.selected_index;
-                    if idx != app.current_index && idx < app.tracks.len() {
-                        if let Some(track) = app.tracks.get(idx).cloned() {
-                            app.loading = true;
-                            app.current_index = idx;
-                            app.position_secs = 0.0;
-                            app.duration_secs = track.duration_ms as f64 / 1000.0;
-                            app.lyrics = None;
-                            app.lyrics_loading = false;
-                            app.reset_lyrics_scroll();
-                            lyrics_fetcher.reset();
-                            queue.jump_to(idx);
-                            tui.draw(&app)?;
-                            match provider.playable_url(&track).await {
-                                Ok(yt_url) => match fetch_audio_url(&yt_url).await {
-                                    Ok(audio_url) => {
-                                        while player.try_recv_event().is_some() {}
-                                        if let Err(e) = player.load(&audio_url).await {
-                                            app.set_error(e.to_string());
-                                        }
-                                    }
-                                    Err(e) => app.set_error(e.to_string()),
-                                },
-                                Err(e) => app.set_error(e.to_string()),
-                            }
-                            app.loading = false;
-                            skip_position = 5;
-                        }
-                    }
+            }
+            Some(Action::SkipExplicit(skip)) => queue.skip_explicit = skip,
+            Some(Action::FetchLyrics(artist, title)) => {
+                if let Some(track) = app.current_track() {
+                    lyrics_fetcher.fetch_manual(
+                        &track.id,
+                        &title,
+                        &artist,
+                        track.duration_ms / 1000,
+                    );
                 }
-                _ => {}
             }
+            // Repeat is applied by this loop as tracks end, and mpv has no
+            // shuffle of its own to switch to
+            Some(_) | None => {}
         }
 
-        if app.show_lyrics && app.lyrics.is_none() && !app.lyrics_loading {
-            if let Some(track) = app.current_track() {
-                let duration = track.duration_ms / 1000;
-                lyrics_fetcher.fetch_for_yt(&track.id, &track.name, duration);
-                app.lyrics_loading = true;
+        if let Some(track) = app.lyrics_wanted() {
+            let duration = track.duration_ms / 1000;
+            lyrics_fetcher.fetch_for_yt(&track.id, &track.name, duration);
+            app.lyrics_loading = true;
+        }
+
+        if app.show_lyrics && app.lyrics.is_some() {
+            for track in queue.upcoming(LYRICS_PREFETCH) {
+                lyrics_fetcher.prefetch_yt(&track.id, &track.name, track.duration_ms / 1000);
             }
         }
 
+        let mut reached_end = false;
         while let Some(event) = player.try_recv_event() {
-            if MpvPlayer::is_track_finished(&event) {
+            if MpvPlayer::is_track_error(&event) && pending.is_none() {
+                let reason = event.file_error.as_deref().unwrap_or("unknown error");
+                if MpvPlayer::is_audio_device_error(&event) {
+                    // Pause instead of moving on, so the track isn't lost
+                    app.is_paused = true;
+                    resume_from = Some((app.current_index, app.position_secs));
+                    app.set_error(format!(
+                        "Audio device lost ({}). Paused; press space to resume.",
+                        reason
+                    ));
+                } else {
+                    app.set_error(format!("Playback failed: {}", reason));
+                }
+                continue;
+            }
+
+            if MpvPlayer::is_track_finished(&event) && pending.is_none() {
                 use crate::playback::events::RepeatMode;
 
                 let track = if app.repeat_mode == RepeatMode::One {
@@ -720,50 +1249,136 @@ async fn play_mpv(
                 } else {
                     match queue.next() {
                         Some(track) => Some(track.clone()),
-                        None if app.repeat_mode == RepeatMode::All => {
-                            queue.jump_to(0);
-                            queue.current_track().cloned()
+                        None if app.repeat_mode == RepeatMode::All
+                            || on_end == PlaylistEnd::Loop =>
+                        {
+                            wrap_queue(
+                                &mut queue,
+                                &mut app,
+                                provider.as_ref(),
+                                &snap.id,
+                                options.loop_playlist,
+                                &mut last_refetch,
+                            )
+                            .await
+                        }
+                        None => {
+                            reached_end = on_end == PlaylistEnd::Quit;
+                            None
                         }
-                        None => None,
                     }
                 };
 
                 if let Some(track) = track {
-                    app.loading = true;
-                    if let Some(idx) = app.tracks.iter().position(|t| t.id == track.id) {
-                        app.current_index = idx;
-                    }
-                    app.position_secs = 0.0;
-                    app.duration_secs = track.duration_ms as f64 / 1000.0;
-                    app.lyrics = None;
-                    app.lyrics_loading = false;
-                    app.reset_lyrics_scroll();
-                    lyrics_fetcher.reset();
-                    tui.draw(&app)?;
-
-                    if let Ok(yt_url) = provider.playable_url(&track).await {
-                        match fetch_audio_url(&yt_url).await {
-                            Ok(audio_url) => {
-                                while player.try_recv_event().is_some() {}
-                                if let Err(e) = player.load(&audio_url).await {
-                                    app.set_error(e.to_string());
-                                }
-                            }
-                            Err(e) => app.set_error(e.to_string()),
-                        }
-                    } else {
-                        app.set_error("Failed to get playable URL".to_string());
-                    }
-                    app.loading = false;
-                    skip_position = 5;
-                    tui.draw(&app)?;
+                    let idx = app.position_of(&track).unwrap_or(app.current_index);
+                    load_track(
+                        provider.as_ref(),
+                        &track,
+                        idx,
+                        &mut app,
+                        &mut tui,
+                        &mut lyrics_fetcher,
+                        &mut pending,
+                    )
+                    .await?;
                 }
             }
         }
+        if reached_end {
+            break;
+        }
     }
 
+    if let Some(handle) = pending.take() {
+        handle.abort();
+    }
     tui.restore()?;
+    now_playing.clear();
     player.quit().await?;
-    let _ = working_playlist::save_last_track(grit_dir, app.current_index);
+    if options.changes.is_none() {
+        let _ = working_playlist::save_last_track(grit_dir, app.current_index);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reported_pause_waits_for_our_own_request() {
+        let sent_at = Instant::now();
+        let mut sent = Some((true, sent_at));
+
+        // Spotify still says playing right after grit paused: lag
+        assert!(!reported_pause_applies(&mut sent, false, sent_at));
+        // It caught up, so later polls are trusted again
+        assert!(reported_pause_applies(&mut sent, true, sent_at));
+        assert!(sent.is_none());
+        assert!(reported_pause_applies(&mut sent, false, sent_at));
+
+        // Resumed elsewhere long after grit paused
+        let mut sent = Some((true, sent_at));
+        assert!(reported_pause_applies(
+            &mut sent,
+            false,
+            sent_at + PAUSE_SETTLE
+        ));
+    }
+
+    #[test]
+    fn test_sync_progress_clamps_to_the_track_length() {
+        let mut app = App::new(
+            "Test".to_string(),
+            vec![Track::test("a")],
+            PlayerBackend::Spotify,
+        );
+        app.duration_secs = 100.0;
+
+        sync_progress(&mut app, Some(150.0), None);
+        assert_eq!(app.position_secs, 100.0);
+
+        // A zero length is Spotify not knowing yet, not a real length
+        sync_progress(&mut app, Some(30.0), Some(0.0));
+        assert_eq!(app.duration_secs, 100.0);
+        assert_eq!(app.position_secs, 30.0);
+
+        // The new length applies before clamping
+        sync_progress(&mut app, Some(250.0), Some(240.0));
+        assert_eq!(app.duration_secs, 240.0);
+        assert_eq!(app.position_secs, 240.0);
+
+        sync_progress(&mut app, None, None);
+        assert_eq!(app.position_secs, 240.0);
+    }
+
+    #[test]
+    fn test_playlist_ended_only_when_stopped_on_the_last_track() {
+        let last = Track {
+            duration_ms: 200_000,
+            ..Track::test("last")
+        };
+        let state = |id: &str, progress: f64, is_playing: bool| PlaybackState {
+            track: Some(crate::playback::PlayingTrack {
+                id: Some(id.to_string()),
+                linked_from: None,
+                name: String::new(),
+            }),
+            shuffle: false,
+            progress_secs: Some(progress),
+            duration_secs: Some(200.0),
+            is_playing,
+        };
+
+        // No active device
+        assert!(playlist_ended(None, &last));
+        // Spotify stops on the last track, rewound or at its end
+        assert!(playlist_ended(Some(&state("last", 0.0, false)), &last));
+        assert!(playlist_ended(Some(&state("last", 200.0, false)), &last));
+
+        // Still playing, paused partway, or on another track
+        assert!(!playlist_ended(Some(&state("last", 200.0, true)), &last));
+        assert!(!playlist_ended(Some(&state("last", 90.0, false)), &last));
+        assert!(!playlist_ended(Some(&state("other", 0.0, false)), &last));
+    }
+}
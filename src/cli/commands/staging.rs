@@ -1,16 +1,29 @@
 use anyhow::{bail, Context, Ok, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
 
 use crate::{
-    cli::commands::utils::create_provider,
-    provider::{ProviderKind, TrackChange},
+    cli::commands::utils::{change_counts, create_provider, fetch_remote},
+    import::{self, ImportRow},
+    playback::{fetch_audio_url, MpvPlayer},
+    provider::{DiffPatch, PlaylistSnapshot, Provider, ProviderKind, Track, TrackChange},
     state::{
-        apply_patch, clear_staged, load_staged, snapshot, stage_change, JournalEntry, Operation,
+        apply_patch, clear_staged, load_staged, remote_cache, reorder_patch, save_staged, snapshot,
+        stage_change, JournalEntry, Operation,
     },
+    utils::{color, editor},
 };
 
-pub async fn status(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
+/// Show staged changes and how local compares to the remote. The remote
+/// comparison is best-effort unless `strict`, so status works offline.
+pub async fn status(
+    playlist: Option<&str>,
+    refresh: bool,
+    strict: bool,
+    grit_dir: &Path,
+) -> Result<()> {
     let playlist_id = playlist.context("Playlist required (use --playlist)")?;
 
     let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
@@ -21,10 +34,23 @@ pub async fn status(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
     let local_snapshot = snapshot::load(&snapshot_path)?;
     let staged_patch = load_staged(grit_dir, playlist_id)?;
 
+    if let Some(depth) = local_snapshot.depth() {
+        println!(
+            "Warning: only the first {} tracks are tracked (grit init --depth); \
+             the rest of the playlist is ignored and push is disabled.",
+            depth
+        );
+    }
+
     // Display staged changes
     println!("\n[Staged Changes]");
     if staged_patch.changes.is_empty() {
         println!("  No staged changes");
+        if local_snapshot.tracks.is_empty() {
+            println!(
+                "  This playlist has no tracks. Use 'grit search' and 'grit add' to add some."
+            );
+        }
     } else {
         let mut added = 0;
         let mut removed = 0;
@@ -35,44 +61,64 @@ pub async fn status(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
                 crate::provider::TrackChange::Added { track, index } => {
                     added += 1;
                     println!(
-                        "  + [{}] {} - {}",
-                        index,
-                        track.name,
-                        track.artists.join(", ")
+                        "  {}",
+                        color::green(&format!(
+                            "+ [{}] {} - {}",
+                            index,
+                            track.name,
+                            track.artists.join(", ")
+                        ))
                     );
                 }
                 crate::provider::TrackChange::Removed { track, index } => {
                     removed += 1;
                     println!(
-                        "  - [{}] {} - {}",
-                        index,
-                        track.name,
-                        track.artists.join(", ")
+                        "  {}",
+                        color::red(&format!(
+                            "- [{}] {} - {}",
+                            index,
+                            track.name,
+                            track.artists.join(", ")
+                        ))
                     );
                 }
                 crate::provider::TrackChange::Moved { track, from, to } => {
                     moved += 1;
                     println!(
-                        "  ~ {} - {} (from {} to {})",
-                        track.name,
-                        track.artists.join(", "),
-                        from,
-                        to
+                        "  {}",
+                        color::yellow(&format!(
+                            "~ {} - {} (from {} to {})",
+                            track.name,
+                            track.artists.join(", "),
+                            from,
+                            to
+                        ))
                     );
                 }
             }
         }
 
-        println!("\n  Summary: +{} -{} ~{}", added, removed, moved);
+        println!("\n  Summary: {}", change_counts(added, removed, moved));
         println!("\nUse 'grit commit -m \"message\"' to commit these changes");
         println!("Use 'grit reset' to discard staged changes");
     }
 
     // Compare local vs remote
     println!("\n[Local vs Remote]");
-    let provider = create_provider(local_snapshot.provider, grit_dir)?;
+    let remote = async {
+        let provider = create_provider(local_snapshot.provider, grit_dir)?;
+        fetch_remote(
+            provider.as_ref(),
+            &local_snapshot,
+            playlist_id,
+            grit_dir,
+            refresh,
+        )
+        .await
+    }
+    .await;
 
-    match provider.fetch(playlist_id).await {
+    match remote {
         std::result::Result::Ok(remote_snapshot) => {
             use crate::state::diff;
             let local_vs_remote = diff(&remote_snapshot, &local_snapshot);
@@ -93,15 +139,14 @@ pub async fn status(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
                 }
 
                 println!(
-                    "  Your local branch is ahead by {} change(s): +{} -{} ~{}",
+                    "  Your local branch is ahead by {} change(s): {}",
                     local_vs_remote.changes.len(),
-                    added,
-                    removed,
-                    moved
+                    change_counts(added, removed, moved)
                 );
                 println!("\n  Use 'grit push' to sync with remote");
             }
         }
+        Err(e) if strict => return Err(e.context("Could not fetch remote")),
         Err(e) => {
             println!("  Could not fetch remote: {}", e);
             println!("  (Local changes can still be committed)");
@@ -113,17 +158,42 @@ pub async fn status(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// How `grit search` fetches and shows results
+pub struct SearchOptions {
+    /// Prompt to add results by number
+    pub add: bool,
+    /// Result number to preview before listing
+    pub preview: Option<usize>,
+    pub limit: usize,
+    pub show_ids: bool,
+}
+
 pub async fn search(
     query: &str,
     provider: Option<ProviderKind>,
-    grit_dir: &Path,
-    add_mode: bool,
+    options: &SearchOptions,
     playlist: Option<&str>,
+    grit_dir: &Path,
 ) -> Result<()> {
-    let provider_kind = provider.context("Provider required for search (use --provider)")?;
+    let add_mode = options.add;
+    // Without --provider, search where the target playlist lives
+    let provider_kind = match provider {
+        Some(kind) => kind,
+        None => {
+            let pid =
+                playlist.context("Provider required for search (use --provider or --playlist)")?;
+            let snapshot_path = snapshot::snapshot_path(grit_dir, pid);
+            if !snapshot_path.exists() {
+                bail!("Playlist not initialized. Run 'grit init' first.");
+            }
+            snapshot::load(&snapshot_path)?.provider
+        }
+    };
     let provider_instance = create_provider(provider_kind, grit_dir)?;
 
-    let tracks = provider_instance.search_by_query(query).await?;
+    let tracks = provider_instance
+        .search_by_query(query, options.limit)
+        .await?;
 
     if tracks.is_empty() {
         println!("No tracks found for '{}'", query);
@@ -145,12 +215,21 @@ pub async fn search(
 
     let mut total_added = 0;
 
+    if let Some(n) = options.preview {
+        let track = n
+            .checked_sub(1)
+            .and_then(|i| tracks.get(i))
+            .with_context(|| format!("No search result #{} ({} found)", n, tracks.len()))?;
+        preview_track(provider_instance.as_ref(), track).await?;
+    }
+
     println!("\nSearch results for '{}':\n", query);
 
     const PAGE_SIZE: usize = 5;
     let mut start = 0;
 
     loop {
+        let page_start = start;
         let end = (start + PAGE_SIZE).min(tracks.len());
         let page_tracks = &tracks[start..end];
 
@@ -161,7 +240,11 @@ pub async fn search(
             let sec = duration_sec % 60;
 
             println!("{}. {} - {}", start + i + 1, track.name, artists);
-            println!("   ID: {} | Duration: {}:{:02}", track.id, min, sec);
+            if options.show_ids {
+                println!("   ID: {} | Duration: {}:{:02}", track.id, min, sec);
+            } else {
+                println!("   Duration: {}:{:02}", min, sec);
+            }
             println!();
         }
 
@@ -171,13 +254,18 @@ pub async fn search(
         // Prompt based on mode
         if add_mode {
             if has_more {
-                print!("Add [1,2,..] / 'm' more / 'q' quit: ");
+                print!("Add [1,2,..] / 'p N' preview / 'm' more / 'q' quit: ");
             } else {
-                print!("Add tracks [1,2,3...] or 'q' to quit: ");
+                print!("Add tracks [1,2,3...], 'p N' to preview or 'q' to quit: ");
             }
         } else if has_more {
             print!("Show more? [Enter] or 'q' to quit: ");
         } else {
+            if !options.show_ids {
+                println!(
+                    "Use 'grit search --add' to add results by number, or --ids to show track ids"
+                );
+            }
             break;
         }
         io::stdout().flush()?;
@@ -190,6 +278,24 @@ pub async fn search(
             break;
         }
 
+        // "p N" previews a result, then shows the same page again
+        if let Some(n) = input
+            .strip_prefix(['p', 'P'])
+            .and_then(|n| n.trim().parse::<usize>().ok())
+        {
+            match n.checked_sub(1).and_then(|i| tracks.get(i)) {
+                Some(track) => {
+                    if let Err(e) = preview_track(provider_instance.as_ref(), track).await {
+                        println!("  Preview failed: {}", e);
+                    }
+                }
+                None => println!("  Invalid index: {}", n),
+            }
+            start = page_start;
+            println!();
+            continue;
+        }
+
         // Handle "show more" - 'm' in add mode, Enter in normal mode
         if add_mode {
             if input.eq_ignore_ascii_case("m") && has_more {
@@ -270,7 +376,89 @@ pub async fn search(
     Ok(())
 }
 
-pub async fn add(track_id: &str, playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
+const PREVIEW_SECS: u64 = 30;
+
+/// Play the first 30 seconds of a search result through mpv.
+async fn preview_track(provider: &dyn Provider, track: &Track) -> Result<()> {
+    let url = match track.provider {
+        ProviderKind::Youtube => fetch_audio_url(&provider.playable_url(track).await?).await?,
+        ProviderKind::Spotify => track
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("preview_url"))
+            .and_then(|u| u.as_str())
+            .map(str::to_string)
+            .context("Spotify has no preview for this track")?,
+    };
+
+    let mut player = MpvPlayer::spawn().await?;
+    player.load(&url).await?;
+
+    println!(
+        "\nPreviewing: {} - {} ({}s, ctrl+c to stop)",
+        track.name,
+        track.artists.join(", "),
+        PREVIEW_SECS
+    );
+
+    tokio::select! {
+        _ = tokio::time::sleep(std::time::Duration::from_secs(PREVIEW_SECS)) => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+
+    let _ = player.quit().await;
+    Ok(())
+}
+
+/// Where `grit add` inserts tracks
+pub enum AddPosition<'a> {
+    End,
+    /// Right after the track with this id
+    After(&'a str),
+    /// Right before the track with this id
+    Before(&'a str),
+}
+
+/// Index in `tracks` the first added track goes to
+fn insert_index(tracks: &[Track], position: &AddPosition) -> Result<usize> {
+    let anchor_index = |anchor: &str| {
+        tracks
+            .iter()
+            .position(|t| t.id == anchor)
+            .with_context(|| format!("Track {} is not in the playlist", anchor))
+    };
+    match position {
+        AddPosition::End => Ok(tracks.len()),
+        AddPosition::After(anchor) => Ok(anchor_index(anchor)? + 1),
+        AddPosition::Before(anchor) => anchor_index(anchor),
+    }
+}
+
+/// `snapshot` with its staged additions and removals applied. Positions for
+/// new additions count those, as they are applied first; moves are left out.
+fn pending_snapshot(
+    snapshot: &PlaylistSnapshot,
+    playlist_id: &str,
+    grit_dir: &Path,
+) -> Result<PlaylistSnapshot> {
+    let mut pending = snapshot.clone();
+    let staged = load_staged(grit_dir, playlist_id)?;
+    let changes = staged
+        .changes
+        .into_iter()
+        .filter(|c| !matches!(c, TrackChange::Moved { .. }))
+        .collect();
+    apply_patch(&mut pending, &DiffPatch { changes })?;
+    Ok(pending)
+}
+
+/// Stage tracks for addition, in the order given, at `position`
+pub async fn add(
+    track_ids: &[String],
+    position: AddPosition<'_>,
+    playlist: Option<&str>,
+    grit_dir: &Path,
+) -> Result<()> {
     let playlist_id = playlist.context("Playlist required (use --playlist)")?;
 
     let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
@@ -279,12 +467,14 @@ pub async fn add(track_id: &str, playlist: Option<&str>, grit_dir: &Path) -> Res
     }
 
     let snapshot = snapshot::load(&snapshot_path)?;
-    let provider = create_provider(snapshot.provider, grit_dir)?;
+    let pending = pending_snapshot(&snapshot, playlist_id, grit_dir)?;
+    let start = insert_index(&pending.tracks, &position)?;
 
-    let track = provider.fetch_track(track_id).await?;
+    let provider = create_provider(snapshot.provider, grit_dir)?;
+    let tracks = provider.fetch_tracks(track_ids).await?;
 
     // Validate provider match
-    if track.provider != snapshot.provider {
+    if let Some(track) = tracks.iter().find(|t| t.provider != snapshot.provider) {
         bail!(
             "Cannot add {:?} track to {:?} playlist. Provider mismatch.",
             track.provider,
@@ -292,27 +482,164 @@ pub async fn add(track_id: &str, playlist: Option<&str>, grit_dir: &Path) -> Res
         );
     }
 
-    let index = snapshot.tracks.len();
+    for (offset, track) in tracks.into_iter().enumerate() {
+        let index = start + offset;
+        println!(
+            "Staged for addition: {} - {}",
+            track.name,
+            track.artists.join(", ")
+        );
+        println!("  Position: {}", index);
 
-    let change = TrackChange::Added {
-        track: track.clone(),
-        index,
-    };
+        stage_change(grit_dir, playlist_id, TrackChange::Added { track, index })?;
+    }
 
-    stage_change(grit_dir, playlist_id, change)?;
+    println!("\nUse 'grit status' to see all staged changes");
+    println!("Use 'grit commit -m \"message\"' to commit");
+
+    Ok(())
+}
+
+/// Stage every track from another service's playlist export. Rows with an id
+/// on the playlist's provider are fetched directly; the rest are searched for
+/// by title and artist.
+pub async fn import(path: &Path, playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
+    let playlist_id = playlist.context("Playlist required (use --playlist)")?;
+
+    let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
+    if !snapshot_path.exists() {
+        bail!("Playlist not initialized. Run 'grit init' first.");
+    }
 
+    let snapshot = snapshot::load(&snapshot_path)?;
+    let provider = create_provider(snapshot.provider, grit_dir)?;
+
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read export {:?}", path))?;
+    let export = import::parse(&content)?;
     println!(
-        "Staged for addition: {} - {}",
-        track.name,
-        track.artists.join(", ")
+        "Importing {} row(s) from {} export\n",
+        export.rows.len(),
+        export.format
     );
-    println!("  Position: {}", index);
-    println!("\nUse 'grit status' to see all staged changes");
-    println!("Use 'grit commit -m \"message\"' to commit");
+
+    let ids: Vec<String> = export
+        .rows
+        .iter()
+        .filter_map(|row| row.track.as_ref())
+        .filter(|(kind, _)| *kind == snapshot.provider)
+        .map(|(_, id)| id.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let by_id = fetch_import_tracks(provider.as_ref(), &ids).await;
+
+    // Imported tracks go after anything already staged for addition
+    let pending = pending_snapshot(&snapshot, playlist_id, grit_dir)?;
+    let start = insert_index(&pending.tracks, &AddPosition::End)?;
+
+    let mut seen: HashSet<String> = pending.tracks.iter().map(|t| t.id.clone()).collect();
+    let mut unresolved: Vec<(&ImportRow, String)> = Vec::new();
+    let mut skipped = 0;
+    let mut added = 0;
+
+    for row in &export.rows {
+        let direct = row
+            .track
+            .as_ref()
+            .filter(|(kind, _)| *kind == snapshot.provider);
+
+        let track = match (direct, row.query()) {
+            (Some((_, id)), _) => match by_id.get(id) {
+                Some(track) => track.clone(),
+                None => {
+                    unresolved.push((row, "not found".to_string()));
+                    continue;
+                }
+            },
+            (None, Some(query)) => match provider.search_by_query(&query, 1).await {
+                std::result::Result::Ok(results) => match results.into_iter().next() {
+                    Some(track) => track,
+                    None => {
+                        unresolved.push((row, "no search match".to_string()));
+                        continue;
+                    }
+                },
+                Err(e) => {
+                    unresolved.push((row, format!("search failed: {:#}", e)));
+                    continue;
+                }
+            },
+            (None, None) => {
+                unresolved.push((row, "no title to search for".to_string()));
+                continue;
+            }
+        };
+
+        if !seen.insert(track.id.clone()) {
+            skipped += 1;
+            continue;
+        }
+
+        let change = TrackChange::Added {
+            track: track.clone(),
+            index: start + added,
+        };
+        stage_change(grit_dir, playlist_id, change)?;
+
+        if direct.is_some() {
+            println!("  Staged: {} - {}", track.name, track.artists.join(", "));
+        } else {
+            println!(
+                "  Staged: {} - {} (matched \"{}\")",
+                track.name,
+                track.artists.join(", "),
+                row.label()
+            );
+        }
+        added += 1;
+    }
+
+    println!("\n{} track(s) staged for addition", added);
+    if skipped > 0 {
+        println!("{} already in the playlist or repeated", skipped);
+    }
+    if !unresolved.is_empty() {
+        println!(
+            "\n{}",
+            color::yellow(&format!(
+                "{} row(s) could not be resolved:",
+                unresolved.len()
+            ))
+        );
+        for (row, reason) in &unresolved {
+            println!("  line {}: {} ({})", row.line, row.label(), reason);
+        }
+    }
+    if added > 0 {
+        println!("\nUse 'grit status' to see all staged changes");
+        println!("Use 'grit commit -m \"message\"' to commit");
+    }
 
     Ok(())
 }
 
+/// Fetch imported ids, falling back to one request per track when a batch
+/// fails so a single dead id doesn't take the rest down with it
+async fn fetch_import_tracks(provider: &dyn Provider, ids: &[String]) -> HashMap<String, Track> {
+    if let std::result::Result::Ok(tracks) = provider.fetch_tracks(ids).await {
+        return ids.iter().cloned().zip(tracks).collect();
+    }
+
+    let mut found = HashMap::new();
+    for id in ids {
+        if let std::result::Result::Ok(track) = provider.fetch_track(id).await {
+            found.insert(id.clone(), track);
+        }
+    }
+    found
+}
+
 pub async fn remove(track_id: &str, playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
     let playlist_id = playlist.context("Playlist required (use --playlist)")?;
 
@@ -352,6 +679,7 @@ pub async fn remove(track_id: &str, playlist: Option<&str>, grit_dir: &Path) ->
 pub async fn move_track(
     track_id: &str,
     new_index: usize,
+    preview: bool,
     playlist: Option<&str>,
     grit_dir: &Path,
 ) -> Result<()> {
@@ -389,6 +717,30 @@ pub async fn move_track(
         to: new_index,
     };
 
+    if preview {
+        let mut moved = snapshot.clone();
+        apply_patch(
+            &mut moved,
+            &DiffPatch {
+                changes: vec![change],
+            },
+        )?;
+
+        println!(
+            "Preview: move {} - {} from {} to {}\n",
+            track.name,
+            track.artists.join(", "),
+            from_index,
+            new_index
+        );
+        println!("Before:");
+        print_move_window(&snapshot.tracks, from_index);
+        println!("\nAfter:");
+        print_move_window(&moved.tracks, new_index);
+        println!("\nNothing staged. Run without --preview to stage the move.");
+        return Ok(());
+    }
+
     stage_change(grit_dir, playlist_id, change)?;
 
     println!("Staged move: {} - {}", track.name, track.artists.join(", "));
@@ -399,6 +751,222 @@ pub async fn move_track(
     Ok(())
 }
 
+/// Print the tracks around `index`, highlighting the one at `index`
+fn print_move_window(tracks: &[Track], index: usize) {
+    const CONTEXT: usize = 2;
+
+    let start = index.saturating_sub(CONTEXT);
+    let end = (index + CONTEXT + 1).min(tracks.len());
+    for (i, track) in tracks.iter().enumerate().take(end).skip(start) {
+        let line = format!("{}. {} - {}", i, track.name, track.artists.join(", "));
+        if i == index {
+            println!("  → {}", color::yellow(&line));
+        } else {
+            println!("    {}", line);
+        }
+    }
+}
+
+const REORDER_HELP: &str = "\
+# Reorder the lines to reorder the playlist; delete a line to remove that track.
+# Only the id (second column) is read. Lines starting with '#' are ignored,
+# and saving an empty list aborts.
+";
+
+/// Stage a whole new track order by editing the track list in `$EDITOR`,
+/// like `git rebase -i`
+pub async fn reorder(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
+    let playlist_id = playlist.context("Playlist required (use --playlist)")?;
+
+    let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
+    if !snapshot_path.exists() {
+        bail!("Playlist not initialized. Run 'grit init' first.");
+    }
+
+    // Indices in the edited list are relative to the committed snapshot
+    if !load_staged(grit_dir, playlist_id)?.changes.is_empty() {
+        bail!("Playlist has staged changes. Commit them or run 'grit reset' first.");
+    }
+
+    let snapshot = snapshot::load(&snapshot_path)?;
+
+    let mut todo = String::from(REORDER_HELP);
+    for (i, track) in snapshot.tracks.iter().enumerate() {
+        todo.push_str(&format!(
+            "{} {} {} - {}\n",
+            i,
+            track.id,
+            track.name,
+            track.artists.join(", ")
+        ));
+    }
+
+    let todo_path = snapshot::playlist_dir(grit_dir, playlist_id).join("REORDER");
+    fs::write(&todo_path, todo).with_context(|| format!("Failed to write {:?}", todo_path))?;
+    let edited = editor::edit_file(&todo_path).and_then(|_| {
+        fs::read_to_string(&todo_path).with_context(|| format!("Failed to read {:?}", todo_path))
+    });
+    let _ = fs::remove_file(&todo_path);
+    let edited = edited?;
+
+    let order = parse_reorder(&edited, &snapshot)?;
+    if order.is_empty() {
+        println!("Empty track list, nothing staged.");
+        return Ok(());
+    }
+
+    let patch = reorder_patch(&snapshot, &order);
+    if patch.changes.is_empty() {
+        println!("Track order unchanged, nothing staged.");
+        return Ok(());
+    }
+
+    let removed = patch
+        .changes
+        .iter()
+        .filter(|c| matches!(c, TrackChange::Removed { .. }))
+        .count();
+    let moved = patch.changes.len() - removed;
+    save_staged(grit_dir, playlist_id, &patch)?;
+
+    println!("Staged reorder: {}", change_counts(0, removed, moved));
+    println!("\nUse 'grit status' to see all staged changes");
+    println!("Use 'grit commit -m \"message\"' to commit");
+
+    Ok(())
+}
+
+/// Track ids from an edited reorder list, in their new order. Every id must
+/// name a track in `snapshot` and appear at most once.
+fn parse_reorder<'a>(content: &'a str, snapshot: &PlaylistSnapshot) -> Result<Vec<&'a str>> {
+    let known: HashSet<&str> = snapshot.tracks.iter().map(|t| t.id.as_str()).collect();
+    let mut seen = HashSet::new();
+    let mut unknown = Vec::new();
+    let mut duplicated = Vec::new();
+    let mut order = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let first = fields.next().unwrap_or_default();
+        let id = fields.next().unwrap_or(first);
+
+        if !known.contains(id) {
+            unknown.push(id);
+        } else if !seen.insert(id) {
+            duplicated.push(id);
+        } else {
+            order.push(id);
+        }
+    }
+
+    if !unknown.is_empty() || !duplicated.is_empty() {
+        let mut problems = Vec::new();
+        if !unknown.is_empty() {
+            problems.push(format!("unknown track ids: {}", unknown.join(", ")));
+        }
+        if !duplicated.is_empty() {
+            problems.push(format!("duplicated track ids: {}", duplicated.join(", ")));
+        }
+        bail!("Reorder aborted, {}", problems.join("; "));
+    }
+
+    Ok(order)
+}
+
+/// Stage a track from one tracked playlist into another, optionally staging
+/// its removal from the source as well.
+pub async fn copy(
+    track_id: &str,
+    from: &str,
+    to: &str,
+    move_source: bool,
+    grit_dir: &Path,
+) -> Result<()> {
+    if from == to {
+        bail!("Source and destination are the same playlist");
+    }
+
+    let from_path = snapshot::snapshot_path(grit_dir, from);
+    if !from_path.exists() {
+        bail!("Source playlist '{}' not initialized.", from);
+    }
+
+    let source = snapshot::load(&from_path)?;
+    let (from_index, track) = source
+        .tracks
+        .iter()
+        .enumerate()
+        .find(|(_, t)| t.id == track_id)
+        .context("Track not found in source playlist")?;
+
+    let (destination, index) = stage_copy(track, to, grit_dir)?;
+
+    println!(
+        "Staged for addition to '{}': {} - {}",
+        destination,
+        track.name,
+        track.artists.join(", ")
+    );
+    println!("  Position: {}", index);
+
+    if move_source {
+        stage_change(
+            grit_dir,
+            from,
+            TrackChange::Removed {
+                track: track.clone(),
+                index: from_index,
+            },
+        )?;
+        println!("Staged for removal from '{}'", source.name);
+        println!("  Position: {}", from_index);
+    }
+
+    println!("\nCommit each playlist with 'grit commit -l <id> -m \"message\"'");
+
+    Ok(())
+}
+
+/// Stage `track` for addition at the end of tracked playlist `to`, returning
+/// that playlist's name and the position staged
+pub fn stage_copy(track: &Track, to: &str, grit_dir: &Path) -> Result<(String, usize)> {
+    let to_path = snapshot::snapshot_path(grit_dir, to);
+    if !to_path.exists() {
+        bail!("Destination playlist '{}' not initialized.", to);
+    }
+
+    let destination = snapshot::load(&to_path)?;
+    if track.provider != destination.provider {
+        bail!(
+            "Cannot copy {:?} track to {:?} playlist. Provider mismatch.",
+            track.provider,
+            destination.provider
+        );
+    }
+
+    // Earlier copies that are only staged count too
+    let pending = pending_snapshot(&destination, to, grit_dir)?;
+    if pending.tracks.iter().any(|t| t.same_as(track)) {
+        bail!("Track is already in '{}'", destination.name);
+    }
+
+    let index = insert_index(&pending.tracks, &AddPosition::End)?;
+    stage_change(
+        grit_dir,
+        to,
+        TrackChange::Added {
+            track: track.clone(),
+            index,
+        },
+    )?;
+    Ok((destination.name, index))
+}
+
 pub async fn reset(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
     let playlist_id = playlist.context("Playlist required (use --playlist)")?;
 
@@ -414,6 +982,7 @@ pub async fn reset(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
     }
 
     clear_staged(grit_dir, playlist_id)?;
+    remote_cache::invalidate(grit_dir, playlist_id);
 
     println!("Staged changes cleared.");
     println!("  {} operations discarded", patch.changes.len());
@@ -421,7 +990,54 @@ pub async fn reset(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-pub async fn commit(message: &str, playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
+/// Write the commit message in `$EDITOR`, starting from a template that
+/// lists the staged changes as comments, like `git commit` without `-m`
+fn edit_commit_message(patch: &DiffPatch, grit_dir: &Path, playlist_id: &str) -> Result<String> {
+    let mut template = String::from(
+        "\n# Describe these changes. Lines starting with '#' are ignored, and an\n\
+         # empty message aborts the commit.\n#\n# Staged changes:\n",
+    );
+    for change in &patch.changes {
+        let line = match change {
+            TrackChange::Added { track, index } => format!(
+                "+ [{}] {} - {}",
+                index,
+                track.name,
+                track.artists.join(", ")
+            ),
+            TrackChange::Removed { track, index } => format!(
+                "- [{}] {} - {}",
+                index,
+                track.name,
+                track.artists.join(", ")
+            ),
+            TrackChange::Moved { track, from, to } => format!(
+                "~ {} - {} (from {} to {})",
+                track.name,
+                track.artists.join(", "),
+                from,
+                to
+            ),
+        };
+        template.push_str(&format!("#   {}\n", line));
+    }
+
+    let path = snapshot::playlist_dir(grit_dir, playlist_id).join("COMMIT_EDITMSG");
+    fs::write(&path, template).with_context(|| format!("Failed to write {:?}", path))?;
+    let edited = editor::edit_file(&path).and_then(|_| {
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))
+    });
+    let _ = fs::remove_file(&path);
+
+    let message = edited?
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(message.trim().to_string())
+}
+
+pub async fn commit(message: Option<&str>, playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
     let playlist_id = playlist.context("Playlist required (use --playlist)")?;
 
     let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
@@ -435,6 +1051,15 @@ pub async fn commit(message: &str, playlist: Option<&str>, grit_dir: &Path) -> R
         return Ok(());
     }
 
+    let message = match message {
+        Some(message) => message.to_string(),
+        None => edit_commit_message(&patch, grit_dir, playlist_id)?,
+    };
+    if message.is_empty() {
+        println!("Empty commit message, nothing committed.");
+        return Ok(());
+    }
+
     let mut snapshot_copy = snapshot::load(&snapshot_path)?;
 
     let mut added = 0;
@@ -465,7 +1090,7 @@ pub async fn commit(message: &str, playlist: Option<&str>, grit_dir: &Path) -> R
         added,
         removed,
         moved,
-        message.to_string(),
+        message.clone(),
     );
     JournalEntry::append(&journal_path, &entry)?;
 
@@ -478,3 +1103,50 @@ pub async fn commit(message: &str, playlist: Option<&str>, grit_dir: &Path) -> R
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_index_next_to_anchor() {
+        let tracks = vec![Track::test("a"), Track::test("b"), Track::test("c")];
+
+        assert_eq!(insert_index(&tracks, &AddPosition::End).unwrap(), 3);
+        assert_eq!(insert_index(&tracks, &AddPosition::After("b")).unwrap(), 2);
+        assert_eq!(insert_index(&tracks, &AddPosition::Before("a")).unwrap(), 0);
+        assert!(insert_index(&tracks, &AddPosition::After("x")).is_err());
+    }
+
+    #[test]
+    fn test_stage_copy_counts_staged_copies() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let destination = PlaylistSnapshot {
+            id: "dst".to_string(),
+            name: "Destination".to_string(),
+            description: None,
+            tracks: vec![Track::test("a")],
+            provider: ProviderKind::Spotify,
+            snapshot_hash: String::new(),
+            metadata: None,
+            schema_version: crate::provider::SCHEMA_VERSION,
+        };
+        snapshot::save(&destination, &snapshot::snapshot_path(dir.path(), "dst")).unwrap();
+
+        assert_eq!(
+            stage_copy(&Track::test("b"), "dst", dir.path()).unwrap().1,
+            1
+        );
+        assert_eq!(
+            stage_copy(&Track::test("c"), "dst", dir.path()).unwrap().1,
+            2
+        );
+        // Already staged, so not copied again
+        assert!(stage_copy(&Track::test("b"), "dst", dir.path()).is_err());
+
+        let mut pending = destination.clone();
+        apply_patch(&mut pending, &load_staged(dir.path(), "dst").unwrap()).unwrap();
+        let ids: Vec<&str> = pending.tracks.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+    }
+}
@@ -3,31 +3,126 @@ use std::path::Path;
 use anyhow::{Context, Result};
 
 use crate::{
-    provider::{Provider, ProviderKind, SpotifyProvider, YoutubeProvider},
-    state::credentials,
+    provider::{
+        FetchOptions, PlaylistSnapshot, Provider, ProviderKind, SpotifyProvider, YoutubeProvider,
+    },
+    state::{config, credentials, remote_cache},
+    utils::color,
 };
 
+/// `+added -removed ~moved` summary, colored like the change lines
+pub fn change_counts(added: usize, removed: usize, moved: usize) -> String {
+    format!(
+        "{} {} {}",
+        color::green(&format!("+{}", added)),
+        color::red(&format!("-{}", removed)),
+        color::yellow(&format!("~{}", moved))
+    )
+}
+
+/// Client id and secret for a provider: the environment variables when set,
+/// else `[credentials.<provider>]` in the config.
+///
+/// Either may be missing: they are only needed when a token has to be
+/// refreshed, so callers defer the error until then.
+pub fn client_credentials(
+    provider_kind: ProviderKind,
+    grit_dir: &Path,
+) -> (Option<String>, Option<String>) {
+    let (id_var, secret_var) = client_env_vars(provider_kind);
+    let configured = config::load(grit_dir)
+        .map(|c| c.credentials.client(provider_kind).clone())
+        .unwrap_or_default();
+
+    let client_id = std::env::var(id_var).ok().or(configured.client_id);
+    let client_secret = std::env::var(secret_var).ok().or_else(|| {
+        let encoded = configured.client_secret?;
+        match credentials::decrypt_secret(&encoded, grit_dir) {
+            Ok(secret) => Some(secret),
+            Err(e) => {
+                tracing::warn!(
+                    "Ignoring [credentials.{}] client_secret: {:#}",
+                    provider_kind,
+                    e
+                );
+                None
+            }
+        }
+    });
+    (client_id, client_secret)
+}
+
+/// Environment variables holding a provider's client id and secret
+pub fn client_env_vars(provider_kind: ProviderKind) -> (&'static str, &'static str) {
+    match provider_kind {
+        ProviderKind::Spotify => ("SPOTIFY_CLIENT_ID", "SPOTIFY_CLIENT_SECRET"),
+        ProviderKind::Youtube => ("YOUTUBE_CLIENT_ID", "YOUTUBE_CLIENT_SECRET"),
+    }
+}
+
 pub fn create_provider(provider_kind: ProviderKind, grit_dir: &Path) -> Result<Box<dyn Provider>> {
     let token = credentials::load(grit_dir, provider_kind)?
         .context("No credentials found. Please run 'grit auth <provider>' first.")?;
+    let (client_id, client_secret) = client_credentials(provider_kind, grit_dir);
 
     let provider: Box<dyn Provider> = match provider_kind {
-        ProviderKind::Spotify => {
-            let client_id =
-                std::env::var("SPOTIFY_CLIENT_ID").context("SPOTIFY_CLIENT_ID not set")?;
-            let client_secret =
-                std::env::var("SPOTIFY_CLIENT_SECRET").context("SPOTIFY_CLIENT_SECRET not set")?;
+        ProviderKind::Spotify => Box::new(
+            SpotifyProvider::with_credentials(client_id, client_secret)
+                .with_token(&token, grit_dir)
+                .with_market(config::load(grit_dir)?.remote.spotify_market),
+        ),
+        ProviderKind::Youtube => Box::new(
+            YoutubeProvider::with_credentials(client_id, client_secret)
+                .with_token(&token, grit_dir),
+        ),
+    };
+    Ok(provider)
+}
+
+/// How init, pull and push fetch: unavailable items are kept as
+/// placeholders unless `[remote] keep_unavailable` is off, so positions
+/// line up with the remote playlist. Playback drops them instead.
+pub fn vcs_fetch_options(limit: Option<usize>, grit_dir: &Path) -> Result<FetchOptions> {
+    Ok(FetchOptions {
+        limit,
+        include_unavailable: config::load(grit_dir)?.remote.keep_unavailable,
+    })
+}
 
-            Box::new(SpotifyProvider::new(client_id, client_secret).with_token(&token, grit_dir))
+/// Fetch the remote playlist, reusing a recent fetch unless `refresh` is set.
+pub async fn fetch_remote(
+    provider: &dyn Provider,
+    local: &PlaylistSnapshot,
+    playlist_id: &str,
+    grit_dir: &Path,
+    refresh: bool,
+) -> Result<PlaylistSnapshot> {
+    if local.id.is_empty() {
+        anyhow::bail!(
+            "'{}' only exists locally (no remote playlist yet)",
+            local.name
+        );
+    }
+
+    let max_age = config::load(grit_dir)?.remote.cache_secs;
+
+    if !refresh && max_age > 0 {
+        if let Some(mut cached) = remote_cache::load_fresh(grit_dir, playlist_id, max_age) {
+            tracing::debug!("Using cached remote state for {}", playlist_id);
+            cached.keep_local_metadata(local);
+            return Ok(cached);
         }
-        ProviderKind::Youtube => {
-            let client_id =
-                std::env::var("YOUTUBE_CLIENT_ID").context("YOUTUBE_CLIENT_ID not set")?;
-            let client_secret =
-                std::env::var("YOUTUBE_CLIENT_SECRET").context("YOUTUBE_CLIENT_SECRET not set")?;
+    }
 
-            Box::new(YoutubeProvider::new(client_id, client_secret).with_token(&token, grit_dir))
+    // Compare like with like: a --depth snapshot against the same slice
+    let options = vcs_fetch_options(local.depth(), grit_dir)?;
+    let mut remote = provider.fetch_with(playlist_id, &options).await?;
+    if max_age > 0 {
+        // The cache only saves a request next time; not having it is fine
+        if let Err(e) = remote_cache::save(grit_dir, playlist_id, &remote) {
+            tracing::warn!("Failed to cache remote state for {}: {:#}", playlist_id, e);
         }
-    };
-    Ok(provider)
+    }
+    remote.keep_local_metadata(local);
+    Ok(remote)
 }
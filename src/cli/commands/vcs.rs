@@ -1,13 +1,49 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, IsTerminal, Write as _};
 use std::path::Path;
 
 use anyhow::{bail, Context, Result};
 
 use crate::{
-    cli::commands::utils::create_provider,
-    state::{diff, load_staged, snapshot, JournalEntry, Operation},
+    cli::commands::utils::{change_counts, create_provider, fetch_remote, vcs_fetch_options},
+    provider::{DiffPatch, PlaylistSnapshot, Provider, Track, TrackChange, LIKED_PLAYLIST_ID},
+    state::{
+        apply_patch, config, diff, load_staged, remote_cache, snapshot, working_playlist,
+        JournalEntry, Operation,
+    },
+    utils::{color, pager, text},
 };
 
-pub async fn push(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
+/// How many extra apply rounds `push --force` makes before giving up
+const FORCE_PUSH_RETRIES: usize = 3;
+
+/// Ask before a push that would spend more quota than `[remote]
+/// youtube_quota_budget` allows. Refuses outright when nobody can answer.
+fn confirm_over_budget(units: u64, budget: u64) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        bail!(
+            "This push needs about {} quota units, over the budget of {}. \
+             Raise youtube_quota_budget under [remote] in config.toml, or push from a terminal to confirm.",
+            units,
+            budget
+        );
+    }
+
+    print!(
+        "This push needs about {} quota units, over the budget of {}. If the quota runs out \
+         it stops partway and can be resumed later. Continue? [y/N] ",
+        units, budget
+    );
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+pub async fn push(playlist: Option<&str>, force: bool, grit_dir: &Path) -> Result<()> {
     let playlist_id = playlist.context("Playlist required (use --playlist)")?;
 
     let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
@@ -23,9 +59,36 @@ pub async fn push(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
         );
     }
 
-    let local_snapshot = snapshot::load(&snapshot_path)?;
+    let mut local_snapshot = snapshot::load(&snapshot_path)?;
+    if local_snapshot.id == LIKED_PLAYLIST_ID {
+        bail!(
+            "'{}' is read-only: liked songs can't be pushed. Copy tracks into a playlist with 'grit cp' instead.",
+            local_snapshot.name
+        );
+    }
+    if let Some(depth) = local_snapshot.depth() {
+        bail!(
+            "'{}' only tracks its first {} tracks (grit init --depth), so pushing would \
+             delete the rest of the playlist. Push is disabled for it.",
+            local_snapshot.name,
+            depth
+        );
+    }
     let provider = create_provider(local_snapshot.provider, grit_dir)?;
 
+    let playlist_id = if local_snapshot.id.is_empty() {
+        create_remote(
+            provider.as_ref(),
+            playlist_id,
+            &mut local_snapshot,
+            grit_dir,
+        )
+        .await?
+    } else {
+        playlist_id.to_string()
+    };
+    let playlist_id = playlist_id.as_str();
+
     println!("Verifying write permissions...");
     let can_modify = provider.can_modify_playlist(playlist_id).await?;
     if !can_modify {
@@ -35,7 +98,8 @@ pub async fn push(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
     }
 
     println!("Fetching remote playlist state...");
-    let remote_snapshot = provider.fetch(playlist_id).await?;
+    let fetch_options = vcs_fetch_options(None, grit_dir)?;
+    let remote_snapshot = provider.fetch_with(playlist_id, &fetch_options).await?;
 
     let patch = diff(&remote_snapshot, &local_snapshot);
 
@@ -56,6 +120,15 @@ pub async fn push(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
         }
     }
 
+    if let Some(units) = provider.estimate_quota(&remote_snapshot, &patch, &local_snapshot) {
+        let budget = config::load(grit_dir)?.remote.youtube_quota_budget;
+        println!("\nEstimated quota cost: {} units", units);
+        if units > budget && !confirm_over_budget(units, budget)? {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
     println!(
         "\nPushing changes to remote: +{} -{} ~{}",
         added, removed, moved
@@ -63,6 +136,38 @@ pub async fn push(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
 
     // Apply patch to remote to match local snapshot
     provider.apply(playlist_id, &patch, &local_snapshot).await?;
+    remote_cache::invalidate(grit_dir, playlist_id);
+
+    if force {
+        // Local is authoritative: re-fetch and re-apply until the remote
+        // matches, e.g. when someone edited it while we were reordering
+        let mut attempt = 0;
+        loop {
+            println!("Verifying remote...");
+            let remote_snapshot = provider.fetch_with(playlist_id, &fetch_options).await?;
+            let remaining = diff(&remote_snapshot, &local_snapshot);
+            if remaining.changes.is_empty() {
+                break;
+            }
+            if attempt == FORCE_PUSH_RETRIES {
+                bail!(
+                    "Remote still differs from local by {} change(s) after {} retries",
+                    remaining.changes.len(),
+                    FORCE_PUSH_RETRIES
+                );
+            }
+            attempt += 1;
+            println!(
+                "Remote differs by {} change(s), re-applying (attempt {}/{})",
+                remaining.changes.len(),
+                attempt,
+                FORCE_PUSH_RETRIES
+            );
+            provider
+                .apply(playlist_id, &remaining, &local_snapshot)
+                .await?;
+        }
+    }
 
     // Record in journal
     let hash = snapshot::compute_hash(&local_snapshot)?;
@@ -76,7 +181,42 @@ pub async fn push(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-pub async fn log(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
+/// Create the remote playlist for one that so far only exists locally, and
+/// move its tracking dir to the new id that later commands look it up by.
+/// Returns that id; the tracks are then pushed like any other changes, so a
+/// push cut short can simply be run again.
+async fn create_remote(
+    provider: &dyn Provider,
+    key: &str,
+    local: &mut PlaylistSnapshot,
+    grit_dir: &Path,
+) -> Result<String> {
+    println!("Creating '{}' on {}...", local.name, local.provider);
+    let id = provider
+        .create_playlist(&local.name, local.description.as_deref())
+        .await
+        .context("Failed to create the remote playlist")?;
+
+    let from = snapshot::playlist_dir(grit_dir, key);
+    let to = snapshot::playlist_dir(grit_dir, &id);
+    fs::rename(&from, &to).with_context(|| format!("Failed to move {:?} to {:?}", from, to))?;
+
+    local.id = id.clone();
+    snapshot::save(local, &snapshot::snapshot_path(grit_dir, &id))?;
+    snapshot::save_by_hash(local, &snapshot::compute_hash(local)?, grit_dir, &id)?;
+
+    if let Ok(mut working) = working_playlist::load_state(grit_dir) {
+        if working.playlist_id == key {
+            working.playlist_id = id.clone();
+            working_playlist::save_state(grit_dir, &working)?;
+        }
+    }
+
+    println!("  Created {}, now tracked under that id", id);
+    Ok(id)
+}
+
+pub async fn log(playlist: Option<&str>, reverse: bool, grit_dir: &Path) -> Result<()> {
     let playlist_id = playlist.context("Playlist required (use --playlist")?;
 
     let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
@@ -92,39 +232,145 @@ pub async fn log(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
         return Ok(());
     }
 
-    println!("\nCommit History:\n");
+    let head = entries.len() - 1;
 
-    for entry in entries.iter().rev() {
-        let hash_short = &entry.snapshot_hash[..8.min(entry.snapshot_hash.len())];
+    let mut ordered: Vec<(usize, &JournalEntry)> = entries.iter().enumerate().collect();
+    if !reverse {
+        ordered.reverse();
+    }
+
+    let mut out = String::from("\nCommit History:\n\n");
+
+    for (index, entry) in ordered {
+        let hash_short = color::yellow(&entry.snapshot_hash[..8.min(entry.snapshot_hash.len())]);
+
+        let decoration = if index == head {
+            color::green(" (HEAD)")
+        } else {
+            String::new()
+        };
         let timestamp = entry.timestamp.format("%Y-%m-%d %H:%M:%S");
 
-        let operation_str = match entry.operation {
-            Operation::Init => "init",
-            Operation::Pull => "pull",
-            Operation::Push => "push",
-            Operation::Apply => "apply",
-            Operation::Commit => "commit",
+        let operation_str = operation_label(entry.operation);
+
+        let changes = change_counts(entry.added, entry.removed, entry.moved);
+
+        if let Some(msg) = &entry.message {
+            writeln!(
+                out,
+                "[{}]{} {} | {} | {}",
+                hash_short, decoration, timestamp, operation_str, msg
+            )?;
+        } else {
+            writeln!(
+                out,
+                "[{}]{} {} | {}",
+                hash_short, decoration, timestamp, operation_str
+            )?;
+        }
+
+        writeln!(out, "  {}", changes)?;
+        writeln!(out)?;
+    }
+
+    pager::page(&out)
+}
+
+pub async fn activity(limit: usize, grit_dir: &Path) -> Result<()> {
+    let playlists_dir = grit_dir.join("playlists");
+
+    if !playlists_dir.exists() {
+        println!("No playlists tracked yet. Use 'grit init <playlist-id>' to start tracking.");
+        return Ok(());
+    }
+
+    let dir_entries = std::fs::read_dir(&playlists_dir)
+        .with_context(|| format!("Failed to read playlists directory: {:?}", playlists_dir))?;
+
+    // (playlist name, entry) pairs from every journal
+    let mut feed: Vec<(String, JournalEntry)> = Vec::new();
+
+    for dir_entry in dir_entries {
+        let path = dir_entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let playlist_id = match path.file_name().and_then(|n| n.to_str()) {
+            Some(dir_name) => snapshot::playlist_id_from_dir(dir_name),
+            None => continue,
         };
 
-        let changes = format!("+{} -{} ~{}", entry.added, entry.removed, entry.moved);
+        let name = snapshot::load(&snapshot::snapshot_path(grit_dir, &playlist_id))
+            .map(|snap| snap.name)
+            .unwrap_or_else(|_| playlist_id.clone());
+
+        let journal_path = JournalEntry::journal_path(grit_dir, &playlist_id);
+        match JournalEntry::read_all(&journal_path) {
+            Ok(entries) => {
+                feed.extend(entries.into_iter().map(|entry| (name.clone(), entry)));
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read journal for {}: {}", playlist_id, e);
+            }
+        }
+    }
+
+    if feed.is_empty() {
+        println!("No activity yet.");
+        return Ok(());
+    }
+
+    // Newest first
+    feed.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.timestamp));
+
+    println!("\nRecent Activity:\n");
+
+    for (name, entry) in feed.iter().take(limit) {
+        let hash_short = &entry.snapshot_hash[..8.min(entry.snapshot_hash.len())];
+        let timestamp = entry.timestamp.format("%Y-%m-%d %H:%M:%S");
+        let operation_str = operation_label(entry.operation);
 
         if let Some(msg) = &entry.message {
             println!(
-                "[{}] {} | {} | {}",
-                hash_short, timestamp, operation_str, msg
+                "[{}] {} | {} | {} | {}",
+                hash_short, timestamp, name, operation_str, msg
             );
         } else {
-            println!("[{}] {} | {}", hash_short, timestamp, operation_str);
+            println!(
+                "[{}] {} | {} | {}",
+                hash_short, timestamp, name, operation_str
+            );
         }
 
-        println!("  {}", changes);
+        println!(
+            "  {}",
+            change_counts(entry.added, entry.removed, entry.moved)
+        );
         println!();
     }
 
+    if feed.len() > limit {
+        println!(
+            "({} older entries not shown, use --limit to see more)",
+            feed.len() - limit
+        );
+    }
+
     Ok(())
 }
 
-pub async fn pull(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
+fn operation_label(operation: Operation) -> &'static str {
+    match operation {
+        Operation::Init => "init",
+        Operation::Pull => "pull",
+        Operation::Push => "push",
+        Operation::Apply => "apply",
+        Operation::Commit => "commit",
+    }
+}
+
+pub async fn pull(playlist: Option<&str>, refresh: bool, grit_dir: &Path) -> Result<()> {
     let playlist_id = playlist.context("Playlist required (use --playlist)")?;
 
     let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
@@ -144,7 +390,14 @@ pub async fn pull(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
     let provider = create_provider(local_snapshot.provider, grit_dir)?;
 
     println!("Fetching remote playlist state...");
-    let remote_snapshot = provider.fetch(playlist_id).await?;
+    let remote_snapshot = fetch_remote(
+        provider.as_ref(),
+        &local_snapshot,
+        playlist_id,
+        grit_dir,
+        refresh,
+    )
+    .await?;
 
     let local_hash = snapshot::compute_hash(&local_snapshot)?;
     let remote_hash = snapshot::compute_hash(&remote_snapshot)?;
@@ -175,6 +428,7 @@ pub async fn pull(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
 
     // Update local snapshot to match remote
     snapshot::save(&remote_snapshot, &snapshot_path)?;
+    remote_cache::invalidate(grit_dir, playlist_id);
 
     // Record in journal
     let journal_path = JournalEntry::journal_path(grit_dir, playlist_id);
@@ -187,12 +441,26 @@ pub async fn pull(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Which comparisons `grit diff` shows, and how
+pub struct DiffOptions {
+    pub staged: bool,
+    pub remote: bool,
+    pub refresh: bool,
+    /// Side-by-side old and new order instead of a change list
+    pub visual: bool,
+}
+
 pub async fn diff_cmd(
     playlist: Option<&str>,
+    options: &DiffOptions,
     grit_dir: &Path,
-    staged: bool,
-    remote: bool,
 ) -> Result<()> {
+    let DiffOptions {
+        staged,
+        remote,
+        refresh,
+        visual,
+    } = *options;
     let playlist_id = playlist.context("Playlist required (use --playlist)")?;
 
     let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
@@ -212,32 +480,53 @@ pub async fn diff_cmd(
 
         if patch.changes.is_empty() {
             println!("No staged changes.\n");
+        } else if visual {
+            let mut after = local_snapshot.clone();
+            apply_patch(&mut after, &patch)?;
+            println!(
+                "{}",
+                render_visual(
+                    &local_snapshot.tracks,
+                    &after.tracks,
+                    &patch,
+                    ("committed", "staged")
+                )
+            );
         } else {
             for change in &patch.changes {
                 match change {
                     crate::provider::TrackChange::Added { track, index } => {
                         println!(
-                            "+ [{}] {} - {}",
-                            index,
-                            track.name,
-                            track.artists.join(", ")
+                            "{}",
+                            color::green(&format!(
+                                "+ [{}] {} - {}",
+                                index,
+                                track.name,
+                                track.artists.join(", ")
+                            ))
                         );
                     }
                     crate::provider::TrackChange::Removed { track, index } => {
                         println!(
-                            "- [{}] {} - {}",
-                            index,
-                            track.name,
-                            track.artists.join(", ")
+                            "{}",
+                            color::red(&format!(
+                                "- [{}] {} - {}",
+                                index,
+                                track.name,
+                                track.artists.join(", ")
+                            ))
                         );
                     }
                     crate::provider::TrackChange::Moved { track, from, to } => {
                         println!(
-                            "~ {} - {} (from {} to {})",
-                            track.name,
-                            track.artists.join(", "),
-                            from,
-                            to
+                            "{}",
+                            color::yellow(&format!(
+                                "~ {} - {} (from {} to {})",
+                                track.name,
+                                track.artists.join(", "),
+                                from,
+                                to
+                            ))
                         );
                     }
                 };
@@ -251,39 +540,71 @@ pub async fn diff_cmd(
 
         let provider = create_provider(local_snapshot.provider, grit_dir)?;
 
-        match provider.fetch(playlist_id).await {
+        match fetch_remote(
+            provider.as_ref(),
+            &local_snapshot,
+            playlist_id,
+            grit_dir,
+            refresh,
+        )
+        .await
+        {
             std::result::Result::Ok(remote_snapshot) => {
                 use crate::state::diff as compute_diff;
                 let patch = compute_diff(&remote_snapshot, &local_snapshot);
 
                 if patch.changes.is_empty() {
                     println!("Local and remote are in sync.\n");
+                } else if visual {
+                    println!(
+                        "{}",
+                        render_visual(
+                            &remote_snapshot.tracks,
+                            &local_snapshot.tracks,
+                            &patch,
+                            ("remote", "local")
+                        )
+                    );
                 } else {
                     for change in &patch.changes {
                         match change {
                             crate::provider::TrackChange::Added { track, index } => {
                                 println!(
-                                    "+ [{}] {} - {}",
-                                    index,
-                                    track.name,
-                                    track.artists.join(", ")
+                                    "{}",
+                                    color::green(&format!(
+                                        "+ [{}] {} - {}",
+                                        index,
+                                        track.name,
+                                        track.artists.join(", ")
+                                    ))
                                 );
                             }
                             crate::provider::TrackChange::Removed { track, index } => {
+                                // Only on remote, so pulling would add it
+                                let added_by = added_by(track)
+                                    .map(|user| format!("  (added by {} on remote)", user))
+                                    .unwrap_or_default();
                                 println!(
-                                    "- [{}] {} - {}",
-                                    index,
-                                    track.name,
-                                    track.artists.join(", ")
+                                    "{}{}",
+                                    color::red(&format!(
+                                        "- [{}] {} - {}",
+                                        index,
+                                        track.name,
+                                        track.artists.join(", ")
+                                    )),
+                                    added_by
                                 );
                             }
                             crate::provider::TrackChange::Moved { track, from, to } => {
                                 println!(
-                                    "~ {} - {} (from {} to {})",
-                                    track.name,
-                                    track.artists.join(", "),
-                                    from,
-                                    to
+                                    "{}",
+                                    color::yellow(&format!(
+                                        "~ {} - {} (from {} to {})",
+                                        track.name,
+                                        track.artists.join(", "),
+                                        from,
+                                        to
+                                    ))
                                 );
                             }
                         }
@@ -291,12 +612,156 @@ pub async fn diff_cmd(
                     println!();
                 }
             }
-            Err(e) => {
-                println!("Could not fetch remote: {}\n", e);
+            // --remote asked for this comparison, so failing it fails the command
+            Err(e) => return Err(e.context("Could not fetch remote")),
+        }
+    }
+
+    Ok(())
+}
+
+/// Width of each column in `diff --visual`
+const VISUAL_COLUMN_WIDTH: usize = 38;
+
+/// Two columns, the order before and after `patch`, one track per row.
+/// Moved tracks point at their position in the other column; removed and
+/// added tracks are marked where they were or will be.
+fn render_visual(
+    before: &[Track],
+    after: &[Track],
+    patch: &DiffPatch,
+    (before_label, after_label): (&str, &str),
+) -> String {
+    let ids = |matches: fn(&TrackChange) -> Option<&Track>| -> HashSet<&str> {
+        patch
+            .changes
+            .iter()
+            .filter_map(matches)
+            .map(|t| t.id.as_str())
+            .collect()
+    };
+    let added = ids(|c| match c {
+        TrackChange::Added { track, .. } => Some(track),
+        _ => None,
+    });
+    let removed = ids(|c| match c {
+        TrackChange::Removed { track, .. } => Some(track),
+        _ => None,
+    });
+    let moved = ids(|c| match c {
+        TrackChange::Moved { track, .. } => Some(track),
+        _ => None,
+    });
+    let position = |tracks: &[Track], id: &str| tracks.iter().position(|t| t.id == id);
+
+    let mut out = format!(
+        "  {:<width$}  {}\n",
+        before_label,
+        after_label,
+        width = VISUAL_COLUMN_WIDTH
+    );
+    for row in 0..before.len().max(after.len()) {
+        let left = before.get(row).map(|track| {
+            let id = track.id.as_str();
+            if removed.contains(id) {
+                color::red(&visual_cell('-', row, track, ""))
+            } else if moved.contains(id) {
+                let to = position(after, id).map(|i| format!(" → {}", i));
+                color::yellow(&visual_cell('~', row, track, &to.unwrap_or_default()))
+            } else {
+                visual_cell(' ', row, track, "")
             }
+        });
+        let right = after.get(row).map(|track| {
+            let id = track.id.as_str();
+            if added.contains(id) {
+                color::green(&visual_cell('+', row, track, ""))
+            } else if moved.contains(id) {
+                let from = position(before, id).map(|i| format!(" ← {}", i));
+                color::yellow(&visual_cell('~', row, track, &from.unwrap_or_default()))
+            } else {
+                visual_cell(' ', row, track, "")
+            }
+        });
+
+        let left = left.unwrap_or_else(|| " ".repeat(VISUAL_COLUMN_WIDTH));
+        let right = right.unwrap_or_default();
+        let _ = writeln!(out, "  {}  {}", left, right.trim_end());
+    }
+    out
+}
+
+/// One column entry, padded to the column width. The name is cut short
+/// rather than `suffix`, which carries the move target.
+fn visual_cell(marker: char, index: usize, track: &Track, suffix: &str) -> String {
+    let prefix = format!("{} {:>3} ", marker, index);
+    let room = VISUAL_COLUMN_WIDTH.saturating_sub(text::width(&prefix) + text::width(suffix));
+    let name = text::truncate(&track.name, room);
+    text::pad(
+        &format!("{}{}{}", prefix, name, suffix),
+        VISUAL_COLUMN_WIDTH,
+    )
+}
+
+/// Collaborator who added the track, if the provider reported one
+fn added_by(track: &Track) -> Option<&str> {
+    track.metadata.as_ref()?.get("added_by")?.as_str()
+}
+
+/// List every stored snapshot, including ones no longer in the journal, so
+/// a lost state can be recovered with `grit revert <hash>`
+pub async fn reflog(playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
+    let playlist_id = playlist.context("Playlist required (use --playlist)")?;
+
+    let snapshot_path = snapshot::snapshot_path(grit_dir, playlist_id);
+    if !snapshot_path.exists() {
+        bail!("Playlist not initialized. Run 'grit init' first.");
+    }
+
+    let stored = snapshot::stored_snapshots(grit_dir, playlist_id)?;
+    if stored.is_empty() {
+        println!("No snapshots stored yet.");
+        return Ok(());
+    }
+
+    let journal_path = JournalEntry::journal_path(grit_dir, playlist_id);
+    let referenced: std::collections::HashSet<String> = JournalEntry::read_all(&journal_path)?
+        .into_iter()
+        .map(|entry| entry.snapshot_hash)
+        .collect();
+
+    println!("\nStored Snapshots:\n");
+
+    for (hash, modified) in stored {
+        let timestamp =
+            chrono::DateTime::<chrono::Local>::from(modified).format("%Y-%m-%d %H:%M:%S");
+        let status = if referenced.contains(&hash) {
+            ""
+        } else {
+            " (unreferenced)"
+        };
+
+        match snapshot::load_by_hash(&hash, grit_dir, playlist_id) {
+            Ok(snap) => println!(
+                "[{}] {} | {} | {} tracks{}",
+                color::yellow(&hash),
+                timestamp,
+                snap.name,
+                snap.tracks.len(),
+                status
+            ),
+            Err(e) => println!(
+                "[{}] {} | unreadable: {}{}",
+                color::yellow(&hash),
+                timestamp,
+                e,
+                status
+            ),
         }
     }
 
+    println!("\nUse 'grit revert <hash>' to restore one of these states");
+
     Ok(())
 }
 
@@ -362,10 +827,14 @@ pub async fn revert(hash: Option<&str>, playlist: Option<&str>, grit_dir: &Path)
     Ok(())
 }
 
+/// Apply a snapshot from a YAML file, or from stdin when `file_path` is "-"
 pub async fn apply(file_path: &str, playlist: Option<&str>, grit_dir: &Path) -> Result<()> {
-    // Load the snapshot from YAML file
-    let file_content = std::fs::read_to_string(file_path)
-        .with_context(|| format!("Failed to read file: {}", file_path))?;
+    let file_content = if file_path == "-" {
+        io::read_to_string(io::stdin()).context("Failed to read snapshot from stdin")?
+    } else {
+        std::fs::read_to_string(file_path)
+            .with_context(|| format!("Failed to read file: {}", file_path))?
+    };
 
     let snapshot: crate::provider::PlaylistSnapshot = serde_yaml::from_str(&file_content)
         .with_context(|| "Failed to parse YAML file as PlaylistSnapshot")?;
@@ -424,3 +893,47 @@ pub async fn apply(file_path: &str, playlist: Option<&str>, grit_dir: &Path) ->
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{PlaylistSnapshot, ProviderKind};
+
+    #[test]
+    fn test_render_visual_marks_each_side() {
+        let before = vec![Track::test("a"), Track::test("b"), Track::test("c")];
+        let after = vec![Track::test("c"), Track::test("a"), Track::test("d")];
+        let patch = diff(
+            &PlaylistSnapshot {
+                tracks: before.clone(),
+                ..empty_snapshot()
+            },
+            &PlaylistSnapshot {
+                tracks: after.clone(),
+                ..empty_snapshot()
+            },
+        );
+
+        let out = render_visual(&before, &after, &patch, ("old", "new"));
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].trim_start().starts_with("old"));
+        assert!(lines[2].contains("-   1 b"));
+        assert!(lines[3].contains("~   2 c → 0"));
+        assert!(lines[1].contains("~   0 c ← 2"));
+        assert!(lines[3].contains("+   2 d"));
+    }
+
+    fn empty_snapshot() -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            id: String::new(),
+            name: String::new(),
+            description: None,
+            tracks: Vec::new(),
+            provider: ProviderKind::Spotify,
+            snapshot_hash: String::new(),
+            metadata: None,
+            schema_version: 0,
+        }
+    }
+}
@@ -1,4 +1,4 @@
 mod args;
 pub mod commands;
 
-pub use args::{Cli, Commands};
+pub use args::{Cli, Commands, ConfigAction};
@@ -0,0 +1,44 @@
+use csv::StringRecord;
+
+use super::{Header, ImportFormat, ImportRow};
+use crate::provider::ProviderKind;
+
+/// CSV written by Exportify (exportify.net) for a Spotify playlist
+pub struct Exportify;
+
+impl ImportFormat for Exportify {
+    fn name(&self) -> &'static str {
+        "Exportify"
+    }
+
+    fn detect(&self, header: &Header) -> bool {
+        header.has("Track URI") && header.has("Track Name")
+    }
+
+    fn row(&self, header: &Header, record: &StringRecord) -> Option<ImportRow> {
+        // Local files have `spotify:local:...` URIs and no track id
+        let track = header
+            .get(record, "Track URI")
+            .and_then(|uri| uri.strip_prefix("spotify:track:"))
+            .map(|id| (ProviderKind::Spotify, id.to_string()));
+        let name = header.get(record, "Track Name").map(str::to_string);
+        let artists = header
+            .get(record, "Artist Name(s)")
+            .map(|names| {
+                names
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        (track.is_some() || name.is_some()).then_some(ImportRow {
+            line: 0,
+            track,
+            name,
+            artists,
+        })
+    }
+}
@@ -0,0 +1,184 @@
+pub mod exportify;
+pub mod takeout;
+
+use anyhow::{bail, Context, Result};
+use csv::StringRecord;
+
+use crate::provider::ProviderKind;
+use exportify::Exportify;
+use takeout::Takeout;
+
+/// Formats tried, in order, against each row until one claims a header
+const FORMATS: &[&dyn ImportFormat] = &[&Exportify, &Takeout];
+
+/// A playlist export made by another tool. Each format recognizes its own
+/// header row and turns the rows below it into `ImportRow`s; add a new one by
+/// implementing this and listing it in `FORMATS`.
+pub trait ImportFormat: Sync {
+    /// Name shown when reporting which format was detected
+    fn name(&self) -> &'static str;
+
+    /// Whether `header` is this format's header row
+    fn detect(&self, header: &Header) -> bool;
+
+    /// Read one row below the header. `None` skips rows without a track.
+    fn row(&self, header: &Header, record: &StringRecord) -> Option<ImportRow>;
+}
+
+/// One track from an export, as much of it as the format records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportRow {
+    /// Line in the file, for reporting rows that can't be resolved
+    pub line: u64,
+    /// Track id and the provider it belongs to, when the export has one
+    pub track: Option<(ProviderKind, String)>,
+    pub name: Option<String>,
+    pub artists: Vec<String>,
+}
+
+impl ImportRow {
+    /// Search query for rows without a usable id
+    pub fn query(&self) -> Option<String> {
+        let name = self.name.as_deref()?;
+        Some(match self.artists.first() {
+            Some(artist) => format!("{} {}", name, artist),
+            None => name.to_string(),
+        })
+    }
+
+    /// How the row is shown in reports, e.g. "Song - Artist"
+    pub fn label(&self) -> String {
+        match (&self.name, &self.track) {
+            (Some(name), _) if self.artists.is_empty() => name.clone(),
+            (Some(name), _) => format!("{} - {}", name, self.artists.join(", ")),
+            (None, Some((_, id))) => id.clone(),
+            (None, None) => "(empty row)".to_string(),
+        }
+    }
+}
+
+/// Header row of an export, looked up by column name
+pub struct Header(StringRecord);
+
+impl Header {
+    fn index(&self, column: &str) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|name| name.trim().eq_ignore_ascii_case(column))
+    }
+
+    pub fn has(&self, column: &str) -> bool {
+        self.index(column).is_some()
+    }
+
+    /// Non-empty value of `column` in `record`
+    pub fn get<'r>(&self, record: &'r StringRecord, column: &str) -> Option<&'r str> {
+        record
+            .get(self.index(column)?)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Rows read from an export, with the format they were read as
+pub struct Import {
+    pub format: &'static str,
+    pub rows: Vec<ImportRow>,
+}
+
+/// Detect the export's format from its header and read its rows. Lines
+/// before the header (Takeout's playlist details) are skipped.
+pub fn parse(content: &str) -> Result<Import> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+
+    let mut found: Option<(&dyn ImportFormat, Header)> = None;
+    let mut rows = Vec::new();
+
+    for record in reader.records() {
+        let record = record.context("Failed to read CSV")?;
+        match &found {
+            None => {
+                let header = Header(record);
+                if let Some(format) = FORMATS.iter().find(|f| f.detect(&header)) {
+                    found = Some((*format, header));
+                }
+            }
+            Some((format, header)) => {
+                if let Some(mut row) = format.row(header, &record) {
+                    row.line = record.position().map_or(0, |p| p.line());
+                    rows.push(row);
+                }
+            }
+        }
+    }
+
+    let Some((format, _)) = found else {
+        let names: Vec<&str> = FORMATS.iter().map(|f| f.name()).collect();
+        bail!(
+            "Unrecognized export: no {} header found",
+            names.join(" or ")
+        );
+    };
+
+    Ok(Import {
+        format: format.name(),
+        rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_exportify() {
+        let csv = "\"Track URI\",\"Track Name\",\"Artist Name(s)\",\"Album Name\"\n\
+                   \"spotify:track:abc\",\"Song, Part 1\",\"Artist A,Artist B\",\"Album\"\n\
+                   \"spotify:local:x:y:z:180\",\"Local Song\",\"Someone\",\"\"\n";
+
+        let import = parse(csv).unwrap();
+        assert_eq!(import.format, "Exportify");
+        assert_eq!(import.rows.len(), 2);
+        assert_eq!(
+            import.rows[0].track,
+            Some((ProviderKind::Spotify, "abc".to_string()))
+        );
+        assert_eq!(import.rows[0].label(), "Song, Part 1 - Artist A, Artist B");
+        assert_eq!(import.rows[0].line, 2);
+        // Local files have no Spotify id, so they can only be searched for
+        assert_eq!(import.rows[1].track, None);
+        assert_eq!(
+            import.rows[1].query().as_deref(),
+            Some("Local Song Someone")
+        );
+    }
+
+    #[test]
+    fn test_parse_takeout_skips_playlist_details() {
+        let csv = "Playlist Id,Add new videos to top,Playlist Title\n\
+                   PL123,False,Mix\n\
+                   \n\
+                   Video Id,Time Added\n\
+                   dQw4w9WgXcQ,2021-01-01 00:00:00 UTC\n";
+
+        let import = parse(csv).unwrap();
+        assert_eq!(import.format, "YouTube Takeout");
+        assert_eq!(
+            import.rows,
+            vec![ImportRow {
+                line: 5,
+                track: Some((ProviderKind::Youtube, "dQw4w9WgXcQ".to_string())),
+                name: None,
+                artists: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_format() {
+        assert!(parse("a,b\n1,2\n").is_err());
+    }
+}
@@ -0,0 +1,30 @@
+use csv::StringRecord;
+
+use super::{Header, ImportFormat, ImportRow};
+use crate::provider::ProviderKind;
+
+/// Playlist CSV from a Google Takeout YouTube export. Older exports start
+/// with a block of playlist details before the video list; both spell the
+/// id column differently ("Video Id" / "Video ID"), which `Header` ignores.
+pub struct Takeout;
+
+impl ImportFormat for Takeout {
+    fn name(&self) -> &'static str {
+        "YouTube Takeout"
+    }
+
+    fn detect(&self, header: &Header) -> bool {
+        header.has("Video Id")
+    }
+
+    fn row(&self, header: &Header, record: &StringRecord) -> Option<ImportRow> {
+        // Takeout only records ids, so rows can't be searched for elsewhere
+        let id = header.get(record, "Video Id")?;
+        Some(ImportRow {
+            line: 0,
+            track: Some((ProviderKind::Youtube, id.to_string())),
+            name: None,
+            artists: vec![],
+        })
+    }
+}
@@ -1,4 +1,5 @@
 mod cli;
+mod import;
 mod playback;
 mod provider;
 mod state;
@@ -7,7 +8,7 @@ mod utils;
 
 use anyhow::Context;
 use clap::Parser;
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, ConfigAction};
 use provider::ProviderKind;
 use std::path::{Path, PathBuf};
 
@@ -17,37 +18,114 @@ async fn main() -> anyhow::Result<()> {
     let _ = dotenvy::dotenv();
 
     let cli = Cli::parse();
-    let grit_dir = PathBuf::from(".grit");
+    let grit_dir = resolve_grit_dir(cli.grit_dir.clone());
+
+    // The TUI takes over the terminal, so log to a file while it runs
+    let tui_active = starts_tui(&cli.command) && grit_dir.exists();
+    let log_file = tui_active.then(|| grit_dir.join("grit.log"));
+    utils::logging::init(cli.verbose, log_file.as_deref())?;
+
+    let config = match state::config::load(&grit_dir) {
+        Ok(config) => config,
+        // `config edit` has to keep working to fix a broken file, and
+        // `doctor` reports the error itself
+        Err(_) if matches!(cli.command, Commands::Config { .. } | Commands::Doctor) => {
+            Default::default()
+        }
+        Err(e) => return Err(e),
+    };
+    utils::http::configure(&config.http);
+    utils::color::configure(cli.no_color);
+    utils::pager::configure(cli.no_pager);
+
+    let auth_account = match &cli.command {
+        Commands::Auth { account, .. } => account.clone(),
+        _ => None,
+    };
+    let account = match auth_account.or(cli.account.clone()) {
+        Some(account) => Some(account),
+        None => config.credentials.default_account,
+    };
+    if let Some(account) = account {
+        state::credentials::select_account(&account)?;
+    }
 
     match cli.command {
-        Commands::Auth { provider } => {
+        Commands::Auth { provider, .. } => {
             cli::commands::auth::run(provider, &grit_dir).await?;
         }
-        Commands::Init { playlist, provider } => {
-            let provider = provider
-                .or(cli.provider)
-                .or_else(|| cli::commands::init::detect_provider(&playlist))
-                .unwrap_or(ProviderKind::Spotify);
-            cli::commands::init::run(provider, &playlist, &grit_dir).await?;
+        Commands::Init {
+            playlist,
+            from_file,
+            name,
+            provider,
+            depth,
+        } => {
+            if let (Some(path), Some(name)) = (from_file, name) {
+                cli::commands::init::run_from_file(
+                    provider.or(cli.provider),
+                    Path::new(&path),
+                    &name,
+                    &grit_dir,
+                )
+                .await?;
+            } else {
+                let playlist = playlist.context("Playlist URL or ID required")?;
+                let provider = provider
+                    .or(cli.provider)
+                    .or_else(|| cli::commands::init::detect_provider(&playlist))
+                    .unwrap_or(ProviderKind::Spotify);
+                let depth = depth.map(|n| n as usize);
+                cli::commands::init::run(provider, &playlist, depth, &grit_dir).await?;
+            }
         }
-        Commands::Search { query, add } => {
+        Commands::Search {
+            query,
+            add,
+            play,
+            limit,
+            ids,
+            no_ids,
+        } => {
             let playlist = if add {
                 Some(resolve_playlist(None, cli.playlist.clone(), &grit_dir)?)
             } else {
-                None
+                // Only used to pick the provider when --provider is missing
+                resolve_playlist(None, cli.playlist.clone(), &grit_dir).ok()
+            };
+            let options = cli::commands::staging::SearchOptions {
+                add,
+                preview: play,
+                limit,
+                show_ids: show_ids(ids, no_ids, config.output.track_ids),
             };
             cli::commands::staging::search(
                 &query,
                 cli.provider,
-                &grit_dir,
-                add,
+                &options,
                 playlist.as_deref(),
+                &grit_dir,
             )
             .await?;
         }
-        Commands::Add { track_id } => {
+        Commands::Add {
+            track_ids,
+            after,
+            before,
+        } => {
+            use cli::commands::staging::AddPosition;
+
             let playlist = resolve_playlist(None, cli.playlist, &grit_dir)?;
-            cli::commands::staging::add(&track_id, Some(&playlist), &grit_dir).await?;
+            let position = match (&after, &before) {
+                (Some(anchor), _) => AddPosition::After(anchor),
+                (None, Some(anchor)) => AddPosition::Before(anchor),
+                (None, None) => AddPosition::End,
+            };
+            cli::commands::staging::add(&track_ids, position, Some(&playlist), &grit_dir).await?;
+        }
+        Commands::Import { file } => {
+            let playlist = resolve_playlist(None, cli.playlist, &grit_dir)?;
+            cli::commands::staging::import(&file, Some(&playlist), &grit_dir).await?;
         }
         Commands::Remove { track_id } => {
             let playlist = resolve_playlist(None, cli.playlist, &grit_dir)?;
@@ -56,26 +134,68 @@ async fn main() -> anyhow::Result<()> {
         Commands::Move {
             track_id,
             new_index,
+            preview,
         } => {
             let playlist = resolve_playlist(None, cli.playlist, &grit_dir)?;
-            cli::commands::staging::move_track(&track_id, new_index, Some(&playlist), &grit_dir)
-                .await?;
+            cli::commands::staging::move_track(
+                &track_id,
+                new_index,
+                preview,
+                Some(&playlist),
+                &grit_dir,
+            )
+            .await?;
         }
-        Commands::Status { playlist } => {
+        Commands::Reorder { playlist } => {
             let playlist = resolve_playlist(playlist, cli.playlist, &grit_dir)?;
-            cli::commands::staging::status(Some(&playlist), &grit_dir).await?;
+            cli::commands::staging::reorder(Some(&playlist), &grit_dir).await?;
+        }
+        Commands::Cp {
+            track_id,
+            from,
+            to,
+            move_track,
+        } => {
+            let from = resolve_playlist(from, cli.playlist, &grit_dir)?;
+            cli::commands::staging::copy(&track_id, &from, &to, move_track, &grit_dir).await?;
+        }
+        Commands::Status {
+            playlist,
+            refresh,
+            strict,
+        } => {
+            let playlist = resolve_playlist(playlist, cli.playlist, &grit_dir)?;
+            cli::commands::staging::status(Some(&playlist), refresh, strict, &grit_dir).await?;
         }
         Commands::Reset { playlist } => {
             let playlist = resolve_playlist(playlist, cli.playlist, &grit_dir)?;
             cli::commands::staging::reset(Some(&playlist), &grit_dir).await?;
         }
-        Commands::List { playlist } => {
+        Commands::List {
+            playlist,
+            group_by,
+            format,
+            ids,
+            no_ids,
+        } => {
             let playlist = resolve_playlist(playlist, cli.playlist, &grit_dir)?;
-            cli::commands::misc::list(Some(&playlist), &grit_dir).await?;
+            let show_ids = show_ids(ids, no_ids, config.output.track_ids);
+            cli::commands::misc::list(Some(&playlist), group_by, format, show_ids, &grit_dir)
+                .await?;
         }
-        Commands::Find { query, playlist } => {
+        Commands::Find {
+            query,
+            playlist,
+            ids,
+            no_ids,
+            play,
+            play_nth,
+        } => {
             let playlist = resolve_playlist(playlist, cli.playlist, &grit_dir)?;
-            cli::commands::misc::find(&query, Some(&playlist), &grit_dir).await?;
+            let show_ids = show_ids(ids, no_ids, config.output.track_ids);
+            let play_nth = play_nth.map(|n| n as usize).or(play.then_some(1));
+            cli::commands::misc::find(&query, Some(&playlist), show_ids, play_nth, &grit_dir)
+                .await?;
         }
         Commands::Logout { provider } => {
             cli::commands::auth::logout(provider, &grit_dir).await?;
@@ -83,52 +203,151 @@ async fn main() -> anyhow::Result<()> {
         Commands::Whoami { provider } => {
             cli::commands::auth::whoami(provider, &grit_dir).await?;
         }
+        Commands::Doctor => {
+            cli::commands::doctor::run(&grit_dir).await?;
+        }
+        Commands::Export {
+            output,
+            include_credentials,
+        } => {
+            cli::commands::backup::export_all(&output, include_credentials, &grit_dir)?;
+        }
+        Commands::ImportArchive { archive, force } => {
+            cli::commands::backup::import_archive(&archive, force, &grit_dir)?;
+        }
+        Commands::Config { action } => match action {
+            ConfigAction::Edit => cli::commands::config::edit(&grit_dir)?,
+            ConfigAction::SetClient { provider } => {
+                cli::commands::config::set_client(provider, &grit_dir)?
+            }
+        },
         Commands::Commit { message } => {
             let playlist = resolve_playlist(None, cli.playlist, &grit_dir)?;
-            cli::commands::staging::commit(&message, Some(&playlist), &grit_dir).await?;
+            cli::commands::staging::commit(message.as_deref(), Some(&playlist), &grit_dir).await?;
         }
-        Commands::Push { playlist } => {
+        Commands::Push { playlist, force } => {
             let playlist = resolve_playlist(playlist, cli.playlist, &grit_dir)?;
-            cli::commands::vcs::push(Some(&playlist), &grit_dir).await?;
+            cli::commands::vcs::push(Some(&playlist), force, &grit_dir).await?;
         }
-        Commands::Log => {
+        Commands::Log { reverse } => {
             let playlist = resolve_playlist(None, cli.playlist, &grit_dir)?;
-            cli::commands::vcs::log(Some(&playlist), &grit_dir).await?;
+            cli::commands::vcs::log(Some(&playlist), reverse, &grit_dir).await?;
         }
-        Commands::Pull => {
+        Commands::Activity { limit } => {
+            cli::commands::vcs::activity(limit, &grit_dir).await?;
+        }
+        Commands::Pull { refresh } => {
             let playlist = resolve_playlist(None, cli.playlist, &grit_dir)?;
-            cli::commands::vcs::pull(Some(&playlist), &grit_dir).await?;
+            cli::commands::vcs::pull(Some(&playlist), refresh, &grit_dir).await?;
         }
-        Commands::Diff { staged, remote } => {
+        Commands::Diff {
+            staged,
+            remote,
+            refresh,
+            visual,
+        } => {
             let playlist = resolve_playlist(None, cli.playlist, &grit_dir)?;
-            cli::commands::vcs::diff_cmd(Some(&playlist), &grit_dir, staged, remote).await?;
+            let options = cli::commands::vcs::DiffOptions {
+                staged,
+                remote,
+                refresh,
+                visual,
+            };
+            cli::commands::vcs::diff_cmd(Some(&playlist), &options, &grit_dir).await?;
         }
         Commands::Playlists { query } => {
             cli::commands::misc::playlists(query.as_deref(), &grit_dir).await?;
         }
+        Commands::Drop {
+            playlist,
+            remote,
+            yes,
+        } => {
+            let playlist = resolve_playlist(playlist, cli.playlist, &grit_dir)?;
+            cli::commands::misc::drop_playlist(&playlist, remote, yes, &grit_dir).await?;
+        }
         Commands::Switch { playlist } => {
             cli::commands::misc::switch(&playlist, &grit_dir).await?;
         }
         Commands::Curr => {
             cli::commands::misc::curr(&grit_dir).await?;
         }
+        Commands::Reflog { playlist } => {
+            let playlist = resolve_playlist(playlist, cli.playlist, &grit_dir)?;
+            cli::commands::vcs::reflog(Some(&playlist), &grit_dir).await?;
+        }
         Commands::Revert { hash, playlist } => {
             let playlist = resolve_playlist(playlist, cli.playlist, &grit_dir)?;
             cli::commands::vcs::revert(hash.as_deref(), Some(&playlist), &grit_dir).await?;
         }
-        Commands::Apply { file } => {
+        Commands::Apply { file, stdin } => {
             let playlist = resolve_playlist(None, cli.playlist, &grit_dir)?;
+            let file = if stdin {
+                "-".to_string()
+            } else {
+                file.unwrap_or_default()
+            };
             cli::commands::vcs::apply(&file, Some(&playlist), &grit_dir).await?;
         }
-        Commands::Play { playlist, shuffle } => {
+        Commands::Play {
+            playlist,
+            shuffle,
+            device,
+            detach,
+            paused,
+            volume,
+            loop_playlist,
+            start_at,
+            changes,
+            mini,
+        } => {
             let playlist = resolve_playlist(playlist, cli.playlist, &grit_dir)?;
-            cli::commands::play::run(Some(&playlist), shuffle, &grit_dir).await?;
+            let options = cli::commands::play::PlayOptions {
+                shuffle,
+                device: device.as_deref(),
+                detach,
+                paused,
+                volume,
+                loop_playlist,
+                start_at,
+                changes,
+                mini,
+            };
+            cli::commands::play::run(Some(&playlist), &options, &grit_dir).await?;
         }
     }
 
     Ok(())
 }
 
+/// Commands that take over the terminal with the player, so logs have to go
+/// to a file instead of stderr
+fn starts_tui(command: &Commands) -> bool {
+    match command {
+        Commands::Play { detach, .. } => !detach,
+        Commands::Find { play, play_nth, .. } => *play || play_nth.is_some(),
+        _ => false,
+    }
+}
+
+/// Where grit keeps its state: `--grit-dir`, then `GRIT_DIR`, then `./.grit`
+/// if it exists (where older versions always kept it), else the user data
+/// directory so commands work from anywhere.
+fn resolve_grit_dir(flag: Option<PathBuf>) -> PathBuf {
+    if let Some(dir) = flag.or_else(|| std::env::var_os("GRIT_DIR").map(PathBuf::from)) {
+        return dir;
+    }
+
+    let local = PathBuf::from(".grit");
+    if local.exists() {
+        return local;
+    }
+
+    directories::ProjectDirs::from("", "", "grit")
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .unwrap_or(local)
+}
+
 /// Resolves the playlist ID to use based on command-line argument,
 /// global option, or working playlist in config.
 fn resolve_playlist(
@@ -141,3 +360,14 @@ fn resolve_playlist(
         .or_else(|| crate::state::working_playlist::load(grit_dir).ok())
         .context("Playlist required (use --playlist, 'grit switch <id>', or run 'grit init' to set working playlist)")
 }
+
+/// Whether to print track ids: `--ids`/`--no-ids` win over the config default.
+fn show_ids(ids: bool, no_ids: bool, default: bool) -> bool {
+    if ids {
+        true
+    } else if no_ids {
+        false
+    } else {
+        default
+    }
+}
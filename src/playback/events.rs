@@ -1,7 +1,84 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum RepeatMode {
     #[default]
+    #[serde(alias = "off")]
     None,
+    #[serde(alias = "track")]
     One,
+    #[serde(alias = "context")]
     All,
 }
+
+impl RepeatMode {
+    /// Value of the `state` parameter for Spotify's repeat endpoint
+    pub fn spotify_state(&self) -> &'static str {
+        match self {
+            RepeatMode::None => "off",
+            RepeatMode::All => "context",
+            RepeatMode::One => "track",
+        }
+    }
+}
+
+impl fmt::Display for RepeatMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepeatMode::None => write!(f, "none"),
+            RepeatMode::One => write!(f, "one"),
+            RepeatMode::All => write!(f, "all"),
+        }
+    }
+}
+
+impl FromStr for RepeatMode {
+    type Err = anyhow::Error;
+
+    /// Accepts our own names as well as Spotify's (`off`/`track`/`context`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" | "off" => Ok(RepeatMode::None),
+            "one" | "track" => Ok(RepeatMode::One),
+            "all" | "context" => Ok(RepeatMode::All),
+            other => anyhow::bail!(
+                "Unknown repeat mode '{}' (expected none, one or all)",
+                other
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [RepeatMode; 3] = [RepeatMode::None, RepeatMode::One, RepeatMode::All];
+
+    #[test]
+    fn test_display_from_str_round_trip() {
+        for mode in ALL {
+            assert_eq!(mode.to_string().parse::<RepeatMode>().unwrap(), mode);
+            assert_eq!(mode.spotify_state().parse::<RepeatMode>().unwrap(), mode);
+        }
+        assert_eq!("ALL".parse::<RepeatMode>().unwrap(), RepeatMode::All);
+        assert!("sometimes".parse::<RepeatMode>().is_err());
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        for mode in ALL {
+            let yaml = serde_yaml::to_string(&mode).unwrap();
+            assert_eq!(yaml.trim(), mode.to_string());
+            assert_eq!(serde_yaml::from_str::<RepeatMode>(&yaml).unwrap(), mode);
+        }
+        assert_eq!(
+            serde_yaml::from_str::<RepeatMode>("context").unwrap(),
+            RepeatMode::All
+        );
+    }
+}
@@ -1,8 +1,14 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use anyhow::Result;
-use reqwest::Client;
+use async_trait::async_trait;
 use serde::Deserialize;
 use tokio::sync::mpsc;
 
+use crate::state::config::LyricsService;
+
 #[derive(Debug, Clone)]
 pub struct LyricLine {
     pub time_secs: f64,
@@ -13,6 +19,101 @@ pub struct LyricLine {
 pub struct Lyrics {
     pub lines: Vec<LyricLine>,
     pub plain: Option<String>,
+    /// Name of the source the lyrics came from
+    pub source: Option<&'static str>,
+}
+
+/// Which kind of lyrics a source returned for a track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LyricsKind {
+    Synced,
+    Plain,
+    NotFound,
+}
+
+impl LyricsKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LyricsKind::Synced => "synced",
+            LyricsKind::Plain => "plain",
+            LyricsKind::NotFound => "not found",
+        }
+    }
+}
+
+/// What a lyrics source can look a track up by
+#[derive(Debug, Clone)]
+pub struct LyricsQuery {
+    pub name: String,
+    pub artist: String,
+    pub duration_secs: u64,
+}
+
+/// Somewhere lyrics can be fetched from. Sources return empty `Lyrics`
+/// when they have nothing for a track, so the next one gets a turn.
+#[async_trait]
+pub trait LyricsSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn fetch(&self, query: &LyricsQuery) -> Result<Lyrics>;
+}
+
+/// lrclib.net, matched by name, artist and duration
+pub struct LrcLib;
+
+#[async_trait]
+impl LyricsSource for LrcLib {
+    fn name(&self) -> &'static str {
+        "lrclib"
+    }
+
+    async fn fetch(&self, query: &LyricsQuery) -> Result<Lyrics> {
+        fetch_lyrics(&query.name, &query.artist, query.duration_secs).await
+    }
+}
+
+/// The configured sources, in the order they should be tried
+pub fn sources(services: &[LyricsService]) -> Vec<Box<dyn LyricsSource>> {
+    services
+        .iter()
+        .map(|service| -> Box<dyn LyricsSource> {
+            match service {
+                LyricsService::Lrclib => Box::new(LrcLib),
+            }
+        })
+        .collect()
+}
+
+/// Try each source in turn, stopping at the first synced lyrics. Plain
+/// lyrics from an earlier source are kept in case nothing synced turns up.
+pub async fn fetch_from(sources: &[Box<dyn LyricsSource>], query: &LyricsQuery) -> Lyrics {
+    let mut best = Lyrics::default();
+    for source in sources {
+        match source.fetch(query).await {
+            Ok(lyrics) => {
+                best = prefer(best, lyrics);
+                if best.kind() == LyricsKind::Synced {
+                    break;
+                }
+            }
+            Err(e) => tracing::debug!("{} lyrics lookup failed: {:#}", source.name(), e),
+        }
+    }
+    best
+}
+
+/// Whichever of two results is more useful: synced beats plain beats none.
+/// Synced lyrics without plain text borrow it from the other result.
+fn prefer(best: Lyrics, candidate: Lyrics) -> Lyrics {
+    match (best.kind(), candidate.kind()) {
+        (LyricsKind::Synced, _)
+        | (_, LyricsKind::NotFound)
+        | (LyricsKind::Plain, LyricsKind::Plain) => best,
+        _ => Lyrics {
+            plain: candidate.plain.or(best.plain),
+            ..candidate
+        },
+    }
 }
 
 #[derive(Deserialize)]
@@ -21,9 +122,50 @@ struct LrcLibResponse {
     synced_lyrics: Option<String>,
     #[serde(rename = "plainLyrics")]
     plain_lyrics: Option<String>,
+    /// Seconds; only used to rank search results
+    #[serde(default)]
+    duration: Option<f64>,
+}
+
+impl LrcLibResponse {
+    fn has_lyrics(&self) -> bool {
+        self.synced_lyrics.is_some() || self.plain_lyrics.is_some()
+    }
+
+    fn into_lyrics(self) -> Lyrics {
+        let lines = self
+            .synced_lyrics
+            .as_ref()
+            .map(|s| parse_lrc(s))
+            .unwrap_or_default();
+
+        Lyrics {
+            lines,
+            plain: self.plain_lyrics,
+            source: Some("lrclib"),
+        }
+    }
 }
 
 impl Lyrics {
+    pub fn kind(&self) -> LyricsKind {
+        if !self.lines.is_empty() {
+            LyricsKind::Synced
+        } else if self.plain.is_some() {
+            LyricsKind::Plain
+        } else {
+            LyricsKind::NotFound
+        }
+    }
+
+    /// Source and kind, e.g. "lrclib synced"
+    pub fn label(&self) -> String {
+        match (self.kind(), self.source) {
+            (LyricsKind::NotFound, _) | (_, None) => self.kind().label().to_string(),
+            (kind, Some(source)) => format!("{} {}", source, kind.label()),
+        }
+    }
+
     pub fn current_line_index(&self, position_secs: f64) -> Option<usize> {
         if self.lines.is_empty() {
             return None;
@@ -87,7 +229,7 @@ pub async fn fetch_lyrics(
     artist_name: &str,
     duration_secs: u64,
 ) -> Result<Lyrics> {
-    let client = Client::new();
+    let client = crate::utils::http::client();
 
     let url = format!(
         "https://lrclib.net/api/get?track_name={}&artist_name={}&duration={}",
@@ -96,76 +238,195 @@ pub async fn fetch_lyrics(
         duration_secs
     );
 
-    let response = client
-        .get(&url)
-        .header("User-Agent", "grit/1.0")
-        .send()
-        .await?;
+    let response = client.get(&url).send().await?;
+
+    if response.status().is_success() {
+        let data: LrcLibResponse = response.json().await?;
+        if data.has_lyrics() {
+            return Ok(data.into_lyrics());
+        }
+    }
+
+    // `get` needs a near exact duration, which stored YouTube durations
+    // often miss; search and take the closest match instead
+    search_lyrics(track_name, artist_name, duration_secs).await
+}
+
+async fn search_lyrics(track_name: &str, artist_name: &str, duration_secs: u64) -> Result<Lyrics> {
+    let client = crate::utils::http::client();
+
+    let mut url = format!(
+        "https://lrclib.net/api/search?track_name={}",
+        urlencoding::encode(track_name)
+    );
+    if !artist_name.is_empty() {
+        url.push_str(&format!(
+            "&artist_name={}",
+            urlencoding::encode(artist_name)
+        ));
+    }
+
+    let response = client.get(&url).send().await?;
 
     if !response.status().is_success() {
         return Ok(Lyrics::default());
     }
 
-    let data: LrcLibResponse = response.json().await?;
+    let results: Vec<LrcLibResponse> = response.json().await?;
+
+    Ok(closest_match(results, duration_secs)
+        .map(LrcLibResponse::into_lyrics)
+        .unwrap_or_default())
+}
+
+/// Search result with lyrics whose duration is closest to `duration_secs`
+fn closest_match(results: Vec<LrcLibResponse>, duration_secs: u64) -> Option<LrcLibResponse> {
+    let target = duration_secs as f64;
+    results
+        .into_iter()
+        .filter(LrcLibResponse::has_lyrics)
+        .min_by(|a, b| {
+            let distance = |r: &LrcLibResponse| (r.duration.unwrap_or(f64::MAX) - target).abs();
+            distance(a).total_cmp(&distance(b))
+        })
+}
+
+/// Decorations in track/video titles that don't belong to the song name
+/// and make lrclib lookups miss. Lowercase.
+const TITLE_NOISE: &[&str] = &[
+    "(official video)",
+    "(official music video)",
+    "(official audio)",
+    "(lyric video)",
+    "(lyrics video)",
+    "(lyrics)",
+    "(audio)",
+    "(visualizer)",
+    "(official visualizer)",
+    "(music video)",
+    "[official video]",
+    "[official music video]",
+    "[official audio]",
+    "[lyric video]",
+    "[lyrics video]",
+    "[lyrics]",
+    "[audio]",
+    "[visualizer]",
+    "[official visualizer]",
+    "[music video]",
+    "official video",
+    "official music video",
+    "official audio",
+    "lyric video",
+    "lyrics video",
+    "music video",
+    "(hd)",
+    "(hq)",
+    "[hd]",
+    "[hq]",
+    "(4k)",
+    "[4k]",
+    "(remastered)",
+    "[remastered]",
+    "(remaster)",
+    "[remaster]",
+    "(live)",
+    "[live]",
+    "(acoustic)",
+    "[acoustic]",
+];
+
+/// Suffix keywords after " - " in Spotify names, e.g. "Song - Remastered 2011"
+const VERSION_SUFFIXES: &[&str] = &[
+    "remaster",
+    "radio edit",
+    "single version",
+    "album version",
+    "mono",
+    "stereo",
+    "remix",
+    "mix",
+    "live",
+    "acoustic",
+    "edit",
+    "version",
+];
+
+/// Drop "(feat. X)" / "[ft. X]" groups
+fn strip_featuring(title: &str) -> String {
+    let mut result = title.to_string();
+    loop {
+        let lower = result.to_ascii_lowercase();
+        let found = [
+            "(feat.",
+            "[feat.",
+            "(ft.",
+            "[ft.",
+            "(featuring",
+            "[featuring",
+        ]
+        .iter()
+        .filter_map(|p| lower.find(p))
+        .min();
+        let Some(start) = found else {
+            break;
+        };
+        let close = if result.as_bytes()[start] == b'(' {
+            ')'
+        } else {
+            ']'
+        };
+        let end = result[start..]
+            .find(close)
+            .map(|i| start + i + 1)
+            .unwrap_or(result.len());
+        result.replace_range(start..end, "");
+    }
+    result
+}
+
+/// Remove `pattern` (lowercase ASCII) wherever it appears, ignoring case
+fn remove_ignore_case(text: &str, pattern: &str) -> String {
+    let mut result = text.to_string();
+    while let Some(start) = result.to_ascii_lowercase().find(pattern) {
+        result.replace_range(start..start + pattern.len(), "");
+    }
+    result
+}
 
-    let lines = data
-        .synced_lyrics
-        .as_ref()
-        .map(|s| parse_lrc(s))
-        .unwrap_or_default();
+/// Track name as lrclib knows it: without featured artists, version
+/// suffixes like " - Remastered 2011" or " - Radio Edit", and the same
+/// decorations `clean_yt_title` strips. Only used for the lookup; the
+/// original name is still shown.
+pub fn normalize_track_name(original: &str) -> String {
+    let mut name = strip_featuring(original);
+
+    if let Some(idx) = name.find(" - ") {
+        // Match at word starts, so "Alive" isn't taken for "live"
+        let suffix = format!(" {}", name[idx + 3..].to_ascii_lowercase());
+        if VERSION_SUFFIXES
+            .iter()
+            .any(|k| suffix.contains(&format!(" {}", k)))
+        {
+            name.truncate(idx);
+        }
+    }
 
-    Ok(Lyrics {
-        lines,
-        plain: data.plain_lyrics,
-    })
+    for pattern in TITLE_NOISE {
+        name = remove_ignore_case(&name, pattern);
+    }
+
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        original.to_string()
+    } else {
+        name
+    }
 }
 
 pub fn clean_yt_title(title: &str) -> (String, Option<String>) {
-    let patterns = [
-        "(official video)",
-        "(official music video)",
-        "(official audio)",
-        "(lyric video)",
-        "(lyrics video)",
-        "(lyrics)",
-        "(audio)",
-        "(visualizer)",
-        "(official visualizer)",
-        "(music video)",
-        "[official video]",
-        "[official music video]",
-        "[official audio]",
-        "[lyric video]",
-        "[lyrics video]",
-        "[lyrics]",
-        "[audio]",
-        "[visualizer]",
-        "[official visualizer]",
-        "[music video]",
-        "official video",
-        "official music video",
-        "official audio",
-        "lyric video",
-        "lyrics video",
-        "music video",
-        "(hd)",
-        "(hq)",
-        "[hd]",
-        "[hq]",
-        "(4k)",
-        "[4k]",
-        "(remastered)",
-        "[remastered]",
-        "(remaster)",
-        "[remaster]",
-        "(live)",
-        "[live]",
-        "(acoustic)",
-        "[acoustic]",
-    ];
-
-    let mut cleaned = title.to_lowercase();
-    for p in patterns {
+    let mut cleaned = strip_featuring(title).to_lowercase();
+    for p in TITLE_NOISE {
         cleaned = cleaned.replace(p, "");
     }
 
@@ -195,32 +456,104 @@ pub fn clean_yt_title(title: &str) -> (String, Option<String>) {
     }
 }
 
-pub async fn fetch_lyrics_for_yt(title: &str, duration_secs: u64) -> Result<Lyrics> {
-    let (track, artist) = clean_yt_title(title);
-    let artist_str = artist.as_deref().unwrap_or("");
-    fetch_lyrics(&track, artist_str, duration_secs).await
-}
+/// How many finished lookups `LyricsFetcher` keeps, current and prefetched
+const LYRICS_CACHE_SIZE: usize = 8;
+/// How long a lookup that found nothing is trusted. Failed requests also end
+/// up as "not found", so the track is looked up again once this passes.
+const NOT_FOUND_TTL: Duration = Duration::from_secs(5 * 60);
 
+/// Runs lyrics lookups in the background. Results are kept by track id, so
+/// lyrics prefetched for upcoming tracks show up as soon as one starts.
 pub struct LyricsFetcher {
-    tx: mpsc::Sender<Lyrics>,
-    rx: mpsc::Receiver<Lyrics>,
+    tx: mpsc::UnboundedSender<LookupResult>,
+    rx: mpsc::UnboundedReceiver<LookupResult>,
     current_track_id: Option<String>,
+    sources: Arc<Vec<Box<dyn LyricsSource>>>,
+    /// Finished lookups, oldest first
+    cache: VecDeque<CachedLyrics>,
+    /// Track ids with a lookup in flight, and the number of the latest one
+    pending: HashMap<String, u64>,
+    next_lookup: u64,
+    /// Lyrics for the current track, waiting for `try_recv`
+    ready: Option<Lyrics>,
+}
+
+struct CachedLyrics {
+    track_id: String,
+    lyrics: Lyrics,
+    /// When a "not found" stops being served; found lyrics never expire
+    expires_at: Option<Instant>,
+}
+
+/// A finished lookup, tagged so it can be matched to the request that
+/// started it
+struct LookupResult {
+    track_id: String,
+    lookup: u64,
+    lyrics: Lyrics,
 }
 
 impl LyricsFetcher {
-    pub fn new() -> Self {
-        let (tx, rx) = mpsc::channel(1);
+    pub fn new(sources: Vec<Box<dyn LyricsSource>>) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
         Self {
             tx,
             rx,
             current_track_id: None,
+            sources: Arc::new(sources),
+            cache: VecDeque::new(),
+            pending: HashMap::new(),
+            next_lookup: 0,
+            ready: None,
         }
     }
 
+    /// Lyrics for the current track once they are in. Results for other
+    /// tracks are only cached, and ones a newer lookup for the same track
+    /// replaced (e.g. a manual re-fetch) are dropped.
     pub fn try_recv(&mut self) -> Option<Lyrics> {
-        self.rx.try_recv().ok()
+        while let Ok(result) = self.rx.try_recv() {
+            if self.pending.get(&result.track_id) != Some(&result.lookup) {
+                continue;
+            }
+            self.pending.remove(&result.track_id);
+            if self.current_track_id.as_deref() == Some(result.track_id.as_str()) {
+                self.ready = Some(result.lyrics.clone());
+            }
+            self.store(result.track_id, result.lyrics);
+        }
+        self.ready.take()
+    }
+
+    fn store(&mut self, track_id: String, lyrics: Lyrics) {
+        self.cache.retain(|entry| entry.track_id != track_id);
+        let expires_at =
+            (lyrics.kind() == LyricsKind::NotFound).then(|| Instant::now() + NOT_FOUND_TTL);
+        self.cache.push_back(CachedLyrics {
+            track_id,
+            lyrics,
+            expires_at,
+        });
+        if self.cache.len() > LYRICS_CACHE_SIZE {
+            self.cache.pop_front();
+        }
+    }
+
+    fn cached(&self, track_id: &str) -> Option<&Lyrics> {
+        let now = Instant::now();
+        self.cache
+            .iter()
+            .find(|entry| entry.track_id == track_id)
+            .filter(|entry| entry.expires_at.is_none_or(|at| now < at))
+            .map(|entry| &entry.lyrics)
+    }
+
+    /// Whether lyrics for the track are cached or on their way
+    fn is_known(&self, track_id: &str) -> bool {
+        self.pending.contains_key(track_id) || self.cached(track_id).is_some()
     }
 
+    /// Fetch lyrics for a Spotify track
     pub fn fetch_for_track(
         &mut self,
         track_id: &str,
@@ -232,15 +565,7 @@ impl LyricsFetcher {
             return;
         }
         self.current_track_id = Some(track_id.to_string());
-        let tx = self.tx.clone();
-        let name = track_name.to_string();
-        let artist = artist.to_string();
-        tokio::spawn(async move {
-            let lyrics = fetch_lyrics(&name, &artist, duration_secs)
-                .await
-                .unwrap_or_default();
-            let _ = tx.send(lyrics).await;
-        });
+        self.request(track_id, spotify_query(track_name, artist, duration_secs));
     }
 
     pub fn fetch_for_yt(&mut self, track_id: &str, title: &str, duration_secs: u64) {
@@ -248,18 +573,291 @@ impl LyricsFetcher {
             return;
         }
         self.current_track_id = Some(track_id.to_string());
+        self.request(track_id, yt_query(title, duration_secs));
+    }
+
+    /// Look up an upcoming Spotify track ahead of time
+    pub fn prefetch_track(
+        &mut self,
+        track_id: &str,
+        track_name: &str,
+        artist: &str,
+        duration_secs: u64,
+    ) {
+        if !self.is_known(track_id) {
+            self.spawn_fetch(track_id, spotify_query(track_name, artist, duration_secs));
+        }
+    }
+
+    /// Look up an upcoming YouTube track ahead of time
+    pub fn prefetch_yt(&mut self, track_id: &str, title: &str, duration_secs: u64) {
+        if !self.is_known(track_id) {
+            self.spawn_fetch(track_id, yt_query(title, duration_secs));
+        }
+    }
+
+    /// Serve the current track from the cache, or start a lookup unless one
+    /// is already running
+    fn request(&mut self, track_id: &str, query: LyricsQuery) {
+        if let Some(lyrics) = self.cached(track_id) {
+            self.ready = Some(lyrics.clone());
+        } else if !self.pending.contains_key(track_id) {
+            self.spawn_fetch(track_id, query);
+        }
+    }
+
+    fn spawn_fetch(&mut self, track_id: &str, query: LyricsQuery) {
+        let lookup = self.next_lookup;
+        self.next_lookup += 1;
+        self.pending.insert(track_id.to_string(), lookup);
+
         let tx = self.tx.clone();
-        let title = title.to_string();
+        let sources = Arc::clone(&self.sources);
+        let track_id = track_id.to_string();
         tokio::spawn(async move {
-            let lyrics = fetch_lyrics_for_yt(&title, duration_secs)
-                .await
-                .unwrap_or_default();
-            let _ = tx.send(lyrics).await;
+            let lyrics = fetch_from(&sources, &query).await;
+            let _ = tx.send(LookupResult {
+                track_id,
+                lookup,
+                lyrics,
+            });
         });
     }
 
+    /// Fetch with a user-corrected name and artist, even if lyrics for this
+    /// track were already fetched.
+    pub fn fetch_manual(
+        &mut self,
+        track_id: &str,
+        track_name: &str,
+        artist: &str,
+        duration_secs: u64,
+    ) {
+        self.reset();
+        self.cache.retain(|entry| entry.track_id != track_id);
+        self.current_track_id = Some(track_id.to_string());
+        // Used verbatim: the user already corrected the name
+        self.spawn_fetch(
+            track_id,
+            LyricsQuery {
+                name: track_name.to_string(),
+                artist: artist.to_string(),
+                duration_secs,
+            },
+        );
+    }
+
+    /// Forget the current track. Cached and in-flight lookups are kept.
     pub fn reset(&mut self) {
         self.current_track_id = None;
-        while self.rx.try_recv().is_ok() {}
+        self.ready = None;
+    }
+}
+
+fn spotify_query(track_name: &str, artist: &str, duration_secs: u64) -> LyricsQuery {
+    LyricsQuery {
+        name: normalize_track_name(track_name),
+        artist: artist.to_string(),
+        duration_secs,
+    }
+}
+
+fn yt_query(title: &str, duration_secs: u64) -> LyricsQuery {
+    let (name, artist) = clean_yt_title(title);
+    LyricsQuery {
+        name,
+        artist: artist.unwrap_or_default(),
+        duration_secs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_track_name() {
+        let cases = [
+            ("Here Comes The Sun - Remastered 2009", "Here Comes The Sun"),
+            ("Bohemian Rhapsody - Remastered 2011", "Bohemian Rhapsody"),
+            ("Mr. Brightside - Radio Edit", "Mr. Brightside"),
+            ("Sicko Mode (feat. Drake)", "Sicko Mode"),
+            (
+                "Old Town Road [ft. Billy Ray Cyrus] - Remix",
+                "Old Town Road",
+            ),
+            ("Hurt (Live)", "Hurt"),
+            ("Stay With Me", "Stay With Me"),
+            ("Someone - Alive", "Someone - Alive"),
+            // A dash that isn't a version marker stays
+            (
+                "Mambo No. 5 - A Little Bit Of...",
+                "Mambo No. 5 - A Little Bit Of...",
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_track_name(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_closest_match_prefers_nearest_duration() {
+        let result = |duration: Option<f64>, plain: Option<&str>| LrcLibResponse {
+            synced_lyrics: None,
+            plain_lyrics: plain.map(str::to_string),
+            duration,
+        };
+        let results = vec![
+            result(Some(180.0), Some("far")),
+            result(Some(212.0), None),
+            result(Some(205.0), Some("near")),
+            result(None, Some("unknown")),
+        ];
+
+        let best = closest_match(results, 212).unwrap();
+        assert_eq!(best.plain_lyrics.as_deref(), Some("near"));
+        assert!(closest_match(vec![], 212).is_none());
+    }
+
+    #[test]
+    fn test_prefer_synced_over_plain() {
+        let plain = |text: &str, source| Lyrics {
+            lines: Vec::new(),
+            plain: Some(text.to_string()),
+            source: Some(source),
+        };
+        let synced = Lyrics {
+            lines: parse_lrc("[00:01.00] hello"),
+            plain: None,
+            source: Some("fallback"),
+        };
+
+        let best = prefer(Lyrics::default(), plain("first", "lrclib"));
+        let best = prefer(best, plain("second", "fallback"));
+        assert_eq!(best.plain.as_deref(), Some("first"));
+
+        let best = prefer(best, synced);
+        assert_eq!(best.label(), "fallback synced");
+        assert_eq!(best.plain.as_deref(), Some("first"));
+
+        let best = prefer(best, plain("later", "lrclib"));
+        assert_eq!(best.label(), "fallback synced");
+    }
+
+    /// Answers each query with its name as plain lyrics, once the test
+    /// opens the gate for that name
+    struct Gated {
+        gates: std::sync::Mutex<HashMap<String, tokio::sync::oneshot::Receiver<()>>>,
+    }
+
+    impl Gated {
+        fn new(names: &[&str]) -> (Self, HashMap<String, tokio::sync::oneshot::Sender<()>>) {
+            let mut gates = HashMap::new();
+            let mut openers = HashMap::new();
+            for name in names {
+                let (open, gate) = tokio::sync::oneshot::channel();
+                gates.insert(name.to_string(), gate);
+                openers.insert(name.to_string(), open);
+            }
+            let source = Self {
+                gates: std::sync::Mutex::new(gates),
+            };
+            (source, openers)
+        }
+    }
+
+    #[async_trait]
+    impl LyricsSource for Gated {
+        fn name(&self) -> &'static str {
+            "gated"
+        }
+
+        async fn fetch(&self, query: &LyricsQuery) -> Result<Lyrics> {
+            let gate = self.gates.lock().unwrap().remove(&query.name);
+            if let Some(gate) = gate {
+                let _ = gate.await;
+            }
+            Ok(Lyrics {
+                plain: Some(query.name.clone()),
+                ..Lyrics::default()
+            })
+        }
+    }
+
+    /// Wait for the next lookup to finish, then return what `try_recv` makes
+    /// of it: the current track's lyrics, or nothing for a stale result
+    async fn landed(fetcher: &mut LyricsFetcher) -> Option<String> {
+        let result = fetcher.rx.recv().await.unwrap();
+        // Back onto the channel, for try_recv to sort out
+        let _ = fetcher.tx.send(result);
+        fetcher.try_recv().and_then(|lyrics| lyrics.plain)
+    }
+
+    #[tokio::test]
+    async fn test_stale_lookups_are_dropped() {
+        let (source, mut gates) = Gated::new(&["auto", "manual", "slow", "fast"]);
+        let mut open = |name: &str| gates.remove(name).unwrap().send(()).unwrap();
+        let mut fetcher = LyricsFetcher::new(vec![Box::new(source)]);
+
+        // A manual re-fetch finishing first isn't overwritten by the
+        // automatic lookup it replaced
+        fetcher.fetch_for_yt("a", "auto", 200);
+        fetcher.fetch_manual("a", "manual", "", 200);
+        open("manual");
+        assert_eq!(landed(&mut fetcher).await.as_deref(), Some("manual"));
+        open("auto");
+        assert_eq!(landed(&mut fetcher).await, None);
+
+        // Skipping on before a lookup lands never shows it for the new track
+        fetcher.reset();
+        fetcher.fetch_for_yt("b", "slow", 200);
+        fetcher.reset();
+        fetcher.fetch_for_yt("c", "fast", 200);
+        open("slow");
+        assert_eq!(landed(&mut fetcher).await, None);
+        open("fast");
+        assert_eq!(landed(&mut fetcher).await.as_deref(), Some("fast"));
+
+        // but it is kept for when that track comes up
+        fetcher.reset();
+        fetcher.fetch_for_yt("b", "slow", 200);
+        assert_eq!(
+            fetcher.try_recv().and_then(|l| l.plain).as_deref(),
+            Some("slow")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_not_found_is_looked_up_again_later() {
+        // No sources: every lookup finds nothing
+        let mut fetcher = LyricsFetcher::new(Vec::new());
+        fetcher.fetch_for_yt("a", "missing", 200);
+        assert_eq!(landed(&mut fetcher).await, None);
+        assert!(fetcher.is_known("a"));
+
+        fetcher.cache[0].expires_at = Some(Instant::now());
+        assert!(!fetcher.is_known("a"));
+        fetcher.prefetch_yt("a", "missing", 200);
+        assert!(fetcher.pending.contains_key("a"));
+
+        // Lyrics that were found stay cached
+        let (source, _) = Gated::new(&[]);
+        let mut fetcher = LyricsFetcher::new(vec![Box::new(source)]);
+        fetcher.fetch_for_yt("b", "found", 200);
+        assert_eq!(landed(&mut fetcher).await.as_deref(), Some("found"));
+        assert_eq!(fetcher.cache[0].expires_at, None);
+    }
+
+    #[test]
+    fn test_normalize_never_empties_name() {
+        assert_eq!(normalize_track_name("(Live)"), "(Live)");
+    }
+
+    #[test]
+    fn test_clean_yt_title_strips_featuring() {
+        let (title, artist) =
+            clean_yt_title("Calvin Harris - Feels (feat. Pharrell) [Official Video]");
+        assert_eq!(title, "feels");
+        assert_eq!(artist.as_deref(), Some("calvin harris"));
     }
 }
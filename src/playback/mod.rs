@@ -1,10 +1,16 @@
 pub mod events;
 pub mod lyrics;
+pub mod mpris;
 pub mod mpv;
+pub mod notify;
+pub mod now_playing;
 pub mod queue;
 pub mod spotify;
 
-pub use lyrics::{Lyrics, LyricsFetcher};
+pub use lyrics::{Lyrics, LyricsFetcher, LyricsKind};
+pub use mpris::{MediaCommand, MediaControls};
 pub use mpv::{fetch_audio_url, MpvPlayer};
+pub use notify::NowPlayingNotifier;
+pub use now_playing::NowPlayingFile;
 pub use queue::Queue;
-pub use spotify::SpotifyPlayer;
+pub use spotify::{PlaybackState, PlayingTrack, SpotifyPlayer};
@@ -0,0 +1,456 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+use crate::provider::Track;
+
+/// Playback request from a desktop media key or widget
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaCommand {
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Next,
+    Previous,
+    /// Relative to the current position, in seconds
+    SeekBy(f64),
+    /// Absolute position in the current track, in seconds
+    SeekTo(f64),
+}
+
+/// How far the position may land from where playing on would have taken it
+/// before it counts as a seek
+const SEEK_JUMP_SECS: f64 = 1.5;
+
+/// What the desktop sees of the player
+#[derive(Debug, Clone, Default, PartialEq)]
+struct MediaState {
+    track_index: usize,
+    title: String,
+    artists: Vec<String>,
+    duration_secs: f64,
+    position_secs: f64,
+    paused: bool,
+    has_track: bool,
+}
+
+impl MediaState {
+    fn same_track(&self, other: &MediaState) -> bool {
+        self.has_track
+            && other.has_track
+            && self.track_index == other.track_index
+            && self.title == other.title
+    }
+
+    /// Whether `next`, `elapsed_secs` later, shows the position jumping
+    /// within the same track rather than moving on by playing
+    fn seeked_to(&self, next: &MediaState, elapsed_secs: f64) -> bool {
+        let played = if self.paused { 0.0 } else { elapsed_secs };
+        self.same_track(next)
+            && (next.position_secs - (self.position_secs + played)).abs() > SEEK_JUMP_SECS
+    }
+}
+
+/// MPRIS2 (`org.mpris.MediaPlayer2.grit`) on the session bus, so media keys
+/// and desktop widgets can control the player. A no-op off Linux or when no
+/// session bus is running.
+pub struct MediaControls {
+    state: Arc<Mutex<MediaState>>,
+    commands: Option<UnboundedReceiver<MediaCommand>>,
+    updated_at: Option<Instant>,
+    #[cfg(target_os = "linux")]
+    connection: Option<zbus::Connection>,
+}
+
+impl MediaControls {
+    /// Controls that never receive anything
+    pub fn disabled() -> Self {
+        Self {
+            state: Arc::default(),
+            commands: None,
+            updated_at: None,
+            #[cfg(target_os = "linux")]
+            connection: None,
+        }
+    }
+
+    /// Register on the session bus, falling back to disabled controls
+    pub async fn start() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            let state = Arc::<Mutex<MediaState>>::default();
+            let (tx, rx) = mpsc::unbounded_channel();
+            match linux::serve(state.clone(), tx).await {
+                Ok(connection) => Self {
+                    state,
+                    commands: Some(rx),
+                    updated_at: None,
+                    connection: Some(connection),
+                },
+                Err(e) => {
+                    tracing::debug!("MPRIS unavailable: {}", e);
+                    Self::disabled()
+                }
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        Self::disabled()
+    }
+
+    pub fn try_recv(&mut self) -> Option<MediaCommand> {
+        self.commands.as_mut()?.try_recv().ok()
+    }
+
+    /// Publish the player state; call on every loop tick. Track and pause
+    /// changes are announced to listeners, and so are seeks; otherwise the
+    /// position is only read on demand.
+    pub fn update(
+        &mut self,
+        index: usize,
+        track: Option<&Track>,
+        position_secs: f64,
+        paused: bool,
+    ) {
+        if self.commands.is_none() {
+            return;
+        }
+        let next = MediaState {
+            track_index: index,
+            title: track.map(|t| t.name.clone()).unwrap_or_default(),
+            artists: track.map(|t| t.artists.clone()).unwrap_or_default(),
+            duration_secs: track.map_or(0.0, |t| t.duration_ms as f64 / 1000.0),
+            position_secs,
+            paused,
+            has_track: track.is_some(),
+        };
+
+        let now = Instant::now();
+        let elapsed = self
+            .updated_at
+            .map_or(0.0, |at| now.duration_since(at).as_secs_f64());
+        self.updated_at = Some(now);
+
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let changed = state.track_index != next.track_index
+            || state.title != next.title
+            || state.paused != next.paused
+            || state.has_track != next.has_track;
+        let seeked = state.seeked_to(&next, elapsed);
+        *state = next;
+        drop(state);
+
+        #[cfg(target_os = "linux")]
+        if changed || seeked {
+            if let Some(connection) = self.connection.clone() {
+                tokio::spawn(async move {
+                    let result = if seeked {
+                        linux::announce_seek(&connection, position_secs).await
+                    } else {
+                        linux::announce_changes(&connection).await
+                    };
+                    if let Err(e) = result {
+                        tracing::debug!("Failed to signal MPRIS change: {}", e);
+                    }
+                });
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = (changed, seeked);
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use tokio::sync::mpsc::UnboundedSender;
+    use zbus::object_server::SignalEmitter;
+    use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+    use super::{MediaCommand, MediaState};
+
+    const BUS_NAME: &str = "org.mpris.MediaPlayer2.grit";
+    const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+    struct Root;
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2")]
+    impl Root {
+        fn raise(&self) {}
+
+        fn quit(&self) {}
+
+        #[zbus(property)]
+        fn can_quit(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn can_raise(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn has_track_list(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn identity(&self) -> String {
+            "grit".to_string()
+        }
+
+        #[zbus(property)]
+        fn supported_uri_schemes(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        #[zbus(property)]
+        fn supported_mime_types(&self) -> Vec<String> {
+            Vec::new()
+        }
+    }
+
+    struct Player {
+        state: Arc<Mutex<MediaState>>,
+        commands: UnboundedSender<MediaCommand>,
+    }
+
+    impl Player {
+        fn send(&self, command: MediaCommand) {
+            let _ = self.commands.send(command);
+        }
+
+        fn state(&self) -> MediaState {
+            self.state.lock().map(|s| s.clone()).unwrap_or_default()
+        }
+    }
+
+    /// MPRIS wants an object path per track; the playlist position will do
+    fn track_path(index: usize) -> String {
+        format!("/org/grit/track/{}", index)
+    }
+
+    fn micros(secs: f64) -> i64 {
+        (secs * 1_000_000.0) as i64
+    }
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl Player {
+        fn next(&self) {
+            self.send(MediaCommand::Next);
+        }
+
+        fn previous(&self) {
+            self.send(MediaCommand::Previous);
+        }
+
+        fn pause(&self) {
+            self.send(MediaCommand::Pause);
+        }
+
+        fn play_pause(&self) {
+            self.send(MediaCommand::PlayPause);
+        }
+
+        fn stop(&self) {
+            self.send(MediaCommand::Stop);
+        }
+
+        fn play(&self) {
+            self.send(MediaCommand::Play);
+        }
+
+        fn seek(&self, offset: i64) {
+            self.send(MediaCommand::SeekBy(offset as f64 / 1_000_000.0));
+        }
+
+        fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+            // Ignored for a track that is no longer playing, per the spec
+            if track_id.as_str() == track_path(self.state().track_index) {
+                self.send(MediaCommand::SeekTo(position as f64 / 1_000_000.0));
+            }
+        }
+
+        fn open_uri(&self, _uri: String) {}
+
+        #[zbus(signal)]
+        async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+        #[zbus(property)]
+        fn playback_status(&self) -> String {
+            let state = self.state();
+            if !state.has_track {
+                "Stopped"
+            } else if state.paused {
+                "Paused"
+            } else {
+                "Playing"
+            }
+            .to_string()
+        }
+
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            let state = self.state();
+            let mut metadata = HashMap::new();
+            if !state.has_track {
+                return metadata;
+            }
+
+            let entries = [
+                (
+                    "mpris:trackid",
+                    ObjectPath::try_from(track_path(state.track_index)).map(Value::from),
+                ),
+                ("mpris:length", Ok(Value::from(micros(state.duration_secs)))),
+                ("xesam:title", Ok(Value::from(state.title))),
+                ("xesam:artist", Ok(Value::from(state.artists))),
+            ];
+            for (key, value) in entries {
+                if let Some(value) = value.ok().and_then(|v| OwnedValue::try_from(v).ok()) {
+                    metadata.insert(key.to_string(), value);
+                }
+            }
+            metadata
+        }
+
+        #[zbus(property(emits_changed_signal = "false"))]
+        fn position(&self) -> i64 {
+            micros(self.state().position_secs)
+        }
+
+        #[zbus(property)]
+        fn rate(&self) -> f64 {
+            1.0
+        }
+
+        #[zbus(property)]
+        fn minimum_rate(&self) -> f64 {
+            1.0
+        }
+
+        #[zbus(property)]
+        fn maximum_rate(&self) -> f64 {
+            1.0
+        }
+
+        #[zbus(property)]
+        fn volume(&self) -> f64 {
+            1.0
+        }
+
+        #[zbus(property)]
+        fn can_go_next(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn can_go_previous(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn can_play(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn can_pause(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn can_seek(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn can_control(&self) -> bool {
+            true
+        }
+    }
+
+    pub(super) async fn serve(
+        state: Arc<Mutex<MediaState>>,
+        commands: UnboundedSender<MediaCommand>,
+    ) -> zbus::Result<zbus::Connection> {
+        zbus::connection::Builder::session()?
+            .name(BUS_NAME)?
+            .serve_at(OBJECT_PATH, Root)?
+            .serve_at(OBJECT_PATH, Player { state, commands })?
+            .build()
+            .await
+    }
+
+    /// Emit `PropertiesChanged` for the status and metadata
+    pub(super) async fn announce_changes(connection: &zbus::Connection) -> zbus::Result<()> {
+        let player = connection
+            .object_server()
+            .interface::<_, Player>(OBJECT_PATH)
+            .await?;
+        let emitter = player.signal_emitter();
+        let iface = player.get().await;
+        iface.playback_status_changed(emitter).await?;
+        iface.metadata_changed(emitter).await
+    }
+
+    /// Emit `Seeked`, which MPRIS wants whenever the position jumps
+    pub(super) async fn announce_seek(
+        connection: &zbus::Connection,
+        position_secs: f64,
+    ) -> zbus::Result<()> {
+        let player = connection
+            .object_server()
+            .interface::<_, Player>(OBJECT_PATH)
+            .await?;
+        Player::seeked(player.signal_emitter(), micros(position_secs)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seek_is_a_jump_within_the_same_track() {
+        let playing = MediaState {
+            title: "Song".to_string(),
+            duration_secs: 200.0,
+            position_secs: 10.0,
+            has_track: true,
+            ..MediaState::default()
+        };
+        let at = |position_secs| MediaState {
+            position_secs,
+            ..playing.clone()
+        };
+
+        // Playing on, give or take a poll correction
+        assert!(!playing.seeked_to(&at(12.0), 2.0));
+        assert!(!playing.seeked_to(&at(13.0), 2.0));
+        // Jumped ahead or back
+        assert!(playing.seeked_to(&at(60.0), 2.0));
+        assert!(playing.seeked_to(&at(0.0), 0.5));
+
+        // A paused player doesn't move on its own
+        let paused = MediaState {
+            paused: true,
+            ..playing.clone()
+        };
+        assert!(paused.seeked_to(&at(12.0), 2.0));
+
+        // A new track starting over isn't a seek
+        let next = MediaState {
+            track_index: 1,
+            position_secs: 0.0,
+            ..playing.clone()
+        };
+        assert!(!playing.seeked_to(&next, 2.0));
+    }
+}
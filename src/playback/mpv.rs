@@ -1,3 +1,66 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A resolved stream URL is only reused while it stays valid at least this
+/// long, so playback and seeking don't run into the expiry
+const EXPIRY_MARGIN_SECS: u64 = 5 * 60;
+
+/// Stream URLs resolved by yt-dlp this session, by YouTube URL
+fn resolved_urls() -> &'static Mutex<HashMap<String, String>> {
+    static RESOLVED: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    RESOLVED.get_or_init(Default::default)
+}
+
+/// Unix time a googlevideo URL stops working, from its `expire` parameter
+/// (`?expire=123` or, in manifest-style URLs, `/expire/123/`)
+fn url_expiry(url: &str) -> Option<u64> {
+    let query = url
+        .split_once('?')
+        .map(|(_, query)| query)
+        .unwrap_or_default();
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("expire="))
+        .or_else(|| url.split_once("/expire/").map(|(_, rest)| rest))
+        .and_then(|value| {
+            let digits = value.split(|c: char| !c.is_ascii_digit()).next()?;
+            digits.parse().ok()
+        })
+}
+
+/// Whether `url` is still good for a while at unix time `now`. URLs without
+/// an expiry are never reused, since there's no telling when they lapse.
+fn is_fresh(url: &str, now: u64) -> bool {
+    url_expiry(url).is_some_and(|expire| expire > now + EXPIRY_MARGIN_SECS)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn cached_audio_url(youtube_url: &str) -> Option<String> {
+    let mut cache = resolved_urls().lock().ok()?;
+    match cache.get(youtube_url) {
+        Some(url) if is_fresh(url, now_secs()) => Some(url.clone()),
+        Some(_) => {
+            cache.remove(youtube_url);
+            None
+        }
+        None => None,
+    }
+}
+
+fn cache_audio_url(youtube_url: &str, audio_url: &str) {
+    if let Ok(mut cache) = resolved_urls().lock() {
+        cache.retain(|_, url| is_fresh(url, now_secs()));
+        cache.insert(youtube_url.to_string(), audio_url.to_string());
+    }
+}
+
 #[cfg(unix)]
 mod unix {
     use anyhow::{Context, Result};
@@ -18,6 +81,9 @@ mod unix {
         pub id: Option<i64>,
         #[serde(default)]
         pub data: Option<serde_json::Value>,
+        /// Why an `end-file` with reason `error` failed
+        #[serde(default)]
+        pub file_error: Option<String>,
     }
 
     #[derive(Debug, Deserialize)]
@@ -75,7 +141,20 @@ mod unix {
         Ok(())
     }
 
+    /// Stream URL for a YouTube video, reusing one resolved earlier this
+    /// session until it's close to expiring
     pub async fn fetch_audio_url(youtube_url: &str) -> Result<String> {
+        if let Some(url) = super::cached_audio_url(youtube_url) {
+            tracing::debug!("Reusing resolved stream for {}", youtube_url);
+            return Ok(url);
+        }
+
+        let url = resolve_audio_url(youtube_url).await?;
+        super::cache_audio_url(youtube_url, &url);
+        Ok(url)
+    }
+
+    async fn resolve_audio_url(youtube_url: &str) -> Result<String> {
         use tokio::process::Command as TokioCommand;
         use tokio::time::{timeout, Duration};
 
@@ -88,6 +167,8 @@ mod unix {
                 "--no-playlist",
                 youtube_url,
             ])
+            // so aborting a pending resolution doesn't leave yt-dlp running
+            .kill_on_drop(true)
             .output();
 
         let output = timeout(Duration::from_secs(15), fetch)
@@ -209,6 +290,18 @@ mod unix {
         }
 
         pub async fn load(&mut self, url: &str) -> Result<()> {
+            self.load_from(url, 0.0).await
+        }
+
+        /// Load `url` and start playing `start_secs` into it
+        pub async fn load_from(&mut self, url: &str, start_secs: f64) -> Result<()> {
+            // `start` applies to every later file too, so always set it
+            self.send_command(vec![
+                json!("set_property"),
+                json!("start"),
+                json!(format!("{:.1}", start_secs.max(0.0))),
+            ])
+            .await?;
             self.send_command(vec![json!("loadfile"), json!(url), json!("replace")])
                 .await?;
             self.send_command(vec![json!("set_property"), json!("pause"), json!(false)])
@@ -228,6 +321,12 @@ mod unix {
                 .await
         }
 
+        /// Set the volume, 0-100
+        pub async fn set_volume(&mut self, percent: u8) -> Result<()> {
+            self.send_command(vec![json!("set_property"), json!("volume"), json!(percent)])
+                .await
+        }
+
         pub async fn seek(&mut self, seconds: i64) -> Result<()> {
             self.send_command(vec![json!("seek"), json!(seconds), json!("relative")])
                 .await
@@ -279,6 +378,22 @@ mod unix {
             false
         }
 
+        /// The current file stopped because of an error rather than reaching
+        /// its end
+        pub fn is_track_error(event: &MpvEvent) -> bool {
+            event.event == "end-file" && event.reason.as_deref() == Some("error")
+        }
+
+        /// A track error caused by the audio output, e.g. headphones being
+        /// unplugged, as opposed to a bad stream
+        pub fn is_audio_device_error(event: &MpvEvent) -> bool {
+            Self::is_track_error(event)
+                && event.file_error.as_deref().is_some_and(|error| {
+                    let error = error.to_lowercase();
+                    error.contains("audio output") || error.contains("audio device")
+                })
+        }
+
         pub async fn quit(&mut self) -> Result<()> {
             self.send_command(vec![json!("quit")]).await
         }
@@ -295,5 +410,47 @@ mod unix {
 #[cfg(unix)]
 pub use unix::*;
 
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn event(json: &str) -> MpvEvent {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_track_errors_are_not_track_ends() {
+        let eof = event(r#"{"event":"end-file","reason":"eof"}"#);
+        let device = event(
+            r#"{"event":"end-file","reason":"error","file_error":"audio output initialization failed"}"#,
+        );
+        let stream =
+            event(r#"{"event":"end-file","reason":"error","file_error":"loading failed"}"#);
+
+        assert!(MpvPlayer::is_track_finished(&eof));
+        assert!(!MpvPlayer::is_track_error(&eof));
+
+        assert!(!MpvPlayer::is_track_finished(&device));
+        assert!(MpvPlayer::is_track_error(&device));
+        assert!(MpvPlayer::is_audio_device_error(&device));
+
+        assert!(MpvPlayer::is_track_error(&stream));
+        assert!(!MpvPlayer::is_audio_device_error(&stream));
+    }
+
+    #[test]
+    fn test_resolved_urls_are_reused_until_close_to_expiry() {
+        let query = "https://rr1.googlevideo.com/videoplayback?expire=10000&ei=x&itag=251";
+        let path = "https://manifest.googlevideo.com/api/manifest/hls/expire/10000/ei/x";
+        assert_eq!(url_expiry(query), Some(10_000));
+        assert_eq!(url_expiry(path), Some(10_000));
+        assert_eq!(url_expiry("https://example.com/audio.webm"), None);
+
+        assert!(is_fresh(query, 10_000 - EXPIRY_MARGIN_SECS - 1));
+        assert!(!is_fresh(query, 10_000 - EXPIRY_MARGIN_SECS));
+        assert!(!is_fresh("https://example.com/audio.webm", 0));
+    }
+}
+
 #[cfg(not(unix))]
 compile_error!("Playback is currently only supported on Unix systems (Linux/macOS). Windows support coming soon.");
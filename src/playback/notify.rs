@@ -0,0 +1,46 @@
+use notify_rust::Notification;
+
+use crate::provider::Track;
+
+/// Shows a desktop notification whenever the playing track changes
+pub struct NowPlayingNotifier {
+    enabled: bool,
+    last_track_id: Option<String>,
+}
+
+impl NowPlayingNotifier {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            last_track_id: None,
+        }
+    }
+
+    /// Call on every loop tick with the current track; only a change of
+    /// track fires a notification.
+    pub fn update(&mut self, track: Option<&Track>) {
+        let Some(track) = track else {
+            return;
+        };
+        if !self.enabled || self.last_track_id.as_deref() == Some(track.id.as_str()) {
+            return;
+        }
+        self.last_track_id = Some(track.id.clone());
+        show(track);
+    }
+}
+
+/// Send the notification in the background. Failures (no notification
+/// daemon) are ignored, it is only a convenience.
+fn show(track: &Track) {
+    let summary = track.name.clone();
+    let body = track.artists.join(", ");
+
+    tokio::task::spawn_blocking(move || {
+        Notification::new()
+            .appname("grit")
+            .summary(&summary)
+            .body(&body)
+            .show()
+    });
+}
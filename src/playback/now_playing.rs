@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::provider::Track;
+
+/// At most one write per interval while only the position moves
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What status bars read from `playback.now_playing_file`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct NowPlaying {
+    title: String,
+    artists: Vec<String>,
+    /// Whole seconds into the track
+    position: u64,
+    duration: u64,
+    paused: bool,
+}
+
+/// Mirrors the playing track into a JSON file for polybar, waybar, tmux and
+/// the like. Track and pause changes are written straight away; position
+/// updates are throttled.
+pub struct NowPlayingFile {
+    path: Option<PathBuf>,
+    last: Option<NowPlaying>,
+    last_write: Option<Instant>,
+}
+
+impl NowPlayingFile {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            last: None,
+            last_write: None,
+        }
+    }
+
+    /// Call on every loop tick with the current state
+    pub fn update(&mut self, track: Option<&Track>, position_secs: f64, paused: bool) {
+        let (Some(path), Some(track)) = (&self.path, track) else {
+            return;
+        };
+        let state = NowPlaying {
+            title: track.name.clone(),
+            artists: track.artists.clone(),
+            position: position_secs as u64,
+            duration: track.duration_ms / 1000,
+            paused,
+        };
+
+        let urgent = self.last.as_ref().is_none_or(|last| {
+            last.title != state.title || last.artists != state.artists || last.paused != paused
+        });
+        let due = self
+            .last_write
+            .is_none_or(|at| at.elapsed() >= WRITE_INTERVAL);
+        if self.last.as_ref() == Some(&state) || !(urgent || due) {
+            return;
+        }
+
+        if let Err(e) = write(path, &state) {
+            tracing::debug!("Failed to write now-playing file: {:#}", e);
+        }
+        self.last = Some(state);
+        self.last_write = Some(Instant::now());
+    }
+
+    /// Remove the file when playback ends, so nothing stale is shown
+    pub fn clear(&self) {
+        if let Some(path) = &self.path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Write through a temp file and rename, so readers never see half a file
+fn write(path: &PathBuf, state: &NowPlaying) -> Result<()> {
+    let json = serde_json::to_string(state)?;
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, json).with_context(|| format!("Failed to write {:?}", tmp))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn read(path: &PathBuf) -> serde_json::Value {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_position_writes_are_throttled() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("now-playing.json");
+        let track = Track {
+            duration_ms: 200_000,
+            ..Track::test("t1")
+        };
+        let mut file = NowPlayingFile::new(Some(path.clone()));
+
+        file.update(Some(&track), 1.0, false);
+        assert_eq!(read(&path)["position"], 1);
+        assert_eq!(read(&path)["duration"], 200);
+
+        // Position alone waits for the interval
+        file.update(Some(&track), 2.0, false);
+        assert_eq!(read(&path)["position"], 1);
+
+        // Pausing is shown immediately
+        file.update(Some(&track), 2.0, true);
+        assert_eq!(read(&path)["paused"], true);
+        assert_eq!(read(&path)["position"], 2);
+
+        file.clear();
+        assert!(!path.exists());
+    }
+}
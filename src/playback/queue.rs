@@ -7,6 +7,8 @@ pub struct Queue {
     play_order: Vec<usize>,
     pub shuffle: bool,
     pub repeat: RepeatMode,
+    /// Step over explicit tracks in `next` and `previous`
+    pub skip_explicit: bool,
 }
 
 impl Queue {
@@ -18,9 +20,18 @@ impl Queue {
             play_order,
             shuffle: false,
             repeat: RepeatMode::None,
+            skip_explicit: false,
         }
     }
 
+    /// Whether the current track is one `skip_explicit` steps over. Never
+    /// true when every track is explicit, so skipping can't loop forever.
+    pub fn skips_current(&self) -> bool {
+        self.skip_explicit
+            && self.current_track().is_some_and(Track::is_explicit)
+            && self.tracks.iter().any(|t| !t.is_explicit())
+    }
+
     pub fn current_track(&self) -> Option<&Track> {
         let track_idx = *self.play_order.get(self.current)?;
         self.tracks.get(track_idx)
@@ -31,28 +42,32 @@ impl Queue {
         if self.repeat == RepeatMode::One {
             return self.current_track();
         }
-        // Try to advance
-        if self.current + 1 < self.play_order.len() {
-            self.current += 1;
-            self.current_track()
-        } else {
-            // At the end
-            match self.repeat {
-                RepeatMode::All => {
-                    self.current = 0; // Loop back
-                    self.current_track()
-                }
-                RepeatMode::One => self.current_track(),
-                RepeatMode::None => None, // Stop
+        for _ in 0..self.play_order.len() {
+            // Try to advance
+            if self.current + 1 < self.play_order.len() {
+                self.current += 1;
+            } else if self.repeat == RepeatMode::All {
+                self.current = 0; // Loop back
+            } else {
+                return None; // Stop
+            }
+            if !self.skips_current() {
+                break;
             }
         }
+        self.current_track()
     }
 
     pub fn previous(&mut self) -> Option<&Track> {
-        if self.current > 0 {
-            self.current -= 1;
-        } else if self.repeat == RepeatMode::All {
-            self.current = self.play_order.len().saturating_sub(1);
+        for _ in 0..self.play_order.len() {
+            if self.current > 0 {
+                self.current -= 1;
+            } else if self.repeat == RepeatMode::All {
+                self.current = self.play_order.len().saturating_sub(1);
+            }
+            if !self.skips_current() {
+                break;
+            }
         }
         self.current_track()
     }
@@ -61,7 +76,7 @@ impl Queue {
         self.shuffle = !self.shuffle;
 
         // Remember what track we're on
-        let current_track_idx = self.play_order[self.current];
+        let current_track_idx = self.play_order.get(self.current).copied();
 
         if self.shuffle {
             // Shuffle the order
@@ -76,10 +91,38 @@ impl Queue {
         self.current = self
             .play_order
             .iter()
-            .position(|&i| i == current_track_idx)
+            .position(|&i| Some(i) == current_track_idx)
             .unwrap_or(0);
     }
 
+    /// Track indices in the order they play
+    pub fn play_order(&self) -> &[usize] {
+        &self.play_order
+    }
+
+    /// Up to `n` tracks that `next` would move to, in order, without moving
+    pub fn upcoming(&self, n: usize) -> Vec<&Track> {
+        if self.repeat == RepeatMode::One {
+            return Vec::new();
+        }
+        let len = self.play_order.len();
+        let steps = if self.repeat == RepeatMode::All {
+            len.saturating_sub(1)
+        } else {
+            len.saturating_sub(self.current + 1)
+        };
+        let any_clean = self.tracks.iter().any(|t| !t.is_explicit());
+
+        (1..=steps)
+            .filter_map(|step| {
+                self.tracks
+                    .get(self.play_order[(self.current + step) % len])
+            })
+            .filter(|t| !(self.skip_explicit && any_clean && t.is_explicit()))
+            .take(n)
+            .collect()
+    }
+
     pub fn jump_to(&mut self, index: usize) -> Option<&Track> {
         if index < self.play_order.len() {
             self.current = index;
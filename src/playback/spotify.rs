@@ -5,8 +5,9 @@ use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 
-use crate::provider::{OAuthToken, ProviderKind};
+use crate::provider::{OAuthToken, ProviderKind, Track};
 use crate::state::credentials;
+use crate::utils::http;
 
 const API_BASE: &str = "https://api.spotify.com/v1";
 const TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
@@ -16,8 +17,8 @@ const TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
 pub struct SpotifyPlayer {
     http: reqwest::Client,
     token: Mutex<OAuthToken>,
-    client_id: String,
-    client_secret: String,
+    client_id: Option<String>,
+    client_secret: Option<String>,
     grit_dir: PathBuf,
     device_id: Option<String>,
 }
@@ -65,8 +66,107 @@ struct CurrentlyPlaying {
 
 #[derive(Debug, Deserialize)]
 struct PlayingItem {
+    // Local files have no id
+    #[serde(default)]
+    id: Option<String>,
     name: String,
+    // Podcast episodes in the queue have no artists
+    #[serde(default)]
     artists: Vec<PlayingArtist>,
+    #[serde(default)]
+    duration_ms: Option<u64>,
+    /// Set when Spotify plays a regional copy of the track
+    #[serde(default)]
+    linked_from: Option<PlayingLinkedFrom>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayingLinkedFrom {
+    id: String,
+}
+
+/// The item Spotify reports as playing
+#[derive(Debug, Clone)]
+pub struct PlayingTrack {
+    /// Track or episode id; `None` for local files
+    pub id: Option<String>,
+    /// Id of the track Spotify relinked to `id`, which is the one a playlist
+    /// holds
+    pub linked_from: Option<String>,
+    pub name: String,
+}
+
+impl PlayingTrack {
+    /// Whether this is `track`: by id or the id it was relinked from, or by
+    /// name for local files that have none
+    pub fn is(&self, track: &Track) -> bool {
+        match &self.id {
+            Some(id) => &track.id == id || self.linked_from.as_ref() == Some(&track.id),
+            None => track.name == self.name,
+        }
+    }
+}
+
+impl PlayingItem {
+    fn into_playing_track(self) -> PlayingTrack {
+        PlayingTrack {
+            id: self.id,
+            linked_from: self.linked_from.map(|l| l.id),
+            name: self.name,
+        }
+    }
+
+    fn into_name_and_artists(self) -> (String, String) {
+        let artists = self
+            .artists
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        (self.name, artists)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayerResponse {
+    item: Option<PlayingItem>,
+    #[serde(default)]
+    shuffle_state: bool,
+    progress_ms: Option<u64>,
+    #[serde(default)]
+    is_playing: bool,
+}
+
+impl PlayerResponse {
+    fn into_playback_state(self) -> PlaybackState {
+        let duration_ms = self.item.as_ref().and_then(|item| item.duration_ms);
+        PlaybackState {
+            track: self.item.map(PlayingItem::into_playing_track),
+            shuffle: self.shuffle_state,
+            progress_secs: self.progress_ms.map(|ms| ms as f64 / 1000.0),
+            duration_secs: duration_ms.map(|ms| ms as f64 / 1000.0),
+            is_playing: self.is_playing,
+        }
+    }
+}
+
+/// Spotify's view of the player: what is playing, how far in, and whether
+/// it shuffles. Reflects control from other clients too.
+#[derive(Debug, Clone)]
+pub struct PlaybackState {
+    pub track: Option<PlayingTrack>,
+    pub shuffle: bool,
+    /// Position in the current track, in seconds
+    pub progress_secs: Option<f64>,
+    /// Length of the current track as Spotify has it, in seconds
+    pub duration_secs: Option<f64>,
+    pub is_playing: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueueResponse {
+    #[serde(default)]
+    queue: Vec<PlayingItem>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -98,14 +198,14 @@ fn parse_spotify_error(text: &str) -> String {
 impl SpotifyPlayer {
     pub fn new(
         token: OAuthToken,
-        client_id: String,
-        client_secret: String,
+        client_id: Option<String>,
+        client_secret: Option<String>,
         grit_dir: &Path,
     ) -> Self {
         Self {
-            http: reqwest::Client::new(),
+            http: crate::utils::http::client(),
+            client_id: client_id.or_else(|| token.client_id.clone()),
             token: Mutex::new(token),
-            client_id,
             client_secret,
             grit_dir: grit_dir.to_path_buf(),
             device_id: None,
@@ -148,8 +248,17 @@ impl SpotifyPlayer {
             .as_ref()
             .context("No refresh token available")?;
 
+        let client_id = self
+            .client_id
+            .as_deref()
+            .context("SPOTIFY_CLIENT_ID not set (required to refresh the Spotify token)")?;
+        let client_secret = self
+            .client_secret
+            .as_deref()
+            .context("SPOTIFY_CLIENT_SECRET not set (required to refresh the Spotify token)")?;
+
         use base64::Engine;
-        let credentials = format!("{}:{}", self.client_id, self.client_secret);
+        let credentials = format!("{}:{}", client_id, client_secret);
         let basic_auth = base64::engine::general_purpose::STANDARD.encode(credentials);
 
         let params = [
@@ -185,6 +294,7 @@ impl SpotifyPlayer {
             expires_at: Some(expires_at),
             token_type: resp.token_type,
             scope: resp.scope,
+            client_id: Some(client_id.to_string()),
         })
     }
 
@@ -214,8 +324,9 @@ impl SpotifyPlayer {
             .collect())
     }
 
-    /// Select a device for playback
-    pub async fn select_device(&mut self) -> Result<()> {
+    /// Select a device for playback, by name if `target` is given.
+    /// Returns the selected device's name.
+    pub async fn select_device(&mut self, target: Option<&str>) -> Result<String> {
         let devices = self.get_devices().await?;
 
         if devices.is_empty() {
@@ -228,7 +339,20 @@ impl SpotifyPlayer {
             );
         }
 
-        let device = if devices.len() == 1 {
+        let device = if let Some(target) = target {
+            devices
+                .iter()
+                .find(|(_, name, _)| name.eq_ignore_ascii_case(target))
+                .with_context(|| {
+                    let names: Vec<&str> =
+                        devices.iter().map(|(_, name, _)| name.as_str()).collect();
+                    format!(
+                        "No Spotify device named '{}'. Available: {}",
+                        target,
+                        names.join(", ")
+                    )
+                })?
+        } else if devices.len() == 1 {
             &devices[0]
         } else {
             // Multiple devices - let user choose
@@ -255,7 +379,7 @@ impl SpotifyPlayer {
 
         println!("Using Spotify device: {}", device.1);
         self.device_id = Some(device.0.clone());
-        Ok(())
+        Ok(device.1.clone())
     }
 
     /// Start playback with a list of track URIs
@@ -394,6 +518,30 @@ impl SpotifyPlayer {
             ))
             .bearer_auth(&token)
             .header("Content-Length", "0")
+            .timeout(http::interactive_timeout())
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            bail!("{}", parse_spotify_error(&text));
+        }
+        Ok(())
+    }
+
+    /// Set the device volume, 0-100
+    pub async fn set_volume(&self, percent: u8) -> Result<()> {
+        let token = self.get_token().await?;
+        let device_id = self.device_id.as_ref().context("No device selected")?;
+
+        let resp = self
+            .http
+            .put(format!(
+                "{}/me/player/volume?device_id={}&volume_percent={}",
+                API_BASE, device_id, percent
+            ))
+            .bearer_auth(&token)
+            .header("Content-Length", "0")
             .send()
             .await?;
 
@@ -432,17 +580,13 @@ impl SpotifyPlayer {
         let token = self.get_token().await?;
         let device_id = self.device_id.as_ref().context("No device selected")?;
 
-        let state = match mode {
-            crate::playback::events::RepeatMode::None => "off",
-            crate::playback::events::RepeatMode::All => "context",
-            crate::playback::events::RepeatMode::One => "track",
-        };
-
         let resp = self
             .http
             .put(format!(
                 "{}/me/player/repeat?device_id={}&state={}",
-                API_BASE, device_id, state
+                API_BASE,
+                device_id,
+                mode.spotify_state()
             ))
             .bearer_auth(&token)
             .header("Content-Length", "0")
@@ -457,13 +601,14 @@ impl SpotifyPlayer {
     }
 
     /// Get currently playing track info
-    pub async fn get_currently_playing(&self) -> Result<Option<(String, String)>> {
+    pub async fn get_currently_playing(&self) -> Result<Option<PlayingTrack>> {
         let token = self.get_token().await?;
 
         let resp = self
             .http
             .get(format!("{}/me/player/currently-playing", API_BASE))
             .bearer_auth(&token)
+            .timeout(http::interactive_timeout())
             .send()
             .await?;
 
@@ -478,16 +623,101 @@ impl SpotifyPlayer {
 
         let playing: CurrentlyPlaying = resp.json().await?;
 
-        if let Some(item) = playing.item {
-            let artists = item
-                .artists
-                .iter()
-                .map(|a| a.name.as_str())
-                .collect::<Vec<_>>()
-                .join(", ");
-            Ok(Some((item.name, artists)))
-        } else {
-            Ok(None)
+        Ok(playing.item.map(PlayingItem::into_playing_track))
+    }
+
+    /// Full player state, for keeping the TUI in step with changes made on
+    /// Spotify's side (another device toggling shuffle, say)
+    pub async fn get_playback_state(&self) -> Result<Option<PlaybackState>> {
+        let token = self.get_token().await?;
+
+        let resp = self
+            .http
+            .get(format!("{}/me/player", API_BASE))
+            .bearer_auth(&token)
+            .timeout(http::interactive_timeout())
+            .send()
+            .await?;
+
+        // 204 = no active device
+        if resp.status().as_u16() == 204 {
+            return Ok(None);
+        }
+
+        if !resp.status().is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            bail!("{}", parse_spotify_error(&text));
+        }
+
+        let state: PlayerResponse = resp.json().await?;
+        Ok(Some(state.into_playback_state()))
+    }
+
+    /// What Spotify will play next, in order, as (name, artists). This is the
+    /// real up-next, so it reflects Spotify's shuffle order and anything the
+    /// user queued from another device.
+    pub async fn get_queue(&self) -> Result<Vec<(String, String)>> {
+        let token = self.get_token().await?;
+
+        let resp = self
+            .http
+            .get(format!("{}/me/player/queue", API_BASE))
+            .bearer_auth(&token)
+            .timeout(http::interactive_timeout())
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            bail!("{}", parse_spotify_error(&text));
         }
+
+        let queue: QueueResponse = resp.json().await?;
+
+        Ok(queue
+            .queue
+            .into_iter()
+            .map(PlayingItem::into_name_and_artists)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_player_response_reads_progress_and_length() {
+        let json = r#"{
+            "is_playing": true,
+            "shuffle_state": false,
+            "progress_ms": 61500,
+            "item": {
+                "id": "abc",
+                "name": "Song",
+                "artists": [{ "name": "Artist" }],
+                "duration_ms": 200000,
+                "linked_from": { "id": "orig" }
+            }
+        }"#;
+        let state = serde_json::from_str::<PlayerResponse>(json)
+            .unwrap()
+            .into_playback_state();
+
+        assert!(state.is_playing);
+        assert_eq!(state.progress_secs, Some(61.5));
+        assert_eq!(state.duration_secs, Some(200.0));
+        let track = state.track.unwrap();
+        assert_eq!(track.id.as_deref(), Some("abc"));
+        assert_eq!(track.linked_from.as_deref(), Some("orig"));
+
+        // Between tracks Spotify sends no item and no progress
+        let state = serde_json::from_str::<PlayerResponse>(r#"{"item": null}"#)
+            .unwrap()
+            .into_playback_state();
+        assert!(!state.is_playing);
+        assert!(state.track.is_none());
+        assert_eq!(state.progress_secs, None);
+        assert_eq!(state.duration_secs, None);
     }
 }
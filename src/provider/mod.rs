@@ -4,6 +4,6 @@ mod types;
 pub mod youtube;
 
 pub use spotify::SpotifyProvider;
-pub use traits::Provider;
+pub use traits::{id_batches, Provider, MAX_SEARCH_LIMIT};
 pub use types::*;
 pub use youtube::YoutubeProvider;
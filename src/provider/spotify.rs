@@ -1,5 +1,6 @@
 use crate::provider::{
-    DiffPatch, OAuthToken, PlaylistSnapshot, Provider, ProviderKind, Track, TrackChange,
+    id_batches, DiffPatch, FetchOptions, OAuthToken, PlaylistSnapshot, Provider, ProviderKind,
+    Track, TrackChange, LIKED_PLAYLIST_ID, MAX_SEARCH_LIMIT, SCHEMA_VERSION,
 };
 use anyhow::{Context, Result};
 use async_trait::async_trait;
@@ -11,10 +12,12 @@ const TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
 const API_BASE: &str = "https://api.spotify.com/v1";
 
 pub struct SpotifyProvider {
-    client_id: String,
-    client_secret: String,
+    client_id: Option<String>,
+    client_secret: Option<String>,
     token: Mutex<Option<OAuthToken>>,
     grit_dir: Option<std::path::PathBuf>,
+    /// Market passed to track lookups, e.g. "US" or "from_token"
+    market: Option<String>,
     http: reqwest::Client,
 }
 
@@ -43,9 +46,15 @@ struct SpotifyOwner {
     id: String,
 }
 
+#[derive(Deserialize)]
+struct SpotifyCreatedPlaylist {
+    id: String,
+}
+
 #[derive(Deserialize)]
 struct SpotifyUser {
     id: String,
+    display_name: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -57,14 +66,151 @@ struct SpotifyTracks {
 #[derive(Deserialize)]
 struct SpotifyTrackItem {
     track: Option<SpotifyTrackObject>,
+    /// Who added the item; missing or null for some (e.g. very old) playlists
+    #[serde(default)]
+    added_by: Option<SpotifyAddedBy>,
+}
+
+#[derive(Deserialize)]
+struct SpotifyAddedBy {
+    #[serde(default)]
+    id: Option<String>,
+}
+
+impl SpotifyTrackItem {
+    /// The item as a track. Local files and tracks unplayable in the market
+    /// become placeholders with `include_unavailable`, else are dropped.
+    fn into_track(self, include_unavailable: bool) -> Option<Track> {
+        let added_by = self.added_by.and_then(|a| a.id).filter(|id| !id.is_empty());
+        let track = self.track?;
+        let unavailable = track.is_local || track.is_playable == Some(false);
+        if unavailable && !include_unavailable {
+            return None;
+        }
+        let mut track = track.into_track();
+        if unavailable {
+            track.mark_unavailable();
+        }
+        if let Some(id) = added_by {
+            track.metadata.get_or_insert_with(|| serde_json::json!({}))["added_by"] = id.into();
+        }
+        Some(track)
+    }
+}
+
+/// Metadata `kind` marking a podcast episode rather than a song
+const EPISODE_KIND: &str = "episode";
+
+/// Spotify URI for a track or podcast episode
+pub fn track_uri(track: &Track) -> String {
+    // Local files have no id and are tracked by their URI
+    if track.id.starts_with("spotify:") {
+        return track.id.clone();
+    }
+
+    let is_episode = track
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get("kind"))
+        .and_then(|k| k.as_str())
+        == Some(EPISODE_KIND);
+
+    if is_episode {
+        format!("spotify:episode:{}", track.id)
+    } else {
+        format!("spotify:track:{}", track.id)
+    }
 }
 
+/// A playlist item: usually a track, but playlists can hold podcast episodes
 #[derive(Deserialize)]
 struct SpotifyTrackObject {
-    id: String,
+    /// Null for local files
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    uri: Option<String>,
+    #[serde(default)]
+    is_local: bool,
+    /// Only sent when a market is given
+    #[serde(default)]
+    is_playable: Option<bool>,
     name: String,
     duration_ms: u64,
+    /// Episodes have a show instead of artists
+    #[serde(default)]
     artists: Vec<SpotifyArtist>,
+    /// "track" or "episode"
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    #[serde(default)]
+    show: Option<SpotifyShow>,
+    /// 30s mp3 preview; null for many tracks
+    #[serde(default)]
+    preview_url: Option<String>,
+    #[serde(default)]
+    explicit: bool,
+    /// Set when a market is given and Spotify swapped in a regional copy
+    #[serde(default)]
+    linked_from: Option<SpotifyLinkedFrom>,
+}
+
+#[derive(Deserialize)]
+struct SpotifyLinkedFrom {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct SpotifyShow {
+    name: String,
+}
+
+impl SpotifyTrackObject {
+    fn into_track(self) -> Track {
+        let own_id = self.id.or(self.uri).unwrap_or_default();
+        if self.kind.as_deref() == Some(EPISODE_KIND) {
+            return Track {
+                id: own_id,
+                name: self.name,
+                artists: self.show.map(|s| s.name).into_iter().collect(),
+                duration_ms: self.duration_ms,
+                provider: ProviderKind::Spotify,
+                metadata: Some(serde_json::json!({ "kind": EPISODE_KIND })),
+            };
+        }
+
+        // The relinked id depends on where the request came from; the
+        // original is what the playlist actually holds
+        let id = self.linked_from.map(|l| l.id).unwrap_or(own_id);
+
+        Track {
+            id,
+            name: self.name,
+            artists: self.artists.into_iter().map(|a| a.name).collect(),
+            duration_ms: self.duration_ms,
+            provider: ProviderKind::Spotify,
+            metadata: self
+                .explicit
+                .then(|| serde_json::json!({ "explicit": true })),
+        }
+    }
+
+    /// Like `into_track`, keeping the preview url in the track metadata.
+    /// Only used for search results, so tracked snapshots stay unchanged.
+    fn into_track_with_preview(self) -> Track {
+        let preview_url = self.preview_url.clone();
+        let mut track = self.into_track();
+        if let Some(url) = preview_url {
+            track.metadata.get_or_insert_with(|| serde_json::json!({}))["preview_url"] = url.into();
+        }
+        track
+    }
+}
+
+#[derive(Deserialize)]
+struct SpotifyTracksResponse {
+    /// `null` for ids Spotify doesn't know
+    tracks: Vec<Option<SpotifyTrackObject>>,
 }
 
 #[derive(Deserialize)]
@@ -87,23 +233,15 @@ struct SpotifyAlbum {
     id: String,
     name: String,
     artists: Vec<SpotifyArtist>,
-    tracks: SpotifyAlbumTracks,
+    tracks: SpotifyTrackObjects,
 }
 
 #[derive(Deserialize)]
-struct SpotifyAlbumTracks {
-    items: Vec<SpotifyAlbumTrack>,
+struct SpotifyTrackObjects {
+    items: Vec<SpotifyTrackObject>,
     next: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct SpotifyAlbumTrack {
-    id: String,
-    name: String,
-    duration_ms: u64,
-    artists: Vec<SpotifyArtist>,
-}
-
 impl SpotifyTokenResponse {
     fn into_oauth_token(self) -> OAuthToken {
         use std::time::{SystemTime, UNIX_EPOCH};
@@ -120,27 +258,56 @@ impl SpotifyTokenResponse {
             expires_at: Some(expires_at),
             token_type: self.token_type,
             scope: self.scope,
+            client_id: None,
         }
     }
 }
 
 impl SpotifyProvider {
     pub fn new(client_id: String, client_secret: String) -> Self {
+        Self::with_credentials(Some(client_id), Some(client_secret))
+    }
+
+    /// Create a provider whose client credentials may be unknown.
+    /// They are only required once a token has to be exchanged or refreshed.
+    pub fn with_credentials(client_id: Option<String>, client_secret: Option<String>) -> Self {
         Self {
             client_id,
             client_secret,
             token: Mutex::new(None),
             grit_dir: None,
-            http: reqwest::Client::new(),
+            market: None,
+            http: crate::utils::http::client(),
         }
     }
 
     pub fn with_token(mut self, token: &OAuthToken, grit_dir: &std::path::Path) -> Self {
+        if self.client_id.is_none() {
+            self.client_id = token.client_id.clone();
+        }
         self.token = Mutex::new(Some(token.clone()));
         self.grit_dir = Some(grit_dir.to_path_buf());
         self
     }
 
+    /// Ask for tracks as available in `market`, so regional relinks report
+    /// their original id
+    pub fn with_market(mut self, market: Option<String>) -> Self {
+        self.market = market.filter(|m| !m.is_empty());
+        self
+    }
+
+    /// `url` with the configured market appended as a query param
+    fn in_market(&self, url: String) -> String {
+        match &self.market {
+            Some(market) => {
+                let sep = if url.contains('?') { '&' } else { '?' };
+                format!("{}{}market={}", url, sep, urlencoding::encode(market))
+            }
+            None => url,
+        }
+    }
+
     /// Check if a token is expired
     fn is_token_expired(token: &OAuthToken) -> bool {
         if let Some(expires_at) = token.expires_at {
@@ -164,7 +331,7 @@ impl SpotifyProvider {
         drop(token_guard);
 
         if Self::is_token_expired(&current_token) {
-            println!("Token expired, refreshing...");
+            tracing::info!("Token expired, refreshing...");
             let new_token = self.refresh_token(&current_token).await?;
 
             if let Some(grit_dir) = &self.grit_dir {
@@ -179,10 +346,18 @@ impl SpotifyProvider {
         }
     }
 
-    fn basic_auth_header(&self) -> String {
+    fn basic_auth_header(&self) -> Result<String> {
         use base64::Engine;
-        let credentials = format!("{}:{}", self.client_id, self.client_secret);
-        base64::engine::general_purpose::STANDARD.encode(credentials)
+        let client_id = self
+            .client_id
+            .as_deref()
+            .context("SPOTIFY_CLIENT_ID not set (required to refresh the Spotify token)")?;
+        let client_secret = self
+            .client_secret
+            .as_deref()
+            .context("SPOTIFY_CLIENT_SECRET not set (required to refresh the Spotify token)")?;
+        let credentials = format!("{}:{}", client_id, client_secret);
+        Ok(base64::engine::general_purpose::STANDARD.encode(credentials))
     }
 
     async fn token_request(&self, params: &[(&str, &str)]) -> Result<SpotifyTokenResponse> {
@@ -191,7 +366,7 @@ impl SpotifyProvider {
             .post(TOKEN_URL)
             .header(
                 "Authorization",
-                format!("Basic {}", self.basic_auth_header()),
+                format!("Basic {}", self.basic_auth_header()?),
             )
             .form(params)
             .send()
@@ -210,6 +385,7 @@ impl SpotifyProvider {
     }
 
     async fn api_get<T: serde::de::DeserializeOwned>(&self, url: &str, token: &str) -> Result<T> {
+        tracing::debug!("GET {}", url);
         let response = self
             .http
             .get(url)
@@ -232,36 +408,22 @@ impl SpotifyProvider {
 
     pub async fn fetch_album(&self, album_id: &str) -> Result<PlaylistSnapshot> {
         let token = self.get_token().await?;
-        let url = format!("{}/albums/{}", API_BASE, album_id);
+        let url = self.in_market(format!("{}/albums/{}", API_BASE, album_id));
 
         let album: SpotifyAlbum = self.api_get(&url, &token).await?;
 
         let mut all_tracks = Vec::new();
 
         for track in album.tracks.items {
-            all_tracks.push(Track {
-                id: track.id,
-                name: track.name,
-                artists: track.artists.into_iter().map(|a| a.name).collect(),
-                duration_ms: track.duration_ms,
-                provider: ProviderKind::Spotify,
-                metadata: None,
-            });
+            all_tracks.push(track.into_track());
         }
 
         let mut next_url = album.tracks.next;
         while let Some(url) = next_url {
-            let page: SpotifyAlbumTracks = self.api_get(&url, &token).await?;
+            let page: SpotifyTrackObjects = self.api_get(&url, &token).await?;
 
             for track in page.items {
-                all_tracks.push(Track {
-                    id: track.id,
-                    name: track.name,
-                    artists: track.artists.into_iter().map(|a| a.name).collect(),
-                    duration_ms: track.duration_ms,
-                    provider: ProviderKind::Spotify,
-                    metadata: None,
-                });
+                all_tracks.push(track.into_track());
             }
 
             next_url = page.next;
@@ -277,8 +439,43 @@ impl SpotifyProvider {
             provider: ProviderKind::Spotify,
             snapshot_hash: String::new(),
             metadata: None,
+            schema_version: SCHEMA_VERSION,
         })
     }
+
+    /// The user's saved tracks, paged from `/me/tracks`
+    async fn fetch_liked(&self, options: &FetchOptions) -> Result<PlaylistSnapshot> {
+        let token = self.get_token().await?;
+
+        let mut all_tracks = Vec::new();
+        let mut next_url = Some(self.in_market(format!("{}/me/tracks?limit=50", API_BASE)));
+        while let Some(url) = next_url
+            .as_deref()
+            .filter(|_| !options.reached(all_tracks.len()))
+        {
+            let page: SpotifyTracks = self.api_get(url, &token).await?;
+
+            for item in page.items {
+                all_tracks.extend(item.into_track(options.include_unavailable));
+            }
+
+            next_url = page.next;
+        }
+
+        let mut snapshot = PlaylistSnapshot {
+            id: LIKED_PLAYLIST_ID.to_string(),
+            name: "Liked Songs".to_string(),
+            description: Some("Saved tracks (read-only)".to_string()),
+            tracks: all_tracks,
+            provider: ProviderKind::Spotify,
+            snapshot_hash: String::new(),
+            metadata: None,
+            schema_version: SCHEMA_VERSION,
+        };
+        snapshot.set_keeps_unavailable(options.include_unavailable);
+        snapshot.limit_to(options.limit, next_url.is_some());
+        Ok(snapshot)
+    }
 }
 
 #[async_trait]
@@ -291,13 +488,14 @@ impl Provider for SpotifyProvider {
             "playlist-modify-private",
             "user-modify-playback-state",
             "user-read-playback-state",
+            "user-library-read",
         ]
         .join(" ");
 
         format!(
             "{}?client_id={}&response_type=code&redirect_uri={}&scope={}&state={}",
             AUTH_URL,
-            urlencoding::encode(&self.client_id),
+            urlencoding::encode(self.client_id.as_deref().unwrap_or_default()),
             urlencoding::encode(redirect_uri),
             urlencoding::encode(&scopes),
             urlencoding::encode(state),
@@ -311,9 +509,10 @@ impl Provider for SpotifyProvider {
             ("redirect_uri", redirect_uri),
         ];
 
-        self.token_request(&params)
-            .await
-            .map(|r| r.into_oauth_token())
+        let mut token = self.token_request(&params).await?.into_oauth_token();
+        token.client_id = self.client_id.clone();
+
+        Ok(token)
     }
 
     async fn refresh_token(&self, token: &OAuthToken) -> Result<OAuthToken> {
@@ -332,52 +531,51 @@ impl Provider for SpotifyProvider {
         if new_token.refresh_token.is_none() {
             new_token.refresh_token = token.refresh_token.clone();
         }
+        new_token.client_id = token.client_id.clone().or_else(|| self.client_id.clone());
 
         Ok(new_token)
     }
 
-    async fn fetch(&self, playlist_id: &str) -> Result<PlaylistSnapshot> {
+    async fn fetch_with(
+        &self,
+        playlist_id: &str,
+        options: &FetchOptions,
+    ) -> Result<PlaylistSnapshot> {
+        if playlist_id == LIKED_PLAYLIST_ID {
+            return self.fetch_liked(options).await;
+        }
+
         let token = self.get_token().await?;
-        let url = format!("{}/playlists/{}", API_BASE, playlist_id);
+        // Without additional_types, episodes come back as null items and a
+        // push would then remove them
+        let url = self.in_market(format!(
+            "{}/playlists/{}?additional_types=track,episode",
+            API_BASE, playlist_id
+        ));
 
         let playlist: SpotifyPlaylist = self.api_get(&url, &token).await?;
 
         let mut all_tracks = Vec::new();
 
         for item in playlist.tracks.items {
-            if let Some(track) = item.track {
-                all_tracks.push(Track {
-                    id: track.id,
-                    name: track.name,
-                    artists: track.artists.into_iter().map(|a| a.name).collect(),
-                    duration_ms: track.duration_ms,
-                    provider: ProviderKind::Spotify,
-                    metadata: None,
-                });
-            }
+            all_tracks.extend(item.into_track(options.include_unavailable));
         }
 
         let mut next_url = playlist.tracks.next;
-        while let Some(url) = next_url {
-            let page: SpotifyTracks = self.api_get(&url, &token).await?;
+        while let Some(url) = next_url
+            .as_deref()
+            .filter(|_| !options.reached(all_tracks.len()))
+        {
+            let page: SpotifyTracks = self.api_get(url, &token).await?;
 
             for item in page.items {
-                if let Some(track) = item.track {
-                    all_tracks.push(Track {
-                        id: track.id,
-                        name: track.name,
-                        artists: track.artists.into_iter().map(|a| a.name).collect(),
-                        duration_ms: track.duration_ms,
-                        provider: ProviderKind::Spotify,
-                        metadata: None,
-                    });
-                }
+                all_tracks.extend(item.into_track(options.include_unavailable));
             }
 
             next_url = page.next;
         }
 
-        Ok(PlaylistSnapshot {
+        let mut snapshot = PlaylistSnapshot {
             id: playlist.id,
             name: playlist.name,
             description: playlist.description,
@@ -385,7 +583,11 @@ impl Provider for SpotifyProvider {
             provider: ProviderKind::Spotify,
             snapshot_hash: playlist.snapshot_id,
             metadata: None,
-        })
+            schema_version: SCHEMA_VERSION,
+        };
+        snapshot.set_keeps_unavailable(options.include_unavailable);
+        snapshot.limit_to(options.limit, next_url.is_some());
+        Ok(snapshot)
     }
 
     async fn apply(
@@ -403,7 +605,7 @@ impl Provider for SpotifyProvider {
             .iter()
             .filter_map(|change| {
                 if let TrackChange::Removed { track, .. } = change {
-                    Some(serde_json::json!({"uri": format!("spotify:track:{}", track.id)}))
+                    Some(serde_json::json!({ "uri": track_uri(track) }))
                 } else {
                     None
                 }
@@ -433,7 +635,7 @@ impl Provider for SpotifyProvider {
             .iter()
             .filter_map(|change| {
                 if let TrackChange::Added { track, .. } = change {
-                    Some(format!("spotify:track:{}", track.id))
+                    Some(track_uri(track))
                 } else {
                     None
                 }
@@ -462,7 +664,7 @@ impl Provider for SpotifyProvider {
             // Fetch current state to find where this track is now
             let current = self.fetch(playlist_id).await?;
 
-            let current_idx = current.tracks.iter().position(|t| t.id == desired_track.id);
+            let current_idx = current.tracks.iter().position(|t| t.same_as(desired_track));
 
             if let Some(current_idx) = current_idx {
                 if current_idx != desired_idx {
@@ -494,16 +696,17 @@ impl Provider for SpotifyProvider {
     }
 
     async fn playable_url(&self, track: &Track) -> Result<String> {
-        Ok(format!("spotify:track:{}", track.id))
+        Ok(track_uri(track))
     }
 
-    async fn search_by_query(&self, query: &str) -> Result<Vec<Track>> {
+    async fn search_by_query(&self, query: &str, limit: usize) -> Result<Vec<Track>> {
         let token = self.get_token().await?;
-        let url = format!(
-            "{}/search?q={}&type=track&limit=10",
+        let url = self.in_market(format!(
+            "{}/search?q={}&type=track&limit={}",
             API_BASE,
-            urlencoding::encode(query)
-        );
+            urlencoding::encode(query),
+            limit.clamp(1, MAX_SEARCH_LIMIT)
+        ));
 
         let resp: SpotifySearchResponse = self.api_get(&url, &token).await?;
 
@@ -511,14 +714,7 @@ impl Provider for SpotifyProvider {
             .tracks
             .items
             .into_iter()
-            .map(|track| Track {
-                id: track.id,
-                name: track.name,
-                artists: track.artists.into_iter().map(|a| a.name).collect(),
-                duration_ms: track.duration_ms,
-                provider: ProviderKind::Spotify,
-                metadata: None,
-            })
+            .map(SpotifyTrackObject::into_track_with_preview)
             .collect();
 
         Ok(tracks)
@@ -526,18 +722,28 @@ impl Provider for SpotifyProvider {
 
     async fn fetch_track(&self, track_id: &str) -> Result<Track> {
         let token = self.get_token().await?;
-        let url = format!("{}/tracks/{}", API_BASE, track_id);
+        let url = self.in_market(format!("{}/tracks/{}", API_BASE, track_id));
 
         let track: SpotifyTrackObject = self.api_get(&url, &token).await?;
 
-        Ok(Track {
-            id: track.id,
-            name: track.name,
-            artists: track.artists.into_iter().map(|a| a.name).collect(),
-            duration_ms: track.duration_ms,
-            provider: ProviderKind::Spotify,
-            metadata: None,
-        })
+        Ok(track.into_track())
+    }
+
+    async fn fetch_tracks(&self, track_ids: &[String]) -> Result<Vec<Track>> {
+        let token = self.get_token().await?;
+        let mut tracks = Vec::with_capacity(track_ids.len());
+
+        for batch in id_batches(track_ids) {
+            let url = self.in_market(format!("{}/tracks?ids={}", API_BASE, batch));
+            let resp: SpotifyTracksResponse = self.api_get(&url, &token).await?;
+
+            for (id, track) in batch.split(',').zip(resp.tracks) {
+                let track = track.with_context(|| format!("Track not found: {}", id))?;
+                tracks.push(track.into_track());
+            }
+        }
+
+        Ok(tracks)
     }
 
     async fn can_modify_playlist(&self, playlist_id: &str) -> Result<bool> {
@@ -551,4 +757,121 @@ impl Provider for SpotifyProvider {
 
         Ok(playlist.owner.id == user.id || playlist.collaborative)
     }
+
+    async fn current_user(&self) -> Result<String> {
+        let token = self.get_token().await?;
+        let url = format!("{}/me", API_BASE);
+        let user: SpotifyUser = self.api_get(&url, &token).await?;
+
+        Ok(user.display_name.unwrap_or(user.id))
+    }
+
+    async fn create_playlist(&self, name: &str, description: Option<&str>) -> Result<String> {
+        let token = self.get_token().await?;
+
+        let body = serde_json::json!({
+            "name": name,
+            "description": description.unwrap_or_default(),
+            "public": false,
+        });
+        let created: SpotifyCreatedPlaylist = self
+            .http
+            .post(format!("{}/me/playlists", API_BASE))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(created.id)
+    }
+
+    async fn delete_playlist(&self, playlist_id: &str) -> Result<()> {
+        let token = self.get_token().await?;
+
+        // Spotify has no real delete: unfollowing removes it from the
+        // owner's library
+        self.http
+            .delete(format!("{}/playlists/{}/followers", API_BASE, playlist_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playlist_episode_item_keeps_kind() {
+        let json = r#"{
+            "added_by": { "id": "friend" },
+            "track": {
+                "type": "episode",
+                "id": "ep1",
+                "name": "Episode 1",
+                "duration_ms": 3600000,
+                "show": { "name": "Some Podcast" }
+            }
+        }"#;
+
+        let item: SpotifyTrackItem = serde_json::from_str(json).unwrap();
+        let track = item.into_track(false).unwrap();
+
+        assert_eq!(track.artists, vec!["Some Podcast"]);
+        assert_eq!(track_uri(&track), "spotify:episode:ep1");
+        // added_by is merged in, not written over the kind
+        let metadata = track.metadata.unwrap();
+        assert_eq!(metadata["kind"], "episode");
+        assert_eq!(metadata["added_by"], "friend");
+    }
+
+    #[test]
+    fn test_local_file_item_is_placeholder() {
+        let json = r#"{
+            "track": {
+                "id": null,
+                "uri": "spotify:local:Artist:Album:Song:200",
+                "is_local": true,
+                "name": "Song",
+                "duration_ms": 200000,
+                "artists": [{ "name": "Artist" }]
+            }
+        }"#;
+
+        let item: SpotifyTrackItem = serde_json::from_str(json).unwrap();
+        let track = item.into_track(true).unwrap();
+        assert!(track.is_unavailable());
+        assert_eq!(track_uri(&track), "spotify:local:Artist:Album:Song:200");
+
+        let item: SpotifyTrackItem = serde_json::from_str(json).unwrap();
+        assert!(item.into_track(false).is_none());
+    }
+
+    #[test]
+    fn test_relinked_track_keeps_original_id() {
+        let json = r#"{
+            "id": "regional",
+            "name": "Song",
+            "duration_ms": 200000,
+            "artists": [{ "name": "Artist" }],
+            "linked_from": { "id": "original" }
+        }"#;
+
+        let track: SpotifyTrackObject = serde_json::from_str(json).unwrap();
+        assert_eq!(track.into_track().id, "original");
+
+        let provider = SpotifyProvider::with_credentials(None, None)
+            .with_market(Some("from_token".to_string()));
+        assert_eq!(
+            provider.in_market(format!("{}/tracks/x", API_BASE)),
+            format!("{}/tracks/x?market=from_token", API_BASE)
+        );
+    }
 }
@@ -1,10 +1,33 @@
-use crate::provider::{DiffPatch, OAuthToken, PlaylistSnapshot, Track};
+use crate::provider::{DiffPatch, FetchOptions, OAuthToken, PlaylistSnapshot, Track};
 use async_trait::async_trait;
 
+/// Most ids Spotify's `/tracks` and YouTube's `/videos` accept per request
+pub const MAX_IDS_PER_REQUEST: usize = 50;
+
+/// Most results either provider returns for one search request
+pub const MAX_SEARCH_LIMIT: usize = 50;
+
+/// Comma-joined id lists of at most `MAX_IDS_PER_REQUEST` ids each
+pub fn id_batches(ids: &[String]) -> Vec<String> {
+    ids.chunks(MAX_IDS_PER_REQUEST)
+        .map(|chunk| chunk.join(","))
+        .collect()
+}
+
 #[async_trait]
 pub trait Provider: Send + Sync {
     /// Fetch playlist snapshot from remote
-    async fn fetch(&self, playlist_id: &str) -> anyhow::Result<PlaylistSnapshot>;
+    async fn fetch(&self, playlist_id: &str) -> anyhow::Result<PlaylistSnapshot> {
+        self.fetch_with(playlist_id, &FetchOptions::default()).await
+    }
+
+    /// Fetch playlist snapshot from remote, stopping at `options.limit`
+    /// tracks. A snapshot cut short records its depth (`limit_to`).
+    async fn fetch_with(
+        &self,
+        playlist_id: &str,
+        options: &FetchOptions,
+    ) -> anyhow::Result<PlaylistSnapshot>;
 
     /// Apply changes to remote playlist to match desired state
     async fn apply(
@@ -14,12 +37,36 @@ pub trait Provider: Send + Sync {
         desired_state: &PlaylistSnapshot,
     ) -> anyhow::Result<()>;
 
+    /// Estimated API quota units `apply` would spend turning `remote` into
+    /// `desired_state`, for providers that meter writes. `None` when the
+    /// provider has no such quota.
+    fn estimate_quota(
+        &self,
+        _remote: &PlaylistSnapshot,
+        _patch: &DiffPatch,
+        _desired_state: &PlaylistSnapshot,
+    ) -> Option<u64> {
+        None
+    }
+
     /// Get playable URL for a track
     async fn playable_url(&self, track: &Track) -> anyhow::Result<String>;
 
     /// Fetch Tracks
     async fn fetch_track(&self, track_id: &str) -> anyhow::Result<Track>;
-    async fn search_by_query(&self, query: &str) -> anyhow::Result<Vec<Track>>;
+
+    /// Fetch several tracks, in the order given. Providers with a batch
+    /// endpoint override this to avoid one request per track.
+    async fn fetch_tracks(&self, track_ids: &[String]) -> anyhow::Result<Vec<Track>> {
+        let mut tracks = Vec::with_capacity(track_ids.len());
+        for id in track_ids {
+            tracks.push(self.fetch_track(id).await?);
+        }
+        Ok(tracks)
+    }
+
+    /// Search tracks, returning at most `limit` (capped at `MAX_SEARCH_LIMIT`)
+    async fn search_by_query(&self, query: &str, limit: usize) -> anyhow::Result<Vec<Track>>;
 
     // OAuth
     /// Generate OAuth authorization URL
@@ -33,4 +80,40 @@ pub trait Provider: Send + Sync {
 
     /// Check if the authenticated user can modify the playlist
     async fn can_modify_playlist(&self, playlist_id: &str) -> anyhow::Result<bool>;
+
+    /// Name of the authenticated user (also validates the stored token)
+    async fn current_user(&self) -> anyhow::Result<String>;
+
+    /// Create an empty private playlist for the authenticated user,
+    /// returning its id
+    async fn create_playlist(
+        &self,
+        name: &str,
+        description: Option<&str>,
+    ) -> anyhow::Result<String>;
+
+    /// Delete the playlist on the provider (unfollow, for Spotify)
+    async fn delete_playlist(&self, playlist_id: &str) -> anyhow::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("id{}", i)).collect()
+    }
+
+    #[test]
+    fn test_id_batches_split_at_fifty() {
+        assert_eq!(id_batches(&ids(50)).len(), 1);
+
+        let batches = id_batches(&ids(101));
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0].split(',').count(), 50);
+        assert_eq!(batches[1].split(',').count(), 50);
+        assert_eq!(batches[2], "id100");
+
+        assert!(id_batches(&[]).is_empty());
+    }
 }
@@ -2,7 +2,7 @@ use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
 pub enum ProviderKind {
     Spotify,
     Youtube,
@@ -18,6 +18,14 @@ impl fmt::Display for ProviderKind {
     }
 }
 
+/// Pseudo-playlist id for the user's library (Spotify saved tracks, YouTube
+/// liked videos). It can be fetched like a playlist but is read-only.
+pub const LIKED_PLAYLIST_ID: &str = "liked";
+
+/// On-disk format version written into new snapshots. Snapshots saved before
+/// versioning existed have no field and load as version 0.
+pub const SCHEMA_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Track {
     pub id: String,
@@ -29,6 +37,132 @@ pub struct Track {
     pub metadata: Option<serde_json::Value>,
 }
 
+#[cfg(test)]
+impl Track {
+    /// Spotify track named after its id, for tests to build on
+    pub fn test(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: id.to_string(),
+            artists: Vec::new(),
+            duration_ms: 1000,
+            provider: ProviderKind::Spotify,
+            metadata: None,
+        }
+    }
+}
+
+impl Track {
+    /// Whether `other` is the same provider item. Matches on id, never on
+    /// name: titles repeat across tracks and providers tweak them over time.
+    pub fn same_as(&self, other: &Track) -> bool {
+        self.provider == other.provider && self.id == other.id
+    }
+
+    /// Flagged explicit by the provider (recorded in metadata; Spotify only)
+    pub fn is_explicit(&self) -> bool {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.get("explicit"))
+            .and_then(|e| e.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Placeholder for an item the provider lists but can't play (a deleted
+    /// or private video, a Spotify local file). Only fetches with
+    /// `include_unavailable` keep these, to hold the item's position.
+    pub fn is_unavailable(&self) -> bool {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.get("unavailable"))
+            .and_then(|u| u.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Remove provider-filled metadata keys, dropping the metadata when
+    /// nothing is left
+    pub fn strip_provider_metadata(&mut self) {
+        self.strip_metadata(PROVIDER_METADATA_KEYS);
+    }
+
+    /// Remove metadata grit records itself (markers), dropping the metadata
+    /// when nothing is left
+    pub fn strip_local_metadata(&mut self) {
+        self.strip_metadata(LOCAL_METADATA_KEYS);
+    }
+
+    fn strip_metadata(&mut self, keys: &[&str]) {
+        if let Some(map) = self.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+            for key in keys {
+                map.remove(*key);
+            }
+            if map.is_empty() {
+                self.metadata = None;
+            }
+        }
+    }
+
+    pub fn mark_unavailable(&mut self) {
+        self.metadata.get_or_insert_with(|| serde_json::json!({}))["unavailable"] = true.into();
+    }
+
+    /// Markers set in the player, in time order
+    pub fn markers(&self) -> Vec<Marker> {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.get("markers"))
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn add_marker(&mut self, marker: Marker) {
+        let mut markers = self.markers();
+        markers.push(marker);
+        markers.sort_by(|a, b| a.secs.total_cmp(&b.secs));
+
+        let metadata = self
+            .metadata
+            .get_or_insert_with(|| serde_json::Value::Object(Default::default()));
+        if let Some(map) = metadata.as_object_mut() {
+            map.insert("markers".to_string(), serde_json::json!(markers));
+        }
+    }
+}
+
+/// A named point within a track, e.g. where a set changes song
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Marker {
+    pub secs: f64,
+    pub label: String,
+}
+
+/// Track metadata grit records itself, which a fetch from the provider
+/// never has. It annotates the local copy only, so it's left out of
+/// snapshot hashes too.
+const LOCAL_METADATA_KEYS: &[&str] = &["markers"];
+
+/// Track metadata filled in by a provider fetch. It describes the item
+/// rather than the playlist, and tracks grit pushed or fetched before a key
+/// existed don't have it, so it's left out of snapshot hashes.
+pub const PROVIDER_METADATA_KEYS: &[&str] = &["added_by", "kind", "explicit"];
+
+/// How much of a playlist `Provider::fetch_with` retrieves
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchOptions {
+    /// Stop after this many tracks (`grit init --depth`)
+    pub limit: Option<usize>,
+    /// Keep unavailable items as placeholder tracks instead of dropping
+    /// them, so positions match the remote playlist
+    pub include_unavailable: bool,
+}
+
+impl FetchOptions {
+    /// Whether `count` tracks are enough, so pagination can stop
+    pub fn reached(&self, count: usize) -> bool {
+        self.limit.is_some_and(|limit| count >= limit)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlaylistSnapshot {
     pub id: String,
@@ -39,6 +173,85 @@ pub struct PlaylistSnapshot {
     pub snapshot_hash: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
+    #[serde(default)]
+    pub schema_version: u32,
+}
+
+impl PlaylistSnapshot {
+    /// Copy grit's own track metadata (markers) from `local` onto the same
+    /// tracks here, so a fetched snapshot only differs by what the provider
+    /// changed and a pull doesn't drop it
+    pub fn keep_local_metadata(&mut self, local: &PlaylistSnapshot) {
+        for track in &mut self.tracks {
+            let Some(local_meta) = local
+                .tracks
+                .iter()
+                .find(|t| t.same_as(track))
+                .and_then(|t| t.metadata.as_ref())
+            else {
+                continue;
+            };
+            for key in LOCAL_METADATA_KEYS {
+                let Some(value) = local_meta.get(*key) else {
+                    continue;
+                };
+                let metadata = track
+                    .metadata
+                    .get_or_insert_with(|| serde_json::Value::Object(Default::default()));
+                if let Some(map) = metadata.as_object_mut() {
+                    map.insert(key.to_string(), value.clone());
+                }
+            }
+        }
+    }
+
+    /// Whether this snapshot was fetched with `include_unavailable`, so a
+    /// missing placeholder means the item is gone rather than left out
+    pub fn keeps_unavailable(&self) -> bool {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.get("keeps_unavailable"))
+            .and_then(|k| k.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Record that placeholders were kept. Called by providers.
+    pub fn set_keeps_unavailable(&mut self, keeps: bool) {
+        if keeps {
+            self.metadata.get_or_insert_with(|| serde_json::json!({}))["keeps_unavailable"] =
+                true.into();
+        }
+    }
+
+    /// Remove placeholder tracks, leaving only what can be played
+    pub fn drop_unavailable(&mut self) {
+        self.tracks.retain(|t| !t.is_unavailable());
+        if let Some(map) = self.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+            map.remove("keeps_unavailable");
+            if map.is_empty() {
+                self.metadata = None;
+            }
+        }
+    }
+
+    /// Track limit this snapshot was fetched with, when it holds only the
+    /// start of the playlist. Tracks past it are unknown, not removed.
+    pub fn depth(&self) -> Option<usize> {
+        let depth = self.metadata.as_ref()?.get("depth")?.as_u64()?;
+        Some(depth as usize)
+    }
+
+    /// Cut the snapshot to `limit` tracks, recording the depth when tracks
+    /// were left out: either past `limit`, or on pages not fetched (`more`)
+    pub fn limit_to(&mut self, limit: Option<usize>, more: bool) {
+        let Some(limit) = limit else {
+            return;
+        };
+        if more || self.tracks.len() > limit {
+            self.tracks.truncate(limit);
+            self.metadata.get_or_insert_with(|| serde_json::json!({}))["depth"] = limit.into();
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,4 +283,7 @@ pub struct OAuthToken {
     pub expires_at: Option<u64>, // unix timestamp
     pub token_type: String,
     pub scope: Option<String>,
+    /// Client id the token was issued to, so refreshes don't depend on env vars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
 }
@@ -1,18 +1,27 @@
 use crate::provider::{
-    DiffPatch, OAuthToken, PlaylistSnapshot, Provider, ProviderKind, Track, TrackChange,
+    id_batches, DiffPatch, FetchOptions, OAuthToken, PlaylistSnapshot, Provider, ProviderKind,
+    Track, TrackChange, LIKED_PLAYLIST_ID, MAX_SEARCH_LIMIT, SCHEMA_VERSION,
 };
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use tokio::sync::Mutex;
 
 const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
 const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
 const API_BASE: &str = "https://www.googleapis.com/youtube/v3";
+/// Quota units charged per `playlistItems` insert, update or delete
+const WRITE_COST: u64 = 50;
+/// Quota units charged per page of a `playlistItems` listing
+const READ_COST: u64 = 1;
+const ITEMS_PER_PAGE: u64 = 50;
 
 pub struct YoutubeProvider {
-    client_id: String,
-    client_secret: String,
+    client_id: Option<String>,
+    client_secret: Option<String>,
     token: Mutex<Option<OAuthToken>>,
     grit_dir: Option<std::path::PathBuf>,
     http: reqwest::Client,
@@ -44,6 +53,27 @@ struct YoutubePlaylistSnippet {
     description: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct YoutubeChannelResponse {
+    items: Vec<YoutubeChannel>,
+}
+
+#[derive(Deserialize)]
+struct YoutubeCreatedPlaylist {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct YoutubeChannel {
+    id: String,
+    snippet: YoutubeChannelSnippet,
+}
+
+#[derive(Deserialize)]
+struct YoutubeChannelSnippet {
+    title: String,
+}
+
 #[derive(Deserialize)]
 struct YoutubePlaylistItemsResponse {
     items: Vec<YoutubePlaylistItem>,
@@ -75,15 +105,99 @@ struct YoutubeItemContentDetails {
 #[derive(Deserialize)]
 struct YoutubeVideoResponse {
     items: Vec<YoutubeVideo>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct YoutubeVideo {
+    id: String,
     snippet: YoutubeVideoSnippet,
     #[serde(rename = "contentDetails")]
     content_details: YoutubeVideoContentDetails,
 }
 
+impl YoutubeVideo {
+    /// Length in milliseconds; 0 for live streams and unparseable durations
+    fn duration_ms(&self) -> u64 {
+        let raw = &self.content_details.duration;
+        YoutubeProvider::parse_iso8601_duration(raw).unwrap_or_else(|| {
+            tracing::warn!("Unrecognized duration {:?} for video {}", raw, self.id);
+            0
+        })
+    }
+
+    fn into_track(self) -> Track {
+        Track {
+            duration_ms: self.duration_ms(),
+            id: self.id,
+            name: self.snippet.title,
+            artists: vec![self
+                .snippet
+                .channel_title
+                .unwrap_or_else(|| "Unknown".to_string())],
+            provider: ProviderKind::Youtube,
+            metadata: None,
+        }
+    }
+}
+
+fn response_cache_dir(grit_dir: &Path) -> PathBuf {
+    grit_dir.join("cache").join("youtube")
+}
+
+/// The playlist a GET reads, from `playlistId` or a `playlists?id=` lookup.
+/// Ids that aren't safe as a folder name are left unscoped.
+fn cached_playlist(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let key = if url.path().ends_with("/playlists") {
+        "id"
+    } else {
+        "playlistId"
+    };
+    url.query_pairs()
+        .find(|(name, _)| name == key)
+        .map(|(_, id)| id.into_owned())
+        .filter(|id| {
+            !id.is_empty()
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+/// Remove cached responses for playlists that are no longer tracked, and
+/// any loose files from before responses were filed by playlist
+pub fn prune_response_cache(grit_dir: &Path) -> Result<()> {
+    let dir = response_cache_dir(grit_dir);
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Ok(());
+    };
+    let tracked = crate::state::snapshot::tracked_playlist_ids(grit_dir)?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_tracked = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| tracked.iter().any(|id| id == name));
+        if !path.is_dir() {
+            std::fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+        } else if !is_tracked {
+            std::fs::remove_dir_all(&path)
+                .with_context(|| format!("Failed to remove {:?}", path))?;
+        }
+    }
+    Ok(())
+}
+
+/// A GET response kept with its ETag, so an unchanged resource can be
+/// answered from disk when YouTube replies `304 Not Modified`
+#[derive(Serialize, Deserialize)]
+struct CachedResponse {
+    etag: String,
+    body: String,
+}
+
 #[derive(Deserialize)]
 struct YoutubeVideoSnippet {
     title: String,
@@ -112,22 +226,32 @@ impl YoutubeTokenResponse {
             expires_at: Some(expires_at),
             token_type: self.token_type,
             scope: self.scope,
+            client_id: None,
         }
     }
 }
 
 impl YoutubeProvider {
     pub fn new(client_id: String, client_secret: String) -> Self {
+        Self::with_credentials(Some(client_id), Some(client_secret))
+    }
+
+    /// Create a provider whose client credentials may be unknown.
+    /// They are only required once a token has to be exchanged or refreshed.
+    pub fn with_credentials(client_id: Option<String>, client_secret: Option<String>) -> Self {
         Self {
             client_id,
             client_secret,
             token: Mutex::new(None),
             grit_dir: None,
-            http: reqwest::Client::new(),
+            http: crate::utils::http::client(),
         }
     }
 
     pub fn with_token(mut self, token: &OAuthToken, grit_dir: &std::path::Path) -> Self {
+        if self.client_id.is_none() {
+            self.client_id = token.client_id.clone();
+        }
         self.token = Mutex::new(Some(token.clone()));
         self.grit_dir = Some(grit_dir.to_path_buf());
         self
@@ -154,7 +278,7 @@ impl YoutubeProvider {
         drop(token_guard);
 
         if Self::is_token_expired(&current_token) {
-            println!("Token expired, refreshing...");
+            tracing::info!("Token expired, refreshing...");
             let new_token = self.refresh_token(&current_token).await?;
 
             if let Some(grit_dir) = &self.grit_dir {
@@ -169,6 +293,18 @@ impl YoutubeProvider {
         }
     }
 
+    fn client_credentials(&self) -> Result<(&str, &str)> {
+        let client_id = self
+            .client_id
+            .as_deref()
+            .context("YOUTUBE_CLIENT_ID not set (required to refresh the YouTube token)")?;
+        let client_secret = self
+            .client_secret
+            .as_deref()
+            .context("YOUTUBE_CLIENT_SECRET not set (required to refresh the YouTube token)")?;
+        Ok((client_id, client_secret))
+    }
+
     async fn token_request(&self, params: &[(&str, &str)]) -> Result<YoutubeTokenResponse> {
         let response = self
             .http
@@ -190,13 +326,26 @@ impl YoutubeProvider {
     }
 
     async fn api_get<T: serde::de::DeserializeOwned>(&self, url: &str, token: &str) -> Result<T> {
-        let response = self
+        tracing::debug!("GET {}", url);
+        let cached = self.load_cached_response(url);
+
+        let mut request = self
             .http
             .get(url)
-            .header("Authorization", format!("Bearer {}", token))
-            .send()
-            .await
-            .context("Failed to send API request")?;
+            .header("Authorization", format!("Bearer {}", token));
+        if let Some(cached) = &cached {
+            request = request.header(reqwest::header::IF_NONE_MATCH, &cached.etag);
+        }
+        let response = request.send().await.context("Failed to send API request")?;
+
+        // A server that ignores If-None-Match just answers 200 as usual
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                tracing::debug!("Not modified, reusing cached response for {}", url);
+                return serde_json::from_str(&cached.body)
+                    .context("Failed to parse cached API response");
+            }
+        }
 
         if !response.status().is_success() {
             let status = response.status();
@@ -204,10 +353,60 @@ impl YoutubeProvider {
             anyhow::bail!("YouTube API error {}: {}", status, error_text);
         }
 
-        response
-            .json()
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response
+            .text()
             .await
-            .context("Failed to parse API response")
+            .context("Failed to read API response")?;
+        let parsed = serde_json::from_str(&body).context("Failed to parse API response")?;
+
+        if let Some(etag) = etag {
+            self.save_cached_response(url, CachedResponse { etag, body });
+        }
+        Ok(parsed)
+    }
+
+    /// One file per URL under `cache/youtube/<playlist-id>`, named by the
+    /// URL's hash, so `prune_response_cache` can drop a playlist's responses
+    /// together. Only playlist reads are cached; searches and video lookups
+    /// rarely repeat and would pile up with nothing to prune them by.
+    fn response_cache_path(&self, url: &str) -> Option<PathBuf> {
+        let grit_dir = self.grit_dir.as_ref()?;
+        let playlist_id = cached_playlist(url)?;
+        let key: String = Sha256::digest(url.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Some(
+            response_cache_dir(grit_dir)
+                .join(playlist_id)
+                .join(format!("{}.json", key)),
+        )
+    }
+
+    fn load_cached_response(&self, url: &str) -> Option<CachedResponse> {
+        let json = std::fs::read_to_string(self.response_cache_path(url)?).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    fn save_cached_response(&self, url: &str, cached: CachedResponse) {
+        let Some(path) = self.response_cache_path(url) else {
+            return;
+        };
+        // A lost cache entry only costs a full response next time
+        if let Err(e) = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(anyhow::Error::from)
+            .and_then(|()| serde_json::to_string(&cached).map_err(anyhow::Error::from))
+            .and_then(|json| std::fs::write(&path, json).map_err(anyhow::Error::from))
+        {
+            tracing::warn!("Failed to cache response in {:?}: {}", path, e);
+        }
     }
 
     async fn fetch_playlist_item_ids(
@@ -243,29 +442,173 @@ impl YoutubeProvider {
         Ok(items)
     }
 
-    fn parse_iso8601_duration(duration: &str) -> u64 {
-        // Parse ISO 8601 duration format (PT1H2M3S) to milliseconds
-        let duration = duration.trim_start_matches("PT");
-        let mut total_ms = 0u64;
-        let mut num = String::new();
+    /// The user's liked videos, paged from `videos?myRating=like`
+    async fn fetch_liked(&self, options: &FetchOptions) -> Result<PlaylistSnapshot> {
+        let token = self.get_token().await?;
 
-        for ch in duration.chars() {
-            if ch.is_ascii_digit() {
-                num.push(ch);
-            } else {
-                if let Ok(value) = num.parse::<u64>() {
-                    total_ms += match ch {
-                        'H' => value * 3600 * 1000,
-                        'M' => value * 60 * 1000,
-                        'S' => value * 1000,
-                        _ => 0,
-                    };
+        let mut all_tracks = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut url = format!(
+                "{}/videos?part=snippet,contentDetails&myRating=like&maxResults=50",
+                API_BASE
+            );
+
+            if let Some(token_str) = &page_token {
+                url.push_str(&format!("&pageToken={}", token_str));
+            }
+
+            let resp: YoutubeVideoResponse = self.api_get(&url, &token).await?;
+
+            all_tracks.extend(resp.items.into_iter().map(YoutubeVideo::into_track));
+
+            page_token = resp.next_page_token;
+            if page_token.is_none() || options.reached(all_tracks.len()) {
+                break;
+            }
+        }
+
+        let mut snapshot = PlaylistSnapshot {
+            id: LIKED_PLAYLIST_ID.to_string(),
+            name: "Liked videos".to_string(),
+            description: Some("Liked videos (read-only)".to_string()),
+            tracks: all_tracks,
+            provider: ProviderKind::Youtube,
+            snapshot_hash: format!("yt-{}", LIKED_PLAYLIST_ID),
+            metadata: None,
+            schema_version: SCHEMA_VERSION,
+        };
+        snapshot.limit_to(options.limit, page_token.is_some());
+        Ok(snapshot)
+    }
+
+    /// Playlist metadata URL. Authenticated with the OAuth bearer token only,
+    /// so private and unlisted playlists of the signed-in user resolve too.
+    fn playlist_url(playlist_id: &str) -> String {
+        format!(
+            "{}/playlists?part=snippet,contentDetails&id={}",
+            API_BASE,
+            urlencoding::encode(playlist_id)
+        )
+    }
+
+    /// Parse an ISO 8601 duration (`PT1H2M3S`, `P1DT3H`) to milliseconds.
+    /// Returns `None` for anything malformed, so it isn't mistaken for a
+    /// zero-length video. Live streams report `P0D`, which parses as 0.
+    fn parse_iso8601_duration(duration: &str) -> Option<u64> {
+        const DATE_UNITS: &[(char, u64)] = &[('D', 86_400)];
+        const TIME_UNITS: &[(char, u64)] = &[('H', 3_600), ('M', 60), ('S', 1)];
+
+        let rest = duration.strip_prefix('P')?;
+        let (date, time) = match rest.split_once('T') {
+            // A 'T' must be followed by at least one time component
+            Some((_, "")) => return None,
+            Some((date, time)) => (date, time),
+            None => (rest, ""),
+        };
+        if date.is_empty() && time.is_empty() {
+            return None;
+        }
+
+        let mut total_secs = 0u64;
+        for (part, units) in [(date, DATE_UNITS), (time, TIME_UNITS)] {
+            let mut units = units.iter();
+            let mut num = String::new();
+            for ch in part.chars() {
+                if ch.is_ascii_digit() {
+                    num.push(ch);
+                    continue;
                 }
+                // Units have to appear in order, each at most once
+                let (_, secs) = units.find(|(unit, _)| *unit == ch)?;
+                let value: u64 = num.parse().ok()?;
+                total_secs = total_secs.checked_add(value.checked_mul(*secs)?)?;
                 num.clear();
             }
+            if !num.is_empty() {
+                return None;
+            }
+        }
+
+        total_secs.checked_mul(1000)
+    }
+}
+
+/// Write requests `apply` makes to turn `current` (video ids in playlist
+/// order) into `desired`: one per removal and addition, plus one per
+/// reorder move, simulated the same way `apply` performs them
+fn planned_writes(current: &[String], patch: &DiffPatch, desired: &[Track]) -> u64 {
+    let mut items: Vec<&str> = current.iter().map(String::as_str).collect();
+    let mut writes = 0;
+
+    for change in &patch.changes {
+        if let TrackChange::Removed { track, .. } = change {
+            if let Some(pos) = items.iter().position(|id| *id == track.id) {
+                items.remove(pos);
+                writes += 1;
+            }
+        }
+    }
+    for change in &patch.changes {
+        if let TrackChange::Added { track, .. } = change {
+            items.push(&track.id);
+            writes += 1;
+        }
+    }
+    for (desired_idx, track) in desired.iter().enumerate() {
+        let current_idx = items
+            .iter()
+            .skip(desired_idx)
+            .position(|id| *id == track.id)
+            .map(|offset| desired_idx + offset);
+        if let Some(current_idx) = current_idx {
+            if current_idx != desired_idx {
+                let item = items.remove(current_idx);
+                items.insert(desired_idx, item);
+                writes += 1;
+            }
         }
+    }
+
+    writes
+}
+
+/// Whether an error body is YouTube's daily quota running out, as opposed
+/// to a permission problem (both come back as 403)
+fn is_quota_exceeded(body: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| value["error"]["errors"].as_array().cloned())
+        .is_some_and(|errors| errors.iter().any(|e| e["reason"] == "quotaExceeded"))
+}
 
-        total_ms
+/// Counts the writes made by `apply`, so running out of quota halfway can
+/// report how far the push got
+struct ApplyProgress {
+    done: u64,
+    total: u64,
+}
+
+impl ApplyProgress {
+    async fn send(&mut self, request: reqwest::RequestBuilder) -> Result<()> {
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            if is_quota_exceeded(&body) {
+                anyhow::bail!(
+                    "YouTube quota exceeded after {} of {} operations; {} remain. \
+                     Push again once the quota resets (midnight Pacific time) to resume.",
+                    self.done,
+                    self.total,
+                    self.total - self.done
+                );
+            }
+            anyhow::bail!("YouTube API error {}: {}", status, body);
+        }
+        self.done += 1;
+        Ok(())
     }
 }
 
@@ -277,7 +620,7 @@ impl Provider for YoutubeProvider {
         format!(
             "{}?client_id={}&redirect_uri={}&response_type=code&scope={}&state={}&access_type=offline&prompt=consent",
             AUTH_URL,
-            urlencoding::encode(&self.client_id),
+            urlencoding::encode(self.client_id.as_deref().unwrap_or_default()),
             urlencoding::encode(redirect_uri),
             urlencoding::encode(scopes),
             urlencoding::encode(state),
@@ -285,17 +628,19 @@ impl Provider for YoutubeProvider {
     }
 
     async fn exchange_code(&self, code: &str, redirect_uri: &str) -> Result<OAuthToken> {
+        let (client_id, client_secret) = self.client_credentials()?;
         let params = [
             ("grant_type", "authorization_code"),
             ("code", code),
             ("redirect_uri", redirect_uri),
-            ("client_id", &self.client_id),
-            ("client_secret", &self.client_secret),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
         ];
 
-        self.token_request(&params)
-            .await
-            .map(|r| r.into_oauth_token())
+        let mut token = self.token_request(&params).await?.into_oauth_token();
+        token.client_id = Some(client_id.to_string());
+
+        Ok(token)
     }
 
     async fn refresh_token(&self, token: &OAuthToken) -> Result<OAuthToken> {
@@ -304,11 +649,12 @@ impl Provider for YoutubeProvider {
             .as_ref()
             .context("No refresh token available")?;
 
+        let (client_id, client_secret) = self.client_credentials()?;
         let params = [
             ("grant_type", "refresh_token"),
             ("refresh_token", refresh.as_str()),
-            ("client_id", &self.client_id),
-            ("client_secret", &self.client_secret),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
         ];
 
         let mut new_token = self.token_request(&params).await?.into_oauth_token();
@@ -316,25 +662,34 @@ impl Provider for YoutubeProvider {
         if new_token.refresh_token.is_none() {
             new_token.refresh_token = token.refresh_token.clone();
         }
+        new_token.client_id = Some(client_id.to_string());
 
         Ok(new_token)
     }
 
-    async fn fetch(&self, playlist_id: &str) -> Result<PlaylistSnapshot> {
-        let token = self.get_token().await?;
+    async fn fetch_with(
+        &self,
+        playlist_id: &str,
+        options: &FetchOptions,
+    ) -> Result<PlaylistSnapshot> {
+        if playlist_id == LIKED_PLAYLIST_ID {
+            return self.fetch_liked(options).await;
+        }
 
-        let playlist_url = format!(
-            "{}/playlists?part=snippet,contentDetails&id={}&key={}",
-            API_BASE, playlist_id, self.client_id
-        );
+        let token = self.get_token().await?;
 
-        let playlist_resp: YoutubePlaylistResponse = self.api_get(&playlist_url, &token).await?;
+        let playlist_resp: YoutubePlaylistResponse = self
+            .api_get(&Self::playlist_url(playlist_id), &token)
+            .await?;
 
-        let playlist = playlist_resp
-            .items
-            .into_iter()
-            .next()
-            .context("Playlist not found")?;
+        let playlist = playlist_resp.items.into_iter().next().with_context(|| {
+            if playlist_id.starts_with("RD") {
+                "Playlist not found (YouTube mixes are generated per viewer and can't be fetched)"
+                    .to_string()
+            } else {
+                "Playlist not found".to_string()
+            }
+        })?;
 
         let mut all_tracks = Vec::new();
         let mut page_token: Option<String> = None;
@@ -365,33 +720,48 @@ impl Provider for YoutubeProvider {
                 );
 
                 let videos_resp: YoutubeVideoResponse = self.api_get(&videos_url, &token).await?;
+                // Deleted and private videos are missing here, so match by id
+                let durations: HashMap<&str, u64> = videos_resp
+                    .items
+                    .iter()
+                    .map(|video| (video.id.as_str(), video.duration_ms()))
+                    .collect();
+
+                for item in &items_resp.items {
+                    let video_id = &item.content_details.video_id;
+                    let duration_ms = durations.get(video_id.as_str()).copied();
+                    if duration_ms.is_none() && !options.include_unavailable {
+                        continue;
+                    }
 
-                for (item, video) in items_resp.items.iter().zip(videos_resp.items.iter()) {
-                    let duration_ms = Self::parse_iso8601_duration(&video.content_details.duration);
                     let artist = item
                         .snippet
                         .video_owner_channel_title
                         .clone()
                         .unwrap_or_else(|| "Unknown".to_string());
 
-                    all_tracks.push(Track {
-                        id: item.content_details.video_id.clone(),
+                    let mut track = Track {
+                        id: video_id.clone(),
                         name: item.snippet.title.clone(),
                         artists: vec![artist],
-                        duration_ms,
+                        duration_ms: duration_ms.unwrap_or(0),
                         provider: ProviderKind::Youtube,
                         metadata: None,
-                    });
+                    };
+                    if duration_ms.is_none() {
+                        track.mark_unavailable();
+                    }
+                    all_tracks.push(track);
                 }
             }
 
             page_token = items_resp.next_page_token;
-            if page_token.is_none() {
+            if page_token.is_none() || options.reached(all_tracks.len()) {
                 break;
             }
         }
 
-        Ok(PlaylistSnapshot {
+        let mut snapshot = PlaylistSnapshot {
             id: playlist.id.clone(),
             name: playlist.snippet.title,
             description: playlist.snippet.description,
@@ -399,7 +769,11 @@ impl Provider for YoutubeProvider {
             provider: ProviderKind::Youtube,
             snapshot_hash: format!("yt-{}", playlist.id),
             metadata: None,
-        })
+            schema_version: SCHEMA_VERSION,
+        };
+        snapshot.set_keeps_unavailable(options.include_unavailable);
+        snapshot.limit_to(options.limit, page_token.is_some());
+        Ok(snapshot)
     }
 
     async fn apply(
@@ -412,6 +786,11 @@ impl Provider for YoutubeProvider {
 
         // Step 1: Remove tracks that shouldn't be there
         let playlist_items = self.fetch_playlist_item_ids(playlist_id, &token).await?;
+        let current: Vec<String> = playlist_items.iter().map(|(_, vid)| vid.clone()).collect();
+        let mut progress = ApplyProgress {
+            done: 0,
+            total: planned_writes(&current, patch, &desired_state.tracks),
+        };
 
         for change in &patch.changes {
             if let TrackChange::Removed { track, .. } = change {
@@ -419,12 +798,13 @@ impl Provider for YoutubeProvider {
                 {
                     let url = format!("{}/playlistItems?id={}", API_BASE, item_id);
 
-                    self.http
-                        .delete(&url)
-                        .header("Authorization", format!("Bearer {}", token))
-                        .send()
-                        .await?
-                        .error_for_status()?;
+                    progress
+                        .send(
+                            self.http
+                                .delete(&url)
+                                .header("Authorization", format!("Bearer {}", token)),
+                        )
+                        .await?;
                 }
             }
         }
@@ -443,52 +823,67 @@ impl Provider for YoutubeProvider {
                     }
                 });
 
-                self.http
-                    .post(format!("{}/playlistItems?part=snippet", API_BASE))
-                    .header("Authorization", format!("Bearer {}", token))
-                    .json(&body)
-                    .send()
-                    .await?
-                    .error_for_status()?;
+                progress
+                    .send(
+                        self.http
+                            .post(format!("{}/playlistItems?part=snippet", API_BASE))
+                            .header("Authorization", format!("Bearer {}", token))
+                            .json(&body),
+                    )
+                    .await?;
             }
         }
 
         // Step 3: Reorder playlist to match desired state
-        // Process from the beginning, moving each track to its correct position
-        for (desired_idx, desired_track) in desired_state.tracks.iter().enumerate() {
-            // Fetch current state to find where this track is now and get its item_id
-            let current = self.fetch(playlist_id).await?;
-            let playlist_items = self.fetch_playlist_item_ids(playlist_id, &token).await?;
+        // Process from the beginning, moving each track to its correct position.
+        // The order is fetched once and kept up to date as moves succeed;
+        // moving a later item to `desired_idx` never disturbs the tracks
+        // before it, so everything ahead of `desired_idx` is final. Tracks
+        // already in place cost no request, so pushing again after an
+        // interrupted reorder resumes where it stopped.
+        let mut items = self.fetch_playlist_item_ids(playlist_id, &token).await?;
 
-            let current_idx = current.tracks.iter().position(|t| t.id == desired_track.id);
+        for (desired_idx, desired_track) in desired_state.tracks.iter().enumerate() {
+            let current_idx = items
+                .iter()
+                .skip(desired_idx)
+                .position(|(_, vid)| vid == &desired_track.id)
+                .map(|offset| desired_idx + offset);
 
             if let Some(current_idx) = current_idx {
                 if current_idx != desired_idx {
-                    // Find the item_id for this track
-                    if let Some((item_id, _)) = playlist_items
-                        .iter()
-                        .find(|(_, vid)| vid == &desired_track.id)
-                    {
-                        let body = serde_json::json!({
-                            "id": item_id,
-                            "snippet": {
-                                "playlistId": playlist_id,
-                                "resourceId": {
-                                    "kind": "youtube#video",
-                                    "videoId": desired_track.id
-                                },
-                                "position": desired_idx
-                            }
-                        });
+                    let body = serde_json::json!({
+                        "id": items[current_idx].0,
+                        "snippet": {
+                            "playlistId": playlist_id,
+                            "resourceId": {
+                                "kind": "youtube#video",
+                                "videoId": desired_track.id
+                            },
+                            "position": desired_idx
+                        }
+                    });
 
-                        self.http
-                            .put(format!("{}/playlistItems?part=snippet", API_BASE))
-                            .header("Authorization", format!("Bearer {}", token))
-                            .json(&body)
-                            .send()
-                            .await?
-                            .error_for_status()?;
-                    }
+                    // Reorders can outlast the token, so check it per request
+                    let token = self.get_token().await?;
+                    progress
+                        .send(
+                            self.http
+                                .put(format!("{}/playlistItems?part=snippet", API_BASE))
+                                .header("Authorization", format!("Bearer {}", token))
+                                .json(&body),
+                        )
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Reorder stopped at track {} of {}; push again to resume",
+                                desired_idx,
+                                desired_state.tracks.len()
+                            )
+                        })?;
+
+                    let item = items.remove(current_idx);
+                    items.insert(desired_idx, item);
                 }
             }
         }
@@ -496,16 +891,31 @@ impl Provider for YoutubeProvider {
         Ok(())
     }
 
+    fn estimate_quota(
+        &self,
+        remote: &PlaylistSnapshot,
+        patch: &DiffPatch,
+        desired_state: &PlaylistSnapshot,
+    ) -> Option<u64> {
+        let current: Vec<String> = remote.tracks.iter().map(|t| t.id.clone()).collect();
+        let writes = planned_writes(&current, patch, &desired_state.tracks);
+        // `apply` lists the playlist items twice: before removing and
+        // again before reordering
+        let pages = (current.len() as u64).div_ceil(ITEMS_PER_PAGE).max(1);
+        Some(writes * WRITE_COST + 2 * pages * READ_COST)
+    }
+
     async fn playable_url(&self, track: &Track) -> Result<String> {
         Ok(format!("https://www.youtube.com/watch?v={}", track.id))
     }
 
-    async fn search_by_query(&self, query: &str) -> Result<Vec<Track>> {
+    async fn search_by_query(&self, query: &str, limit: usize) -> Result<Vec<Track>> {
         let token = self.get_token().await?;
         let url = format!(
-            "{}/search?part=snippet&q={}&type=video&maxResults=10",
+            "{}/search?part=snippet&q={}&type=video&maxResults={}",
             API_BASE,
-            urlencoding::encode(query)
+            urlencoding::encode(query),
+            limit.clamp(1, MAX_SEARCH_LIMIT)
         );
 
         #[derive(Deserialize)]
@@ -549,7 +959,7 @@ impl Provider for YoutubeProvider {
             .iter()
             .zip(videos_resp.items.iter())
             .map(|(item, video)| {
-                let duration_ms = Self::parse_iso8601_duration(&video.content_details.duration);
+                let duration_ms = video.duration_ms();
                 let artist = video
                     .snippet
                     .channel_title
@@ -581,20 +991,36 @@ impl Provider for YoutubeProvider {
 
         let video = resp.items.into_iter().next().context("Track not found")?;
 
-        let duration_ms = Self::parse_iso8601_duration(&video.content_details.duration);
-        let artist = video
-            .snippet
-            .channel_title
-            .unwrap_or_else(|| "Unknown".to_string());
-
-        Ok(Track {
-            id: track_id.to_string(),
-            name: video.snippet.title,
-            artists: vec![artist],
-            duration_ms,
-            provider: ProviderKind::Youtube,
-            metadata: None,
-        })
+        Ok(video.into_track())
+    }
+
+    async fn fetch_tracks(&self, track_ids: &[String]) -> Result<Vec<Track>> {
+        let token = self.get_token().await?;
+        let mut tracks = Vec::with_capacity(track_ids.len());
+
+        for batch in id_batches(track_ids) {
+            let url = format!(
+                "{}/videos?part=snippet,contentDetails&maxResults=50&id={}",
+                API_BASE, batch
+            );
+            let resp: YoutubeVideoResponse = self.api_get(&url, &token).await?;
+
+            // Unknown or private videos are left out of the response rather
+            // than nulled, so match them back up by id
+            let found: HashMap<String, Track> = resp
+                .items
+                .into_iter()
+                .map(|video| (video.id.clone(), video.into_track()))
+                .collect();
+            for id in batch.split(',') {
+                let track = found
+                    .get(id)
+                    .with_context(|| format!("Track not found: {}", id))?;
+                tracks.push(track.clone());
+            }
+        }
+
+        Ok(tracks)
     }
 
     async fn can_modify_playlist(&self, playlist_id: &str) -> Result<bool> {
@@ -606,4 +1032,181 @@ impl Provider for YoutubeProvider {
             Err(_) => Ok(false),
         }
     }
+
+    async fn current_user(&self) -> Result<String> {
+        let token = self.get_token().await?;
+        let url = format!("{}/channels?part=snippet&mine=true", API_BASE);
+        let resp: YoutubeChannelResponse = self.api_get(&url, &token).await?;
+
+        let channel = resp
+            .items
+            .into_iter()
+            .next()
+            .context("No YouTube channel found for this account")?;
+
+        Ok(format!("{} ({})", channel.snippet.title, channel.id))
+    }
+
+    async fn create_playlist(&self, name: &str, description: Option<&str>) -> Result<String> {
+        let token = self.get_token().await?;
+
+        let body = serde_json::json!({
+            "snippet": {
+                "title": name,
+                "description": description.unwrap_or_default(),
+            },
+            "status": { "privacyStatus": "private" },
+        });
+        let created: YoutubeCreatedPlaylist = self
+            .http
+            .post(format!("{}/playlists?part=snippet,status", API_BASE))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(created.id)
+    }
+
+    async fn delete_playlist(&self, playlist_id: &str) -> Result<()> {
+        let token = self.get_token().await?;
+
+        self.http
+            .delete(format!("{}/playlists?id={}", API_BASE, playlist_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_iso8601_duration() {
+        let parse = YoutubeProvider::parse_iso8601_duration;
+        assert_eq!(parse("PT1H2M3S"), Some(3_723_000));
+        assert_eq!(parse("PT45S"), Some(45_000));
+        assert_eq!(parse("P1DT3H"), Some(97_200_000));
+        assert_eq!(parse("PT0S"), Some(0));
+        assert_eq!(parse("P0D"), Some(0));
+
+        for garbage in [
+            "", "PT", "P", "1H2M", "PT3S2M", "PT1X", "PT12", "P1DT", "banana",
+        ] {
+            assert_eq!(parse(garbage), None, "{:?}", garbage);
+        }
+    }
+
+    #[test]
+    fn test_planned_writes_counts_removals_additions_and_moves() {
+        let current: Vec<String> = ["a", "b", "c", "d"].map(String::from).to_vec();
+        let desired = vec![
+            Track::test("d"),
+            Track::test("a"),
+            Track::test("e"),
+            Track::test("c"),
+        ];
+        let patch = DiffPatch {
+            changes: vec![
+                TrackChange::Removed {
+                    track: Track::test("b"),
+                    index: 1,
+                },
+                TrackChange::Added {
+                    track: Track::test("e"),
+                    index: 2,
+                },
+            ],
+        };
+
+        // [a c d e] -> move d to 0 -> [d a c e] -> move e to 2 -> [d a e c]
+        assert_eq!(planned_writes(&current, &patch, &desired), 4);
+
+        // Already in order: nothing to write
+        let in_order: Vec<String> = ["d", "a", "e", "c"].map(String::from).to_vec();
+        let empty = DiffPatch::default();
+        assert_eq!(planned_writes(&in_order, &empty, &desired), 0);
+    }
+
+    #[test]
+    fn test_is_quota_exceeded() {
+        let quota = r#"{"error":{"code":403,"errors":[{"reason":"quotaExceeded"}]}}"#;
+        let forbidden = r#"{"error":{"code":403,"errors":[{"reason":"forbidden"}]}}"#;
+        assert!(is_quota_exceeded(quota));
+        assert!(!is_quota_exceeded(forbidden));
+        assert!(!is_quota_exceeded("not json"));
+    }
+
+    #[test]
+    fn test_cached_responses_are_keyed_by_url() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut provider = YoutubeProvider::new(String::new(), String::new());
+        provider.grit_dir = Some(dir.path().to_path_buf());
+
+        let url = format!("{}/playlistItems?playlistId=PL1", API_BASE);
+        assert!(provider.load_cached_response(&url).is_none());
+
+        provider.save_cached_response(
+            &url,
+            CachedResponse {
+                etag: "\"abc\"".to_string(),
+                body: "{}".to_string(),
+            },
+        );
+        let cached = provider.load_cached_response(&url).unwrap();
+        assert_eq!(cached.etag, "\"abc\"");
+        assert_eq!(cached.body, "{}");
+
+        let other = format!("{}/playlistItems?playlistId=PL2", API_BASE);
+        assert!(provider.load_cached_response(&other).is_none());
+    }
+
+    #[test]
+    fn test_prune_drops_responses_for_untracked_playlists() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut provider = YoutubeProvider::new(String::new(), String::new());
+        provider.grit_dir = Some(dir.path().to_path_buf());
+        std::fs::create_dir_all(crate::state::snapshot::playlist_dir(dir.path(), "PL1")).unwrap();
+
+        let urls = [
+            format!("{}/playlistItems?playlistId=PL1", API_BASE),
+            YoutubeProvider::playlist_url("PL2"),
+            format!("{}/videos?part=snippet&id=abc", API_BASE),
+        ];
+        for url in &urls {
+            provider.save_cached_response(
+                url,
+                CachedResponse {
+                    etag: "\"abc\"".to_string(),
+                    body: "{}".to_string(),
+                },
+            );
+        }
+
+        prune_response_cache(dir.path()).unwrap();
+        assert!(provider.load_cached_response(&urls[0]).is_some());
+        assert!(provider.load_cached_response(&urls[1]).is_none());
+        // Not tied to a playlist, so never written in the first place
+        assert!(provider.load_cached_response(&urls[2]).is_none());
+
+        let loose = response_cache_dir(dir.path()).join("old.json");
+        std::fs::write(&loose, "{}").unwrap();
+        prune_response_cache(dir.path()).unwrap();
+        assert!(!loose.exists());
+    }
+
+    #[test]
+    fn test_playlist_url_has_no_api_key() {
+        let url = YoutubeProvider::playlist_url("PLunlisted123");
+        assert!(!url.contains("key="));
+        assert!(url.ends_with("&id=PLunlisted123"));
+    }
 }
@@ -0,0 +1,315 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::provider::ProviderKind;
+
+/// User settings stored in `.grit/config.toml`. Every field is optional so
+/// a missing or partial file falls back to the defaults, but unknown keys are
+/// rejected so typos don't go unnoticed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub auth: AuthConfig,
+    pub credentials: CredentialsConfig,
+    pub remote: RemoteConfig,
+    pub http: HttpConfig,
+    pub tui: TuiConfig,
+    pub playback: PlaybackConfig,
+    pub output: OutputConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Local port for the OAuth redirect (`http://127.0.0.1:<port>/callback`),
+    /// which must match the redirect URI registered with the provider
+    pub callback_port: u16,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            callback_port: 8888,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CredentialsConfig {
+    /// Account used when `--account` is not given
+    pub default_account: Option<String>,
+    /// Spotify app credentials, used when the environment doesn't set them
+    pub spotify: ClientConfig,
+    /// YouTube app credentials, used when the environment doesn't set them
+    pub youtube: ClientConfig,
+}
+
+impl CredentialsConfig {
+    pub fn client(&self, provider: ProviderKind) -> &ClientConfig {
+        match provider {
+            ProviderKind::Spotify => &self.spotify,
+            ProviderKind::Youtube => &self.youtube,
+        }
+    }
+}
+
+/// OAuth app credentials, written by `grit config set-client`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientConfig {
+    pub client_id: Option<String>,
+    /// Encrypted with the key in `.grit/encryption.key`, base64-encoded
+    pub client_secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RemoteConfig {
+    /// How long a fetched remote snapshot is reused, in seconds (0 disables)
+    pub cache_secs: u64,
+    /// Quota units a single YouTube push may spend before asking first.
+    /// YouTube grants 10,000 a day by default.
+    pub youtube_quota_budget: u64,
+    /// Keep deleted videos and local files as placeholder tracks when
+    /// fetching for init, pull and push, so positions match the remote
+    pub keep_unavailable: bool,
+    /// Market for Spotify track lookups ("US", "from_token", ...). Unset
+    /// leaves relinking to Spotify.
+    pub spotify_market: Option<String>,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            cache_secs: 60,
+            youtube_quota_budget: 10_000,
+            keep_unavailable: true,
+            spotify_market: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// Total time allowed for a request, in seconds
+    pub timeout_secs: u64,
+    pub connect_timeout_secs: u64,
+    /// Timeout for requests made while the TUI is running
+    pub interactive_timeout_secs: u64,
+}
+
+impl HttpConfig {
+    /// A zero timeout makes every request fail at once, so refuse it here
+    /// rather than as a confusing network error later
+    fn validate(&self) -> Result<()> {
+        for (key, secs) in [
+            ("timeout_secs", self.timeout_secs),
+            ("connect_timeout_secs", self.connect_timeout_secs),
+            ("interactive_timeout_secs", self.interactive_timeout_secs),
+        ] {
+            if secs == 0 {
+                bail!("[http] {} must be at least 1 second", key);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 20,
+            connect_timeout_secs: 5,
+            interactive_timeout_secs: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TuiConfig {
+    /// Up at the top of the playlist goes to the bottom and vice versa
+    pub wrap_navigation: bool,
+    /// How often the progress bar redraws while playing, in milliseconds
+    pub refresh_ms: u64,
+    /// Plain ASCII status symbols, for terminals without the Unicode ones
+    pub ascii_symbols: bool,
+}
+
+impl Default for TuiConfig {
+    fn default() -> Self {
+        Self {
+            wrap_navigation: false,
+            refresh_ms: 250,
+            ascii_symbols: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlaybackConfig {
+    /// Desktop notification when the track changes
+    pub notifications: bool,
+    /// How often the TUI asks Spotify what is playing, in seconds
+    pub spotify_poll_secs: u64,
+    /// How often the TUI checks the playlist file for changes, in seconds
+    pub file_watch_secs: u64,
+    /// Skip tracks the provider flags as explicit (toggle with `x`)
+    pub skip_explicit: bool,
+    /// What happens after the last track when repeat is off
+    pub on_playlist_end: PlaylistEnd,
+    /// JSON file kept up to date with the playing track, for status bars
+    pub now_playing_file: Option<PathBuf>,
+    /// Where lyrics are looked up, in order. The first synced result wins.
+    /// Only LRCLIB for now: Spotify's lyrics endpoint rejects grit's token.
+    pub lyrics_sources: Vec<LyricsService>,
+    /// Who shuffles Spotify playback: Spotify itself, or grit handing
+    /// Spotify its own shuffled order as with mpv (toggle with `S`)
+    pub spotify_shuffle: SpotifyShuffle,
+    /// Starting volume (0-100) for Spotify Connect playback, unless
+    /// `--volume` is given. Unset leaves the device as it is.
+    pub spotify_volume: Option<u8>,
+    /// Starting volume (0-100) for YouTube playback through mpv
+    pub mpv_volume: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpotifyShuffle {
+    /// Spotify's shuffle setting
+    #[default]
+    Native,
+    /// grit's queue shuffle, the same as for YouTube
+    Grit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LyricsService {
+    /// lrclib.net, matched by title, artist and duration
+    Lrclib,
+}
+
+/// End-of-playlist behavior with repeat off, for both `n` on the last track
+/// and the last track finishing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaylistEnd {
+    /// Stay on the last track
+    #[default]
+    Stop,
+    /// Start over from the first track
+    Loop,
+    /// Close the player
+    Quit,
+}
+
+impl Default for PlaybackConfig {
+    fn default() -> Self {
+        Self {
+            notifications: false,
+            spotify_poll_secs: 3,
+            file_watch_secs: 5,
+            skip_explicit: false,
+            on_playlist_end: PlaylistEnd::Stop,
+            now_playing_file: None,
+            lyrics_sources: vec![LyricsService::Lrclib],
+            spotify_shuffle: SpotifyShuffle::Native,
+            spotify_volume: None,
+            mpv_volume: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    /// Print provider track ids in `search`, `find` and `list` results
+    pub track_ids: bool,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self { track_ids: true }
+    }
+}
+
+pub fn config_path(grit_dir: &Path) -> PathBuf {
+    grit_dir.join("config.toml")
+}
+
+pub fn load(grit_dir: &Path) -> Result<Config> {
+    let path = config_path(grit_dir);
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read config from {:?}", path))?;
+    parse(&content).with_context(|| format!("Failed to parse config {:?}", path))
+}
+
+/// Parse the contents of a config file
+pub fn parse(content: &str) -> Result<Config> {
+    let config: Config = toml::from_str(content)?;
+    config.http.validate()?;
+    Ok(config)
+}
+
+/// Replace the config file with `content` once it parses. It's swapped in
+/// whole, so a failed write can't leave the file half updated.
+pub fn save(grit_dir: &Path, content: &str) -> Result<()> {
+    parse(content).context("Config is invalid")?;
+
+    let path = config_path(grit_dir);
+    let tmp = path.with_extension("toml.tmp");
+    fs::write(&tmp, content).with_context(|| format!("Failed to write {:?}", tmp))?;
+    fs::rename(&tmp, &path).with_context(|| format!("Failed to replace {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_config_uses_defaults() {
+        let config: Config = toml::from_str("[remote]\ncache_secs = 0\n").unwrap();
+        assert_eq!(config.remote.cache_secs, 0);
+        assert_eq!(config.http.timeout_secs, 20);
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        let err = toml::from_str::<Config>("[remote]\ncache_sec = 0\n").unwrap_err();
+        assert!(err.to_string().contains("cache_sec"));
+        assert!(err.to_string().contains("line 2"));
+
+        let err = toml::from_str::<Config>("[playbck]\nnotifications = true\n").unwrap_err();
+        assert!(err.to_string().contains("playbck"));
+    }
+
+    #[test]
+    fn test_zero_timeout_is_rejected() {
+        let err = parse("[http]\nconnect_timeout_secs = 0\n").unwrap_err();
+        assert!(err.to_string().contains("connect_timeout_secs"));
+        assert!(parse("[http]\ntimeout_secs = 1\n").is_ok());
+    }
+
+    #[test]
+    fn test_on_playlist_end_parses_lowercase() {
+        let config: Config = toml::from_str("[playback]\non_playlist_end = \"quit\"\n").unwrap();
+        assert_eq!(config.playback.on_playlist_end, PlaylistEnd::Quit);
+        assert_eq!(
+            Config::default().playback.on_playlist_end,
+            PlaylistEnd::Stop
+        );
+    }
+}
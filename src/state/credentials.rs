@@ -3,7 +3,45 @@ use crate::utils::crypto;
 use anyhow::{Context, Result};
 use base64::Engine;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Account name that maps to the legacy single-file credentials path
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+static ACCOUNT: OnceLock<String> = OnceLock::new();
+
+/// Select the account whose credentials are used for the rest of the process.
+///
+/// Must be called before any credentials are loaded. Selecting a different
+/// account once one is set is an error rather than a silent no-op.
+pub fn select_account(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!(
+            "Invalid account name '{}': use letters, digits, '-' or '_'",
+            name
+        );
+    }
+    let selected = ACCOUNT.get_or_init(|| name.to_string());
+    if selected != name {
+        anyhow::bail!(
+            "Account '{}' is already selected, can't switch to '{}'",
+            selected,
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Currently selected account name. Reading it settles on the default when
+/// none was selected, so a later `select_account` can't switch under it.
+pub fn account() -> &'static str {
+    ACCOUNT.get_or_init(|| DEFAULT_ACCOUNT.to_string())
+}
 
 pub fn save(grit_dir: &Path, provider: ProviderKind, token: &OAuthToken) -> Result<()> {
     let path = credentials_path(grit_dir, provider);
@@ -14,11 +52,7 @@ pub fn save(grit_dir: &Path, provider: ProviderKind, token: &OAuthToken) -> Resu
     }
 
     let json = serde_json::to_string(token).context("Failed to serialize token")?;
-
-    let encrypted =
-        crypto::encrypt(json.as_bytes(), grit_dir).context("Failed to encrypt credentials")?;
-
-    let encoded = base64::engine::general_purpose::STANDARD.encode(&encrypted);
+    let encoded = encrypt_secret(&json, grit_dir)?;
 
     fs::write(&path, encoded)
         .with_context(|| format!("Failed to write credentials to {:?}", path))?;
@@ -42,6 +76,22 @@ pub fn load(grit_dir: &Path, provider: ProviderKind) -> Result<Option<OAuthToken
     let encoded = fs::read_to_string(&path)
         .with_context(|| format!("Failed to read credentials from {:?}", path))?;
 
+    let json = decrypt_secret(&encoded, grit_dir)?;
+
+    let token = serde_json::from_str(&json).context("Failed to parse credentials")?;
+
+    Ok(Some(token))
+}
+
+/// Encrypt `secret` with the grit key and base64-encode it for storing as text
+pub fn encrypt_secret(secret: &str, grit_dir: &Path) -> Result<String> {
+    let encrypted =
+        crypto::encrypt(secret.as_bytes(), grit_dir).context("Failed to encrypt credentials")?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(&encrypted))
+}
+
+/// Reverse of [`encrypt_secret`]
+pub fn decrypt_secret(encoded: &str, grit_dir: &Path) -> Result<String> {
     let encrypted = base64::engine::general_purpose::STANDARD
         .decode(encoded.trim())
         .context("Failed to decode credentials")?;
@@ -49,11 +99,7 @@ pub fn load(grit_dir: &Path, provider: ProviderKind) -> Result<Option<OAuthToken
     let decrypted =
         crypto::decrypt(&encrypted, grit_dir).context("Failed to decrypt credentials")?;
 
-    let json = String::from_utf8(decrypted).context("Invalid UTF-8 in decrypted credentials")?;
-
-    let token = serde_json::from_str(&json).context("Failed to parse credentials")?;
-
-    Ok(Some(token))
+    String::from_utf8(decrypted).context("Invalid UTF-8 in decrypted credentials")
 }
 
 #[allow(dead_code)]
@@ -83,12 +129,20 @@ pub fn delete(grit_dir: &Path, provider: ProviderKind) -> Result<()> {
     Ok(())
 }
 
-fn credentials_path(grit_dir: &Path, provider: ProviderKind) -> std::path::PathBuf {
-    let filename = match provider {
-        ProviderKind::Spotify => "spotify.json",
-        ProviderKind::Youtube => "youtube.json",
-    };
-    grit_dir.join("credentials").join(filename)
+/// Credentials file for the selected account
+pub fn credentials_path(grit_dir: &Path, provider: ProviderKind) -> PathBuf {
+    account_path(grit_dir, provider, account())
+}
+
+fn account_path(grit_dir: &Path, provider: ProviderKind, account: &str) -> PathBuf {
+    let dir = grit_dir.join("credentials");
+    if account == DEFAULT_ACCOUNT {
+        // Keep the pre-multi-account location for the default account
+        dir.join(format!("{}.json", provider))
+    } else {
+        dir.join(provider.to_string())
+            .join(format!("{}.json", account))
+    }
 }
 
 #[cfg(test)]
@@ -103,6 +157,7 @@ mod tests {
             expires_at: Some(9999999999),
             token_type: "Bearer".to_string(),
             scope: Some("playlist-read-private".to_string()),
+            client_id: None,
         }
     }
 
@@ -120,6 +175,18 @@ mod tests {
         assert_eq!(loaded.refresh_token, token.refresh_token);
     }
 
+    #[test]
+    fn test_secret_round_trip() {
+        let temp = TempDir::new().unwrap();
+
+        let encoded = encrypt_secret("client-secret", temp.path()).unwrap();
+        assert!(!encoded.contains("client-secret"));
+        assert_eq!(
+            decrypt_secret(&encoded, temp.path()).unwrap(),
+            "client-secret"
+        );
+    }
+
     #[test]
     fn test_load_nonexistent() {
         let temp = TempDir::new().unwrap();
@@ -135,6 +202,7 @@ mod tests {
             expires_at: Some(9999999999), // Far future
             token_type: "Bearer".to_string(),
             scope: None,
+            client_id: None,
         };
         assert!(!is_expired(&token));
     }
@@ -147,6 +215,7 @@ mod tests {
             expires_at: Some(1000), // Long past
             token_type: "Bearer".to_string(),
             scope: None,
+            client_id: None,
         };
         assert!(is_expired(&token));
     }
@@ -159,6 +228,7 @@ mod tests {
             expires_at: None, // No expiry
             token_type: "Bearer".to_string(),
             scope: None,
+            client_id: None,
         };
         assert!(!is_expired(&token));
     }
@@ -185,6 +255,7 @@ mod tests {
             expires_at: None,
             token_type: "Bearer".to_string(),
             scope: None,
+            client_id: None,
         };
 
         let youtube_token = OAuthToken {
@@ -193,6 +264,7 @@ mod tests {
             expires_at: None,
             token_type: "Bearer".to_string(),
             scope: None,
+            client_id: None,
         };
 
         save(temp.path(), ProviderKind::Spotify, &spotify_token).unwrap();
@@ -204,4 +276,24 @@ mod tests {
         assert_eq!(loaded_spotify.access_token, "spotify_token");
         assert_eq!(loaded_youtube.access_token, "youtube_token");
     }
+
+    #[test]
+    fn test_account_path() {
+        let grit_dir = Path::new(".grit");
+
+        assert_eq!(
+            account_path(grit_dir, ProviderKind::Spotify, DEFAULT_ACCOUNT),
+            grit_dir.join("credentials/spotify.json")
+        );
+        assert_eq!(
+            account_path(grit_dir, ProviderKind::Youtube, "work"),
+            grit_dir.join("credentials/youtube/work.json")
+        );
+    }
+
+    #[test]
+    fn test_select_account_rejects_paths() {
+        assert!(select_account("../spotify").is_err());
+        assert!(select_account("").is_err());
+    }
 }
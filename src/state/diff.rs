@@ -1,9 +1,18 @@
 use anyhow::Result;
 
 use crate::provider::{DiffPatch, PlaylistSnapshot, Track, TrackChange};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub fn diff(old: &PlaylistSnapshot, new: &PlaylistSnapshot) -> DiffPatch {
+    // Placeholders only line up when both sides kept them; otherwise they
+    // would show up as added or removed and shift every position after them
+    if old.keeps_unavailable() != new.keeps_unavailable() {
+        let (mut old, mut new) = (old.clone(), new.clone());
+        old.drop_unavailable();
+        new.drop_unavailable();
+        return diff(&old, &new);
+    }
+
     let mut changes = Vec::new();
 
     //idx_map : track_id -> (index, &Track)
@@ -55,6 +64,92 @@ pub fn diff(old: &PlaylistSnapshot, new: &PlaylistSnapshot) -> DiffPatch {
     DiffPatch { changes }
 }
 
+/// Staged changes that turn `old` into the track order `order`, a list of
+/// distinct ids from `old`. Tracks missing from `order` are removed. Moves use
+/// post-removal indices and are meant to be applied in sequence, as
+/// `apply_patch` does; tracks already in relative order are left alone, so
+/// dragging one track to the end is a single move.
+pub fn reorder_patch(old: &PlaylistSnapshot, order: &[&str]) -> DiffPatch {
+    let mut changes = Vec::new();
+    let kept: HashSet<&str> = order.iter().copied().collect();
+
+    let mut current = Vec::new();
+    for (index, track) in old.tracks.iter().enumerate() {
+        if kept.contains(track.id.as_str()) {
+            current.push(track);
+        } else {
+            changes.push(TrackChange::Removed {
+                track: track.clone(),
+                index,
+            });
+        }
+    }
+
+    let target_index: HashMap<&str, usize> =
+        order.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    let positions: Vec<usize> = current
+        .iter()
+        .map(|t| target_index[t.id.as_str()])
+        .collect();
+    // The longest run of tracks already in the right relative order stays put
+    let stable: HashSet<&str> = longest_increasing(&positions)
+        .into_iter()
+        .map(|i| order[positions[i]])
+        .collect();
+
+    // Place every other track right after its predecessor in the new order,
+    // working front to back so each predecessor is already where it belongs
+    for (i, id) in order.iter().enumerate() {
+        if stable.contains(id) {
+            continue;
+        }
+        let from = current.iter().position(|t| t.id == *id).unwrap();
+        let track = current.remove(from);
+        let to = match i {
+            0 => 0,
+            _ => current.iter().position(|t| t.id == order[i - 1]).unwrap() + 1,
+        };
+        current.insert(to, track);
+        if from != to {
+            changes.push(TrackChange::Moved {
+                track: track.clone(),
+                from,
+                to,
+            });
+        }
+    }
+
+    DiffPatch { changes }
+}
+
+/// Indices of one longest strictly increasing subsequence of `values`
+fn longest_increasing(values: &[usize]) -> Vec<usize> {
+    // tails[k]: index of the smallest tail of an increasing run of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev = vec![None; values.len()];
+
+    for (i, value) in values.iter().enumerate() {
+        let k = tails.partition_point(|&t| values[t] < *value);
+        if k > 0 {
+            prev[i] = Some(tails[k - 1]);
+        }
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut run = Vec::with_capacity(tails.len());
+    let mut next = tails.last().copied();
+    while let Some(i) = next {
+        run.push(i);
+        next = prev[i];
+    }
+    run.reverse();
+    run
+}
+
 pub fn apply_patch(snapshot: &mut PlaylistSnapshot, patch: &DiffPatch) -> Result<()> {
     // Process changes in correct order:
     // 1. Removals (from highest index to lowest to avoid shifting issues)
@@ -74,7 +169,7 @@ pub fn apply_patch(snapshot: &mut PlaylistSnapshot, patch: &DiffPatch) -> Result
     }
 
     // Sort removals by index (highest first to avoid shifting)
-    removals.sort_by(|a, b| b.0.cmp(&a.0));
+    removals.sort_by_key(|r| std::cmp::Reverse(r.0));
 
     //remove
     for (_, change) in removals {
@@ -108,3 +203,115 @@ pub fn apply_patch(snapshot: &mut PlaylistSnapshot, patch: &DiffPatch) -> Result
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderKind;
+
+    fn snapshot(ids: &[&str]) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            id: "playlist".to_string(),
+            name: "Playlist".to_string(),
+            description: None,
+            tracks: ids.iter().copied().map(Track::test).collect(),
+            provider: ProviderKind::Spotify,
+            snapshot_hash: String::new(),
+            metadata: None,
+            schema_version: crate::provider::SCHEMA_VERSION,
+        }
+    }
+
+    fn ids(snapshot: &PlaylistSnapshot) -> Vec<&str> {
+        snapshot.tracks.iter().map(|t| t.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_limited_snapshots_record_depth_only_when_cut() {
+        let mut whole = snapshot(&["a", "b"]);
+        whole.limit_to(Some(2), false);
+        assert_eq!(whole.depth(), None);
+
+        let mut cut = snapshot(&["a", "b", "c"]);
+        cut.limit_to(Some(2), false);
+        assert_eq!(ids(&cut), ["a", "b"]);
+        assert_eq!(cut.depth(), Some(2));
+
+        // Exactly at the limit with pages left unfetched
+        let mut paged = snapshot(&["a", "b"]);
+        paged.limit_to(Some(2), true);
+        assert_eq!(paged.depth(), Some(2));
+
+        // The unfetched tail isn't a removal
+        assert!(diff(&cut, &paged).changes.is_empty());
+    }
+
+    #[test]
+    fn test_placeholders_only_diff_when_both_sides_keep_them() {
+        let mut kept = snapshot(&["a", "gone", "b"]);
+        kept.tracks[1].mark_unavailable();
+        kept.set_keeps_unavailable(true);
+        let dropped = snapshot(&["a", "b"]);
+
+        // Switching modes shows no changes and keeps the hash
+        assert!(diff(&kept, &dropped).changes.is_empty());
+        assert!(diff(&dropped, &kept).changes.is_empty());
+        assert_eq!(
+            crate::state::snapshot::compute_hash(&kept).unwrap(),
+            crate::state::snapshot::compute_hash(&dropped).unwrap()
+        );
+
+        // Between two kept snapshots, removing a placeholder is a change
+        let mut cleaned = snapshot(&["a", "b"]);
+        cleaned.set_keeps_unavailable(true);
+        let patch = diff(&kept, &cleaned);
+        assert_eq!(patch.changes.len(), 2);
+        assert!(patch
+            .changes
+            .iter()
+            .any(|c| matches!(c, TrackChange::Removed { track, index: 1 } if track.id == "gone")));
+    }
+
+    #[test]
+    fn test_fetched_snapshot_keeps_local_markers() {
+        let mut local = snapshot(&["a", "b"]);
+        local.tracks[1].add_marker(crate::provider::Marker {
+            secs: 90.0,
+            label: "drop".to_string(),
+        });
+
+        let mut remote = snapshot(&["b", "c"]);
+        remote.keep_local_metadata(&local);
+
+        assert_eq!(remote.tracks[0].markers(), local.tracks[1].markers());
+        assert!(remote.tracks[1].markers().is_empty());
+        assert_eq!(remote.tracks[1].metadata, None);
+    }
+
+    #[test]
+    fn test_reorder_patch_applies_to_new_order() {
+        let old = snapshot(&["a", "b", "c", "d", "e", "f"]);
+        for order in [
+            vec!["f", "e", "d", "c", "b", "a"],
+            vec!["c", "a", "f", "d"],
+            vec!["b", "a", "d", "c", "f", "e"],
+            vec!["a", "b", "c", "d", "e", "f"],
+        ] {
+            let patch = reorder_patch(&old, &order);
+            let mut new = old.clone();
+            apply_patch(&mut new, &patch).unwrap();
+            assert_eq!(ids(&new), order);
+        }
+    }
+
+    #[test]
+    fn test_reorder_patch_moves_only_displaced_tracks() {
+        let old = snapshot(&["a", "b", "c", "d"]);
+        let patch = reorder_patch(&old, &["b", "c", "d", "a"]);
+        assert_eq!(patch.changes.len(), 1);
+        assert!(matches!(
+            patch.changes[0],
+            TrackChange::Moved { from: 0, to: 3, .. }
+        ));
+    }
+}
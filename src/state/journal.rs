@@ -97,10 +97,7 @@ impl JournalEntry {
     }
 
     pub fn journal_path(grit_dir: &Path, playlist_id: &str) -> std::path::PathBuf {
-        grit_dir
-            .join("playlists")
-            .join(playlist_id)
-            .join("journal.log")
+        crate::state::snapshot::playlist_dir(grit_dir, playlist_id).join("journal.log")
     }
 }
 
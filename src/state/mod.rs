@@ -1,10 +1,12 @@
+pub mod config;
 pub mod credentials;
 pub mod diff;
 pub mod journal;
+pub mod remote_cache;
 pub mod snapshot;
 pub mod staging;
 pub mod working_playlist;
 
-pub use diff::{apply_patch, diff};
+pub use diff::{apply_patch, diff, reorder_patch};
 pub use journal::{JournalEntry, Operation};
 pub use staging::*;
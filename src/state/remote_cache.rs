@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::provider::PlaylistSnapshot;
+use crate::state::snapshot;
+
+/// Last fetched remote state, so back-to-back `status`/`diff`/`pull` calls
+/// don't hit the provider API each time.
+#[derive(Serialize, Deserialize)]
+struct CachedRemote {
+    fetched_at: u64, // unix timestamp
+    snapshot: PlaylistSnapshot,
+}
+
+pub fn cache_path(grit_dir: &Path, playlist_id: &str) -> PathBuf {
+    snapshot::playlist_dir(grit_dir, playlist_id).join("remote_cache.yaml")
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Cached remote snapshot if it is at most `max_age_secs` old
+pub fn load_fresh(
+    grit_dir: &Path,
+    playlist_id: &str,
+    max_age_secs: u64,
+) -> Option<PlaylistSnapshot> {
+    let content = fs::read_to_string(cache_path(grit_dir, playlist_id)).ok()?;
+    let cached: CachedRemote = serde_yaml::from_str(&content).ok()?;
+
+    if now().saturating_sub(cached.fetched_at) > max_age_secs {
+        return None;
+    }
+    Some(cached.snapshot)
+}
+
+pub fn save(grit_dir: &Path, playlist_id: &str, remote: &PlaylistSnapshot) -> Result<()> {
+    let path = cache_path(grit_dir, playlist_id);
+    let cached = CachedRemote {
+        fetched_at: now(),
+        snapshot: remote.clone(),
+    };
+    let yaml = serde_yaml::to_string(&cached).context("Failed to serialize remote cache")?;
+    fs::write(&path, yaml).with_context(|| format!("Failed to write remote cache {:?}", path))
+}
+
+/// Drop the cache after the remote or the local state it was compared with
+/// has changed
+pub fn invalidate(grit_dir: &Path, playlist_id: &str) {
+    let _ = fs::remove_file(cache_path(grit_dir, playlist_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{ProviderKind, SCHEMA_VERSION};
+    use tempfile::TempDir;
+
+    fn sample_remote() -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            id: "playlist123".to_string(),
+            name: "Remote".to_string(),
+            description: None,
+            tracks: vec![],
+            provider: ProviderKind::Spotify,
+            snapshot_hash: String::new(),
+            metadata: None,
+            schema_version: SCHEMA_VERSION,
+        }
+    }
+
+    #[test]
+    fn test_fresh_cache_is_reused() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(snapshot::playlist_dir(temp.path(), "playlist123")).unwrap();
+
+        save(temp.path(), "playlist123", &sample_remote()).unwrap();
+        let cached = load_fresh(temp.path(), "playlist123", 60).unwrap();
+        assert_eq!(cached.name, "Remote");
+
+        invalidate(temp.path(), "playlist123");
+        assert!(load_fresh(temp.path(), "playlist123", 60).is_none());
+    }
+
+    #[test]
+    fn test_stale_cache_is_ignored() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(snapshot::playlist_dir(temp.path(), "playlist123")).unwrap();
+
+        let stale = CachedRemote {
+            fetched_at: now() - 120,
+            snapshot: sample_remote(),
+        };
+        fs::write(
+            cache_path(temp.path(), "playlist123"),
+            serde_yaml::to_string(&stale).unwrap(),
+        )
+        .unwrap();
+
+        assert!(load_fresh(temp.path(), "playlist123", 60).is_none());
+        assert!(load_fresh(temp.path(), "playlist123", 300).is_some());
+    }
+}
@@ -1,12 +1,29 @@
 use std::{fs, path::Path};
 
-use anyhow::{Context, Ok};
+use anyhow::{bail, Context, Ok};
 use sha2::{Digest, Sha256};
 
-use crate::provider::PlaylistSnapshot;
+use crate::provider::{PlaylistSnapshot, SCHEMA_VERSION};
 
 pub fn compute_hash(snapshot: &PlaylistSnapshot) -> anyhow::Result<String> {
-    let yaml = serde_yaml::to_string(snapshot)
+    // Placeholders only stand in for positions, so fetching with or without
+    // them gives the same hash. Likewise for metadata only a fetch fills in,
+    // and for markers, which never leave this machine.
+    let mut playable = snapshot.clone();
+    playable.drop_unavailable();
+    for track in &mut playable.tracks {
+        track.strip_provider_metadata();
+        track.strip_local_metadata();
+    }
+
+    // The schema version describes the file, not the playlist, so it's left
+    // out to keep hashes stable across format upgrades
+    let mut value = serde_yaml::to_value(&playable)
+        .with_context(|| "Failed to serialize snapshot for hashing")?;
+    if let serde_yaml::Value::Mapping(map) = &mut value {
+        map.remove("schema_version");
+    }
+    let yaml = serde_yaml::to_string(&value)
         .with_context(|| "Failed to serialize snapshot for hashing")?;
 
     let mut hasher = Sha256::new();
@@ -37,22 +54,82 @@ pub fn load(path: &Path) -> anyhow::Result<PlaylistSnapshot> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read snapshot from {:?}", path))?;
 
-    serde_yaml::from_str(&content).with_context(|| "Failed to parse snapshot YAML")
+    let snapshot: PlaylistSnapshot =
+        serde_yaml::from_str(&content).with_context(|| "Failed to parse snapshot YAML")?;
+
+    migrate(snapshot).with_context(|| format!("Failed to load snapshot {:?}", path))
 }
 
-pub fn snapshot_path(grit_dir: &Path, playlist_id: &str) -> std::path::PathBuf {
+/// Upgrade a snapshot read from disk to the current `SCHEMA_VERSION`, one
+/// version at a time
+fn migrate(mut snapshot: PlaylistSnapshot) -> anyhow::Result<PlaylistSnapshot> {
+    if snapshot.schema_version > SCHEMA_VERSION {
+        bail!(
+            "Snapshot schema version {} is newer than this grit supports ({}). Upgrade grit.",
+            snapshot.schema_version,
+            SCHEMA_VERSION
+        );
+    }
+
+    if snapshot.schema_version == 0 {
+        // v1 only introduced the version field itself
+        snapshot.schema_version = 1;
+    }
+
+    Ok(snapshot)
+}
+
+/// Turn a playlist id into a safe directory name.
+///
+/// Everything except ASCII letters, digits, '-' and '_' is percent-encoded,
+/// so provider ids map to themselves while '/', '..', spaces and unicode
+/// can't escape or break the playlists directory.
+pub fn sanitize_playlist_id(playlist_id: &str) -> String {
+    let mut out = String::with_capacity(playlist_id.len());
+    for byte in playlist_id.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Recover the playlist id from a directory name made by `sanitize_playlist_id`.
+pub fn playlist_id_from_dir(dir_name: &str) -> String {
+    let bytes = dir_name.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Directory holding all state for a playlist
+pub fn playlist_dir(grit_dir: &Path, playlist_id: &str) -> std::path::PathBuf {
     grit_dir
         .join("playlists")
-        .join(playlist_id)
-        .join("playlist.yaml")
+        .join(sanitize_playlist_id(playlist_id))
+}
+
+pub fn snapshot_path(grit_dir: &Path, playlist_id: &str) -> std::path::PathBuf {
+    playlist_dir(grit_dir, playlist_id).join("playlist.yaml")
 }
 
 /// Get the snapshots directory path for a playlist
 pub fn snapshots_dir(grit_dir: &Path, playlist_id: &str) -> std::path::PathBuf {
-    grit_dir
-        .join("playlists")
-        .join(playlist_id)
-        .join("snapshots")
+    playlist_dir(grit_dir, playlist_id).join("snapshots")
 }
 
 /// Save a snapshot with its hash for historical reference
@@ -92,6 +169,65 @@ pub fn load_by_hash(
     anyhow::bail!("No snapshot found with hash '{}'", hash)
 }
 
+/// Ids of every tracked playlist, sorted
+pub fn tracked_playlist_ids(grit_dir: &Path) -> anyhow::Result<Vec<String>> {
+    let playlists_dir = grit_dir.join("playlists");
+    if !playlists_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut ids = Vec::new();
+    for entry in fs::read_dir(&playlists_dir)
+        .with_context(|| format!("Failed to read {:?}", playlists_dir))?
+        .flatten()
+    {
+        let path = entry.path();
+        if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
+            if path.is_dir() {
+                ids.push(playlist_id_from_dir(dir_name));
+            }
+        }
+    }
+
+    ids.sort();
+    Ok(ids)
+}
+
+/// Every snapshot stored for a playlist, referenced by the journal or not,
+/// as (hash, last modified), newest first
+pub fn stored_snapshots(
+    grit_dir: &Path,
+    playlist_id: &str,
+) -> anyhow::Result<Vec<(String, std::time::SystemTime)>> {
+    let snapshots_dir = snapshots_dir(grit_dir, playlist_id);
+    if !snapshots_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(&snapshots_dir)
+        .with_context(|| format!("Failed to read {:?}", snapshots_dir))?
+        .flatten()
+    {
+        let path = entry.path();
+        let Some(hash) = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(".yaml"))
+        else {
+            continue;
+        };
+        let modified = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .unwrap_or(std::time::UNIX_EPOCH);
+        snapshots.push((hash.to_string(), modified));
+    }
+
+    snapshots.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    Ok(snapshots)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,17 +239,11 @@ mod tests {
             id: "playlist123".to_string(),
             name: "Test Playlist".to_string(),
             description: Some("A test".to_string()),
-            tracks: vec![Track {
-                id: "track1".to_string(),
-                name: "Song One".to_string(),
-                artists: vec!["Artist A".to_string()],
-                duration_ms: 180000,
-                provider: ProviderKind::Spotify,
-                metadata: None,
-            }],
+            tracks: vec![Track::test("track1")],
             provider: ProviderKind::Spotify,
             snapshot_hash: String::new(),
             metadata: None,
+            schema_version: SCHEMA_VERSION,
         }
     }
 
@@ -126,6 +256,45 @@ mod tests {
         assert_eq!(hash1.len(), 12); // Short hash
     }
 
+    #[test]
+    fn test_provider_metadata_does_not_change_hash() {
+        // As pushed by grit, before the remote reports who added the track
+        let pushed = sample_snapshot();
+        let mut fetched = sample_snapshot();
+        fetched.tracks[0].metadata = Some(serde_json::json!({
+            "added_by": "friend",
+            "kind": "episode",
+            "explicit": true,
+        }));
+
+        // Equal hashes are what make pull report "Already up to date"
+        assert_eq!(
+            compute_hash(&pushed).unwrap(),
+            compute_hash(&fetched).unwrap()
+        );
+
+        fetched.tracks[0].name = "Renamed".to_string();
+        assert_ne!(
+            compute_hash(&pushed).unwrap(),
+            compute_hash(&fetched).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_markers_do_not_change_hash() {
+        let plain = sample_snapshot();
+        let mut marked = sample_snapshot();
+        marked.tracks[0].add_marker(crate::provider::Marker {
+            secs: 42.0,
+            label: "drop".to_string(),
+        });
+
+        assert_eq!(
+            compute_hash(&plain).unwrap(),
+            compute_hash(&marked).unwrap()
+        );
+    }
+
     #[test]
     fn test_save_and_load() {
         let temp = TempDir::new().unwrap();
@@ -139,4 +308,87 @@ mod tests {
         assert_eq!(loaded.name, snapshot.name);
         assert_eq!(loaded.tracks.len(), 1);
     }
+
+    #[test]
+    fn test_load_v0_snapshot_without_version() {
+        let v0 = "id: playlist123\nname: Old Playlist\ndescription: null\ntracks: []\nprovider: Spotify\nsnapshot_hash: abc\n";
+
+        let raw: PlaylistSnapshot = serde_yaml::from_str(v0).unwrap();
+        assert_eq!(raw.schema_version, 0);
+
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("playlist.yaml");
+        fs::write(&path, v0).unwrap();
+
+        let loaded = load(&path).unwrap();
+        assert_eq!(loaded.name, "Old Playlist");
+        assert_eq!(loaded.schema_version, SCHEMA_VERSION);
+        // Migrating doesn't change the content hash
+        assert_eq!(compute_hash(&raw).unwrap(), compute_hash(&loaded).unwrap());
+    }
+
+    #[test]
+    fn test_stored_snapshots_lists_every_hash() {
+        let temp = TempDir::new().unwrap();
+        assert!(stored_snapshots(temp.path(), "playlist123")
+            .unwrap()
+            .is_empty());
+
+        let snapshot = sample_snapshot();
+        save_by_hash(&snapshot, "aaaaaaaaaaaa", temp.path(), "playlist123").unwrap();
+        save_by_hash(&snapshot, "bbbbbbbbbbbb", temp.path(), "playlist123").unwrap();
+
+        let mut hashes: Vec<String> = stored_snapshots(temp.path(), "playlist123")
+            .unwrap()
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+        hashes.sort();
+        assert_eq!(hashes, vec!["aaaaaaaaaaaa", "bbbbbbbbbbbb"]);
+    }
+
+    #[test]
+    fn test_sanitize_keeps_provider_ids() {
+        assert_eq!(
+            sanitize_playlist_id("37i9dQZF1DXcBWIGoYBM5M"),
+            "37i9dQZF1DXcBWIGoYBM5M"
+        );
+        assert_eq!(sanitize_playlist_id("PLx-0_abcDEF"), "PLx-0_abcDEF");
+    }
+
+    #[test]
+    fn test_sanitize_blocks_traversal() {
+        let temp = TempDir::new().unwrap();
+        let dir = playlist_dir(temp.path(), "../../etc");
+
+        assert_eq!(sanitize_playlist_id("../../etc"), "%2E%2E%2F%2E%2E%2Fetc");
+        assert_eq!(dir.parent().unwrap(), temp.path().join("playlists"));
+    }
+
+    #[test]
+    fn test_sanitize_round_trip() {
+        for id in ["../secret", "my playlist", "música/ñ 日本", "100%", "a.b"] {
+            let dir_name = sanitize_playlist_id(id);
+            assert!(dir_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_%".contains(c)));
+            assert_eq!(playlist_id_from_dir(&dir_name), id);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_path_with_unsafe_id() {
+        let temp = TempDir::new().unwrap();
+        let mut snapshot = sample_snapshot();
+        snapshot.id = "local/my mix".to_string();
+
+        let path = snapshot_path(temp.path(), &snapshot.id);
+        save(&snapshot, &path).unwrap();
+
+        assert!(temp
+            .path()
+            .join("playlists/local%2Fmy%20mix/playlist.yaml")
+            .exists());
+        assert_eq!(load(&path).unwrap().id, "local/my mix");
+    }
 }
@@ -1,13 +1,14 @@
 use crate::provider::{DiffPatch, TrackChange};
-use anyhow::{Context, Ok, Result};
+use crate::state::snapshot;
+use anyhow::{bail, Context, Ok, Result};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+/// Load the staged patch. A file that doesn't parse is moved aside to
+/// `staged.json.corrupt.N` and treated as empty, so one bad write doesn't
+/// block every command on the playlist.
 pub fn load_staged(grit_dir: &Path, playlist_id: &str) -> Result<DiffPatch> {
-    let staged_path = grit_dir
-        .join("playlists")
-        .join(playlist_id)
-        .join("staged.json");
+    let staged_path = snapshot::playlist_dir(grit_dir, playlist_id).join("staged.json");
 
     if !staged_path.exists() {
         return Ok(DiffPatch { changes: vec![] });
@@ -15,21 +16,39 @@ pub fn load_staged(grit_dir: &Path, playlist_id: &str) -> Result<DiffPatch> {
 
     let contents = fs::read_to_string(&staged_path).context("Failed to read staged.json")?;
 
-    let patch: DiffPatch =
-        serde_json::from_str(&contents).context("Failed to parse staged.json")?;
+    match serde_json::from_str::<DiffPatch>(&contents) {
+        std::result::Result::Ok(patch) => Ok(patch),
+        Err(e) => {
+            let backup = corrupt_backup_path(&staged_path);
+            fs::rename(&staged_path, &backup)
+                .with_context(|| format!("Failed to move corrupt staged.json to {:?}", backup))?;
+            tracing::warn!(
+                "staged.json was unreadable ({}); moved it to {:?} and started with no staged changes",
+                e,
+                backup
+            );
+            Ok(DiffPatch { changes: vec![] })
+        }
+    }
+}
 
-    Ok(patch)
+/// First unused `staged.json.corrupt.N`, so earlier backups are kept
+fn corrupt_backup_path(staged_path: &Path) -> PathBuf {
+    (1..)
+        .map(|n| staged_path.with_extension(format!("json.corrupt.{}", n)))
+        .find(|path| !path.exists())
+        .expect("unbounded range")
 }
 
 pub fn save_staged(grit_dir: &Path, playlist_id: &str, patch: &DiffPatch) -> Result<()> {
-    let staged_path = grit_dir
-        .join("playlists")
-        .join(playlist_id)
-        .join("staged.json");
+    let staged_path = snapshot::playlist_dir(grit_dir, playlist_id).join("staged.json");
 
     let json = serde_json::to_string_pretty(patch).context("Failed to serialize staged changes")?;
 
-    fs::write(&staged_path, json).context("Failed to write staged.json")?;
+    // Write then rename, so an interrupted save leaves the old file intact
+    let tmp_path = staged_path.with_extension("json.tmp");
+    fs::write(&tmp_path, json).context("Failed to write staged.json")?;
+    fs::rename(&tmp_path, &staged_path).context("Failed to write staged.json")?;
 
     Ok(())
 }
@@ -40,12 +59,154 @@ pub fn clear_staged(grit_dir: &Path, playlist_id: &str) -> Result<()> {
 
 pub fn stage_change(grit_dir: &Path, playlist_id: &str, change: TrackChange) -> Result<()> {
     let mut patch = load_staged(grit_dir, playlist_id)?;
-    patch.changes.push(change);
+    merge_change(&mut patch, change)?;
     save_staged(grit_dir, playlist_id, &patch)
 }
 
+fn change_track_id(change: &TrackChange) -> &str {
+    match change {
+        TrackChange::Added { track, .. }
+        | TrackChange::Removed { track, .. }
+        | TrackChange::Moved { track, .. } => &track.id,
+    }
+}
+
+/// Add `change` to the staged patch, keeping at most one move or removal per
+/// track: a second move replaces the first, and a removal drops any staged
+/// move. Moving or removing a track that is already staged for removal is
+/// rejected, since the patch would contradict itself.
+fn merge_change(patch: &mut DiffPatch, change: TrackChange) -> Result<()> {
+    let track_id = change_track_id(&change).to_string();
+    let already_removed = patch
+        .changes
+        .iter()
+        .any(|c| matches!(c, TrackChange::Removed { track, .. } if track.id == track_id));
+
+    match &change {
+        TrackChange::Moved { track, .. } | TrackChange::Removed { track, .. }
+            if already_removed =>
+        {
+            bail!(
+                "'{}' is already staged for removal. Run 'grit reset' to start over.",
+                track.name
+            );
+        }
+        TrackChange::Moved { .. } | TrackChange::Removed { .. } => {
+            patch.changes.retain(|c| {
+                !(matches!(c, TrackChange::Moved { .. }) && change_track_id(c) == track_id)
+            });
+        }
+        TrackChange::Added { .. } => {}
+    }
+
+    patch.changes.push(change);
+    Ok(())
+}
+
 #[allow(dead_code)]
 pub fn has_staged_changes(grit_dir: &Path, playlist_id: &str) -> Result<bool> {
     let patch = load_staged(grit_dir, playlist_id)?;
     Ok(!patch.changes.is_empty())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::Track;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_corrupt_staged_file_is_set_aside() {
+        let dir = TempDir::new().unwrap();
+        let playlist_dir = snapshot::playlist_dir(dir.path(), "abc");
+        fs::create_dir_all(&playlist_dir).unwrap();
+        fs::write(playlist_dir.join("staged.json"), "{\"changes\": [").unwrap();
+
+        let patch = load_staged(dir.path(), "abc").unwrap();
+        assert!(patch.changes.is_empty());
+        assert!(!playlist_dir.join("staged.json").exists());
+        assert!(playlist_dir.join("staged.json.corrupt.1").exists());
+
+        // A second corruption doesn't overwrite the first backup
+        fs::write(playlist_dir.join("staged.json"), "nope").unwrap();
+        load_staged(dir.path(), "abc").unwrap();
+        assert_eq!(
+            fs::read_to_string(playlist_dir.join("staged.json.corrupt.1")).unwrap(),
+            "{\"changes\": ["
+        );
+        assert!(playlist_dir.join("staged.json.corrupt.2").exists());
+
+        // Staging works again straight away
+        stage_change(
+            dir.path(),
+            "abc",
+            TrackChange::Added {
+                track: Track::test("a"),
+                index: 0,
+            },
+        )
+        .unwrap();
+        assert_eq!(load_staged(dir.path(), "abc").unwrap().changes.len(), 1);
+    }
+
+    #[test]
+    fn test_double_move_keeps_latest_target() {
+        let mut patch = DiffPatch { changes: vec![] };
+        let moved = |to| TrackChange::Moved {
+            track: Track::test("a"),
+            from: 0,
+            to,
+        };
+        merge_change(&mut patch, moved(2)).unwrap();
+        merge_change(&mut patch, moved(4)).unwrap();
+
+        assert_eq!(patch.changes.len(), 1);
+        assert!(matches!(patch.changes[0], TrackChange::Moved { to: 4, .. }));
+    }
+
+    #[test]
+    fn test_move_and_remove_do_not_coexist() {
+        let mut patch = DiffPatch { changes: vec![] };
+        merge_change(
+            &mut patch,
+            TrackChange::Moved {
+                track: Track::test("a"),
+                from: 0,
+                to: 2,
+            },
+        )
+        .unwrap();
+        merge_change(
+            &mut patch,
+            TrackChange::Removed {
+                track: Track::test("a"),
+                index: 0,
+            },
+        )
+        .unwrap();
+
+        // The removal replaces the pending move
+        assert_eq!(patch.changes.len(), 1);
+        assert!(matches!(patch.changes[0], TrackChange::Removed { .. }));
+
+        // Moving or removing it again contradicts the removal
+        let err = merge_change(
+            &mut patch,
+            TrackChange::Moved {
+                track: Track::test("a"),
+                from: 0,
+                to: 1,
+            },
+        );
+        assert!(err.is_err());
+        assert!(merge_change(
+            &mut patch,
+            TrackChange::Removed {
+                track: Track::test("a"),
+                index: 0,
+            },
+        )
+        .is_err());
+        assert_eq!(patch.changes.len(), 1);
+    }
+}
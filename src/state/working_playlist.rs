@@ -46,6 +46,16 @@ pub fn save_state(grit_dir: &Path, state: &WorkingState) -> Result<()> {
         .with_context(|| format!("Failed to write working state to {:?}", path))
 }
 
+/// Forget the working playlist, e.g. after it was dropped
+pub fn clear(grit_dir: &Path) -> Result<()> {
+    let path = config_path(grit_dir);
+    if path.exists() {
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove working state {:?}", path))?;
+    }
+    Ok(())
+}
+
 pub fn save_last_track(grit_dir: &Path, track_index: usize) -> Result<()> {
     let mut state = load_state(grit_dir).unwrap_or_default();
     state.last_track_index = Some(track_index);
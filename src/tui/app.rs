@@ -1,12 +1,51 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
 use crate::playback::events::RepeatMode;
-use crate::playback::Lyrics;
-use crate::provider::Track;
+use crate::playback::lyrics::clean_yt_title;
+use crate::playback::{Lyrics, MediaCommand, PlayingTrack};
+use crate::provider::{Marker, Track};
 
 pub enum PlayerBackend {
     Mpv,
     Spotify,
 }
 
+/// What a key press asks of the player. `App::handle_key` makes every change
+/// that only touches the app and leaves these for the backend loop to carry
+/// out against Spotify or mpv.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Quit,
+    /// Pause (`true`) or resume; `is_paused` is already updated
+    Pause(bool),
+    Next,
+    Previous,
+    /// Start the track at this playlist position
+    Play(usize),
+    /// Seek to a position in seconds
+    Seek(f64),
+    /// Seek by this many seconds from the current position
+    SeekBy(f64),
+    /// Shuffle was switched on or off; `shuffle` is already updated
+    Shuffle(bool),
+    /// Switch between the provider's shuffle and grit's
+    SwitchShuffleMode,
+    /// Repeat mode changed; `repeat_mode` is already updated
+    Repeat(RepeatMode),
+    /// Explicit skipping was switched; `skip_explicit` is already updated
+    SkipExplicit(bool),
+    /// Stop loading the current track
+    CancelLoad,
+    /// Open the copy prompt, offering the tracked playlists
+    ChooseCopyTarget,
+    /// Stage the current track into this playlist
+    StageCopy(String),
+    /// Save a new marker on the current track
+    SaveMarker(Marker),
+    /// Look up lyrics for the current track as (artist, title)
+    FetchLyrics(String, String),
+}
+
 pub struct App {
     pub playlist_name: String,
     pub tracks: Vec<Track>,
@@ -29,7 +68,33 @@ pub struct App {
     pub lyrics_loading: bool,
     pub lyrics_scroll: usize,
     pub lyrics_auto_scroll: bool,
+    /// "artist - title" being edited for a manual lyrics lookup
+    pub lyrics_query: Option<String>,
     pub search_blocked: bool,
+    /// Moving past either end of the playlist wraps to the other end
+    pub wrap_navigation: bool,
+    /// Spotify's own up-next as (name, artists). `None` when the queue
+    /// couldn't be read and "next up" falls back to playlist order.
+    pub remote_queue: Option<Vec<(String, String)>>,
+    /// Volume set from grit, 0-100; `None` leaves the player's own level
+    pub volume: Option<u8>,
+    /// Destination playlist being typed to stage the current track into
+    pub copy_target: Option<String>,
+    /// Tracked playlists offered for completion, as (id, name)
+    pub copy_candidates: Vec<(String, String)>,
+    /// Short confirmation shown in the controls bar until the next key
+    pub notice: Option<String>,
+    /// The seek position is following a mouse drag on the progress bar
+    pub scrubbing: bool,
+    /// Draw status symbols in plain ASCII
+    pub ascii_symbols: bool,
+    /// Skip tracks flagged explicit
+    pub skip_explicit: bool,
+    /// Position and label being typed for a new marker
+    pub marker_draft: Option<(f64, String)>,
+    /// The current track's markers replace the playlist pane
+    pub show_markers: bool,
+    pub marker_selected: usize,
 }
 
 impl App {
@@ -60,10 +125,283 @@ impl App {
             lyrics_loading: false,
             lyrics_scroll: 0,
             lyrics_auto_scroll: true,
+            lyrics_query: None,
             search_blocked: false,
+            wrap_navigation: false,
+            remote_queue: None,
+            volume: None,
+            copy_target: None,
+            copy_candidates: Vec::new(),
+            notice: None,
+            scrubbing: false,
+            ascii_symbols: false,
+            skip_explicit: false,
+            marker_draft: None,
+            show_markers: false,
+            marker_selected: 0,
         }
     }
 
+    /// Apply a key press. Prompts and list navigation are handled here;
+    /// anything that needs the player comes back as an `Action`.
+    /// `page_size` is how many rows PageUp/PageDown move.
+    pub fn handle_key(&mut self, key: KeyEvent, page_size: usize) -> Option<Action> {
+        if self.is_searching() {
+            return self.search_key(key);
+        }
+
+        if self.is_editing_lyrics_query() {
+            match key.code {
+                KeyCode::Esc => self.cancel_lyrics_query(),
+                KeyCode::Enter => {
+                    let query = self.submit_lyrics_query();
+                    if let (Some((artist, title)), Some(_)) = (query, self.current_track()) {
+                        self.lyrics = None;
+                        self.lyrics_loading = true;
+                        self.reset_lyrics_scroll();
+                        return Some(Action::FetchLyrics(artist, title));
+                    }
+                }
+                KeyCode::Backspace => self.pop_lyrics_query_char(),
+                KeyCode::Char(c) => self.push_lyrics_query_char(c),
+                _ => {}
+            }
+            return None;
+        }
+
+        if self.is_choosing_copy_target() {
+            match key.code {
+                KeyCode::Esc => self.cancel_copy_target(),
+                KeyCode::Tab => self.complete_copy_target(),
+                KeyCode::Enter => {
+                    return self
+                        .submit_copy_target()
+                        .filter(|_| self.current_track().is_some())
+                        .map(Action::StageCopy);
+                }
+                KeyCode::Backspace => self.pop_copy_target_char(),
+                KeyCode::Char(c) => self.push_copy_target_char(c),
+                _ => {}
+            }
+            return None;
+        }
+
+        if self.is_naming_marker() {
+            match key.code {
+                KeyCode::Esc => self.cancel_marker(),
+                KeyCode::Enter => return self.submit_marker().map(Action::SaveMarker),
+                KeyCode::Backspace => self.pop_marker_char(),
+                KeyCode::Char(c) => self.push_marker_char(c),
+                _ => {}
+            }
+            return None;
+        }
+
+        // Keys the marker list doesn't use fall through to the player
+        if self.show_markers {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('m') => {
+                    self.toggle_markers();
+                    return None;
+                }
+                KeyCode::Up => {
+                    self.select_prev_marker();
+                    return None;
+                }
+                KeyCode::Down => {
+                    self.select_next_marker();
+                    return None;
+                }
+                KeyCode::Enter => {
+                    return self
+                        .selected_marker()
+                        .map(|marker| Action::Seek(marker.secs));
+                }
+                _ => {}
+            }
+        }
+
+        if self.is_seeking() {
+            match key.code {
+                KeyCode::Esc => self.cancel_seeking(),
+                KeyCode::Enter => {
+                    let target = self.get_seek_position();
+                    self.cancel_seeking();
+                    return target.map(Action::Seek);
+                }
+                KeyCode::Left => self.seek_backward(5.0),
+                KeyCode::Right => self.seek_forward(5.0),
+                _ => {}
+            }
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char('/') if self.show_lyrics => {
+                self.search_blocked = true;
+            }
+            _ => {
+                self.search_blocked = false;
+                self.clear_error();
+                self.notice = None;
+            }
+        }
+
+        match key.code {
+            KeyCode::Char('q') => return Some(Action::Quit),
+            KeyCode::Char('b') => self.start_marker(),
+            KeyCode::Char('m') => self.toggle_markers(),
+            KeyCode::Char('+') => return Some(Action::ChooseCopyTarget),
+            KeyCode::Esc if self.loading => {
+                self.loading = false;
+                return Some(Action::CancelLoad);
+            }
+            KeyCode::Char('/') if !self.show_lyrics => self.start_search(),
+            KeyCode::Char('g') => self.start_seeking(),
+            KeyCode::Char(' ') => {
+                self.is_paused = !self.is_paused;
+                return Some(Action::Pause(self.is_paused));
+            }
+            KeyCode::Char('n') => return Some(Action::Next),
+            KeyCode::Char('p') => return Some(Action::Previous),
+            KeyCode::Char('s') => {
+                self.shuffle = !self.shuffle;
+                return Some(Action::Shuffle(self.shuffle));
+            }
+            KeyCode::Char('S') => return Some(Action::SwitchShuffleMode),
+            KeyCode::Char('r') => {
+                self.cycle_repeat();
+                return Some(Action::Repeat(self.repeat_mode));
+            }
+            KeyCode::Char('x') => {
+                self.toggle_skip_explicit();
+                return Some(Action::SkipExplicit(self.skip_explicit));
+            }
+            KeyCode::Char('l') => self.toggle_lyrics(),
+            KeyCode::Char('a') if self.show_lyrics => self.lyrics_toggle_auto_scroll(),
+            KeyCode::Char('e') if self.show_lyrics => self.start_lyrics_query(),
+            KeyCode::Left => return Some(Action::SeekBy(-5.0)),
+            KeyCode::Right => return Some(Action::SeekBy(5.0)),
+            KeyCode::Up if self.show_lyrics => self.lyrics_scroll_up(),
+            KeyCode::Up => self.select_prev(),
+            KeyCode::Down if self.show_lyrics => {
+                let max_lines = self.lyrics_line_count();
+                self.lyrics_scroll_down(max_lines);
+            }
+            KeyCode::Down => self.select_next(),
+            KeyCode::Home if !self.show_lyrics => self.select_first(),
+            KeyCode::End if !self.show_lyrics => self.select_last(),
+            KeyCode::Char('c') if !self.show_lyrics => self.select_current(),
+            KeyCode::PageUp if !self.show_lyrics => self.select_page(-(page_size as isize)),
+            KeyCode::PageDown if !self.show_lyrics => self.select_page(page_size as isize),
+            KeyCode::Enter => return self.play_selected(),
+            _ => {}
+        }
+        None
+    }
+
+    fn search_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) => self.cancel_search(),
+            (KeyCode::Enter, _) => {
+                self.cancel_search();
+                return self.play_selected();
+            }
+            (KeyCode::Char('n'), m) if m.contains(KeyModifiers::CONTROL) => {
+                self.next_search_match()
+            }
+            (KeyCode::Char('p'), m) if m.contains(KeyModifiers::CONTROL) => {
+                self.prev_search_match()
+            }
+            (KeyCode::Up, _) => self.select_prev(),
+            (KeyCode::Down, _) => self.select_next(),
+            (KeyCode::Backspace, _) => self.pop_search_char(),
+            (KeyCode::Char(c), _) => self.push_search_char(c),
+            _ => {}
+        }
+        None
+    }
+
+    /// What a desktop media key or widget asks for. Kept apart from key
+    /// presses, so it acts the same whether or not a prompt has focus.
+    pub fn media_action(&mut self, command: MediaCommand) -> Option<Action> {
+        let pause = match command {
+            MediaCommand::PlayPause => !self.is_paused,
+            MediaCommand::Play => false,
+            MediaCommand::Pause | MediaCommand::Stop => true,
+            MediaCommand::Next => return Some(Action::Next),
+            MediaCommand::Previous => return Some(Action::Previous),
+            MediaCommand::SeekBy(offset) => return Some(Action::SeekBy(offset)),
+            // Positions outside the track are ignored, as MPRIS specifies
+            MediaCommand::SeekTo(secs) => {
+                return (0.0..=self.duration_secs)
+                    .contains(&secs)
+                    .then_some(Action::Seek(secs));
+            }
+        };
+        if pause == self.is_paused {
+            return None;
+        }
+        self.is_paused = pause;
+        Some(Action::Pause(pause))
+    }
+
+    /// Play the selected track unless it's already the current one
+    fn play_selected(&self) -> Option<Action> {
+        let idx = self.selected_index;
+        (idx != self.current_index && idx < self.tracks.len()).then_some(Action::Play(idx))
+    }
+
+    /// Advance the position by `elapsed` seconds of wall time, for backends
+    /// that aren't asked where they are on every frame
+    pub fn tick(&mut self, elapsed: f64) {
+        if !self.is_paused && !self.loading {
+            self.position_secs = (self.position_secs + elapsed).min(self.duration_secs);
+        }
+    }
+
+    /// Move to the track at `index` from its start, dropping the previous
+    /// track's lyrics
+    pub fn start_track(&mut self, index: usize) {
+        self.current_index = index;
+        self.position_secs = 0.0;
+        self.duration_secs = self
+            .tracks
+            .get(index)
+            .map_or(0.0, |t| t.duration_ms as f64 / 1000.0);
+        self.lyrics = None;
+        self.lyrics_loading = false;
+        self.reset_lyrics_scroll();
+    }
+
+    /// The track to look up lyrics for, when the lyrics panel is open and
+    /// nothing is showing or on its way yet
+    pub fn lyrics_wanted(&self) -> Option<&Track> {
+        if self.show_lyrics && self.lyrics.is_none() && !self.lyrics_loading {
+            self.current_track()
+        } else {
+            None
+        }
+    }
+
+    /// Whether the current track should be skipped for being explicit.
+    /// Never true when every track is explicit, so playback can't spin.
+    pub fn explicit_to_skip(&self) -> bool {
+        self.skip_explicit
+            && self.current_track().is_some_and(Track::is_explicit)
+            && self.tracks.iter().any(|t| !t.is_explicit())
+    }
+
+    pub fn toggle_skip_explicit(&mut self) {
+        self.skip_explicit = !self.skip_explicit;
+        let notice = if self.skip_explicit {
+            "Skipping explicit tracks"
+        } else {
+            "Playing explicit tracks"
+        };
+        self.notice = Some(notice.to_string());
+    }
+
     pub fn toggle_lyrics(&mut self) {
         self.show_lyrics = !self.show_lyrics;
     }
@@ -102,10 +440,218 @@ impl App {
         self.lyrics_auto_scroll = true;
     }
 
+    /// Open the lyrics query prompt, prefilled with what the automatic
+    /// lookup used.
+    pub fn start_lyrics_query(&mut self) {
+        let query = self
+            .current_track()
+            .map(|track| match self.backend {
+                PlayerBackend::Mpv => match clean_yt_title(&track.name) {
+                    (title, Some(artist)) => format!("{} - {}", artist, title),
+                    (title, None) => title,
+                },
+                PlayerBackend::Spotify => format!(
+                    "{} - {}",
+                    track.artists.first().map(|s| s.as_str()).unwrap_or(""),
+                    track.name
+                ),
+            })
+            .unwrap_or_default();
+        self.lyrics_query = Some(query);
+    }
+
+    pub fn cancel_lyrics_query(&mut self) {
+        self.lyrics_query = None;
+    }
+
+    pub fn is_editing_lyrics_query(&self) -> bool {
+        self.lyrics_query.is_some()
+    }
+
+    pub fn push_lyrics_query_char(&mut self, c: char) {
+        if let Some(ref mut query) = self.lyrics_query {
+            query.push(c);
+        }
+    }
+
+    pub fn pop_lyrics_query_char(&mut self) {
+        if let Some(ref mut query) = self.lyrics_query {
+            query.pop();
+        }
+    }
+
+    /// Close the prompt and split the query into (artist, title).
+    pub fn submit_lyrics_query(&mut self) -> Option<(String, String)> {
+        let query = self.lyrics_query.take()?;
+        let query = query.trim();
+        if query.is_empty() {
+            return None;
+        }
+        match query.split_once(" - ") {
+            Some((artist, title)) => Some((artist.trim().to_string(), title.trim().to_string())),
+            None => Some((String::new(), query.to_string())),
+        }
+    }
+
+    /// Open the label prompt for a marker at the current position
+    pub fn start_marker(&mut self) {
+        if self.current_track().is_some() {
+            self.marker_draft = Some((self.position_secs, String::new()));
+        }
+    }
+
+    pub fn cancel_marker(&mut self) {
+        self.marker_draft = None;
+    }
+
+    pub fn is_naming_marker(&self) -> bool {
+        self.marker_draft.is_some()
+    }
+
+    pub fn push_marker_char(&mut self, c: char) {
+        if let Some((_, ref mut label)) = self.marker_draft {
+            label.push(c);
+        }
+    }
+
+    pub fn pop_marker_char(&mut self) {
+        if let Some((_, ref mut label)) = self.marker_draft {
+            label.pop();
+        }
+    }
+
+    /// Close the prompt and add the marker to the current track. An empty
+    /// label falls back to the timestamp.
+    pub fn submit_marker(&mut self) -> Option<Marker> {
+        let (secs, label) = self.marker_draft.take()?;
+        let label = match label.trim() {
+            "" => Self::format_time(secs),
+            label => label.to_string(),
+        };
+        let marker = Marker { secs, label };
+        self.tracks
+            .get_mut(self.current_index)?
+            .add_marker(marker.clone());
+        Some(marker)
+    }
+
+    pub fn current_markers(&self) -> Vec<Marker> {
+        self.current_track().map(Track::markers).unwrap_or_default()
+    }
+
+    pub fn toggle_markers(&mut self) {
+        self.show_markers = !self.show_markers;
+        self.marker_selected = 0;
+    }
+
+    pub fn select_next_marker(&mut self) {
+        let last = self.current_markers().len().saturating_sub(1);
+        self.marker_selected = (self.marker_selected + 1).min(last);
+    }
+
+    pub fn select_prev_marker(&mut self) {
+        self.marker_selected = self.marker_selected.saturating_sub(1);
+    }
+
+    pub fn selected_marker(&self) -> Option<Marker> {
+        self.current_markers().get(self.marker_selected).cloned()
+    }
+
+    /// Open the destination prompt for staging the current track elsewhere
+    pub fn start_copy_target(&mut self, candidates: Vec<(String, String)>) {
+        self.copy_candidates = candidates;
+        self.copy_target = Some(String::new());
+    }
+
+    pub fn cancel_copy_target(&mut self) {
+        self.copy_target = None;
+    }
+
+    pub fn is_choosing_copy_target(&self) -> bool {
+        self.copy_target.is_some()
+    }
+
+    pub fn push_copy_target_char(&mut self, c: char) {
+        if let Some(ref mut target) = self.copy_target {
+            target.push(c);
+        }
+    }
+
+    pub fn pop_copy_target_char(&mut self) {
+        if let Some(ref mut target) = self.copy_target {
+            target.pop();
+        }
+    }
+
+    /// First candidate whose id or name starts with what's been typed
+    pub fn copy_target_match(&self) -> Option<&(String, String)> {
+        let typed = self.copy_target.as_deref()?.trim().to_lowercase();
+        self.copy_candidates.iter().find(|(id, name)| {
+            id.to_lowercase().starts_with(&typed) || name.to_lowercase().starts_with(&typed)
+        })
+    }
+
+    /// Replace the typed text with the matching candidate's id
+    pub fn complete_copy_target(&mut self) {
+        if let Some((id, _)) = self.copy_target_match().cloned() {
+            self.copy_target = Some(id);
+        }
+    }
+
+    /// Close the prompt and return the chosen playlist id. Text that isn't
+    /// an exact id resolves to the completion match, if any.
+    pub fn submit_copy_target(&mut self) -> Option<String> {
+        let matched = self.copy_target_match().map(|(id, _)| id.clone());
+        let typed = self.copy_target.take()?;
+        let typed = typed.trim();
+        if typed.is_empty() {
+            return None;
+        }
+        if self.copy_candidates.iter().any(|(id, _)| id == typed) {
+            return Some(typed.to_string());
+        }
+        Some(matched.unwrap_or_else(|| typed.to_string()))
+    }
+
     pub fn current_lyric_index(&self) -> Option<usize> {
         self.lyrics.as_ref()?.current_line_index(self.position_secs)
     }
 
+    /// Swap in a reloaded track list. Stays on the current track if it's
+    /// still there and keeps every index in range if the list shrank.
+    pub fn replace_tracks(&mut self, tracks: Vec<Track>) {
+        let current = self.current_track().cloned();
+        self.tracks = tracks;
+
+        let last = self.tracks.len().saturating_sub(1);
+        self.current_index = current
+            .and_then(|track| self.position_of(&track))
+            .unwrap_or(self.current_index.min(last));
+        self.selected_index = self.selected_index.min(last);
+
+        let len = self.tracks.len();
+        self.search_matches.retain(|&i| i < len);
+        self.search_match_index = self
+            .search_match_index
+            .min(self.search_matches.len().saturating_sub(1));
+    }
+
+    /// Index of `track` in the playlist, matched by identity
+    pub fn position_of(&self, track: &Track) -> Option<usize> {
+        self.tracks.iter().position(|t| t.same_as(track))
+    }
+
+    /// Index of what Spotify reports as playing: by id, or by name for
+    /// local files that have none. Prefers the current index so a track
+    /// listed twice doesn't make the highlight jump.
+    pub fn position_of_playing(&self, playing: &PlayingTrack) -> Option<usize> {
+        let matches = |t: &Track| playing.is(t);
+        if self.current_track().is_some_and(matches) {
+            return Some(self.current_index);
+        }
+        self.tracks.iter().position(matches)
+    }
+
     pub fn current_track(&self) -> Option<&Track> {
         self.tracks.get(self.current_index)
     }
@@ -122,6 +668,29 @@ impl App {
         }
     }
 
+    /// Up to `n` tracks after the current one in playlist order, wrapping
+    /// with repeat-all. Empty while shuffled, since the order isn't known.
+    pub fn upcoming_tracks(&self, n: usize) -> Vec<&Track> {
+        if self.shuffle || self.repeat_mode == RepeatMode::One {
+            return Vec::new();
+        }
+        let len = self.tracks.len();
+        let steps = if self.repeat_mode == RepeatMode::All {
+            len.saturating_sub(1)
+        } else {
+            len.saturating_sub(self.current_index + 1)
+        };
+        (1..=steps.min(n))
+            .filter_map(|step| self.tracks.get((self.current_index + step) % len))
+            .collect()
+    }
+
+    /// Whether the current track is the playlist's last. Always false for
+    /// an empty playlist.
+    pub fn is_last_track(&self) -> bool {
+        self.current_index + 1 == self.tracks.len()
+    }
+
     pub fn progress(&self) -> f64 {
         if self.duration_secs > 0.0 {
             (self.position_secs / self.duration_secs).min(1.0)
@@ -130,10 +699,27 @@ impl App {
         }
     }
 
+    /// Time left in the playlist: the rest of the current track plus every
+    /// track after it
+    pub fn remaining_secs(&self) -> f64 {
+        let rest_of_current = (self.duration_secs - self.position_secs).max(0.0);
+        let after: u64 = self
+            .tracks
+            .iter()
+            .skip(self.current_index + 1)
+            .map(|t| t.duration_ms)
+            .sum();
+        rest_of_current + after as f64 / 1000.0
+    }
+
     pub fn format_time(secs: f64) -> String {
-        let mins = (secs / 60.0) as u64;
-        let secs = (secs % 60.0) as u64;
-        format!("{}:{:02}", mins, secs)
+        let total = secs.max(0.0) as u64;
+        let (hours, mins, secs) = (total / 3600, total / 60 % 60, total % 60);
+        if hours > 0 {
+            format!("{}:{:02}:{:02}", hours, mins, secs)
+        } else {
+            format!("{}:{:02}", mins, secs)
+        }
     }
 
     pub fn set_error(&mut self, msg: String) {
@@ -147,11 +733,37 @@ impl App {
     pub fn select_next(&mut self) {
         if self.selected_index < self.tracks.len().saturating_sub(1) {
             self.selected_index += 1;
+        } else if self.wrap_navigation {
+            self.select_first();
         }
     }
 
     pub fn select_prev(&mut self) {
-        self.selected_index = self.selected_index.saturating_sub(1);
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        } else if self.wrap_navigation {
+            self.select_last();
+        }
+    }
+
+    pub fn select_first(&mut self) {
+        self.selected_index = 0;
+    }
+
+    pub fn select_last(&mut self) {
+        self.selected_index = self.tracks.len().saturating_sub(1);
+    }
+
+    /// Move the selection back to the playing track, which scrolls the list
+    /// to it
+    pub fn select_current(&mut self) {
+        self.selected_index = self.current_index;
+    }
+
+    /// Move the selection by `delta` tracks, stopping at either end
+    pub fn select_page(&mut self, delta: isize) {
+        let last = self.tracks.len().saturating_sub(1);
+        self.selected_index = self.selected_index.saturating_add_signed(delta).min(last);
     }
 
     #[allow(dead_code)]
@@ -173,6 +785,21 @@ impl App {
 
     pub fn cancel_seeking(&mut self) {
         self.seek_position = None;
+        self.scrubbing = false;
+    }
+
+    /// Preview a progress bar drag at `ratio` of the track
+    pub fn scrub_to(&mut self, ratio: f64) {
+        self.scrubbing = true;
+        self.seek_position = Some(ratio.clamp(0.0, 1.0) * self.duration_secs);
+    }
+
+    /// End a drag at `ratio`, returning the position to seek to
+    pub fn finish_scrub(&mut self, ratio: f64) -> f64 {
+        let secs = ratio.clamp(0.0, 1.0) * self.duration_secs;
+        self.cancel_seeking();
+        self.position_secs = secs;
+        secs
     }
 
     pub fn seek_forward(&mut self, secs: f64) {
@@ -279,3 +906,441 @@ impl App {
         self.search_matches.contains(&index)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn explicit(id: &str) -> Track {
+        Track {
+            metadata: Some(serde_json::json!({ "explicit": true })),
+            ..Track::test(id)
+        }
+    }
+
+    #[test]
+    fn test_upcoming_tracks() {
+        let tracks = vec![Track::test("a"), Track::test("b"), Track::test("c")];
+        let mut app = App::new("Test".to_string(), tracks, PlayerBackend::Spotify);
+        app.current_index = 1;
+
+        let ids = |app: &App| -> Vec<String> {
+            app.upcoming_tracks(2)
+                .iter()
+                .map(|t| t.id.clone())
+                .collect()
+        };
+        assert_eq!(ids(&app), vec!["c"]);
+
+        app.repeat_mode = RepeatMode::All;
+        assert_eq!(ids(&app), vec!["c", "a"]);
+
+        app.shuffle = true;
+        assert!(ids(&app).is_empty());
+    }
+
+    #[test]
+    fn test_explicit_to_skip() {
+        let tracks = vec![explicit("a"), Track::test("b")];
+        let mut app = App::new("Test".to_string(), tracks, PlayerBackend::Spotify);
+        assert!(!app.explicit_to_skip());
+
+        app.toggle_skip_explicit();
+        assert!(app.explicit_to_skip());
+        app.current_index = 1;
+        assert!(!app.explicit_to_skip());
+
+        // Nothing clean to skip to
+        app.replace_tracks(vec![explicit("a")]);
+        app.current_index = 0;
+        assert!(!app.explicit_to_skip());
+    }
+
+    #[test]
+    fn test_replace_tracks_follows_current_track() {
+        let tracks = vec![Track::test("a"), Track::test("b"), Track::test("c")];
+        let mut app = App::new("Test".to_string(), tracks, PlayerBackend::Mpv);
+        app.current_index = 2;
+        app.selected_index = 2;
+
+        app.replace_tracks(vec![Track::test("c"), Track::test("a")]);
+        assert_eq!(app.current_index, 0);
+        assert_eq!(app.selected_index, 1);
+
+        app.replace_tracks(vec![Track::test("x")]);
+        assert_eq!(app.current_index, 0);
+
+        app.replace_tracks(Vec::new());
+        assert_eq!(app.current_index, 0);
+        assert!(app.current_track().is_none());
+    }
+
+    #[test]
+    fn test_selection_jumps_stop_at_the_ends() {
+        let tracks = vec![
+            Track::test("a"),
+            Track::test("b"),
+            Track::test("c"),
+            Track::test("d"),
+        ];
+        let mut app = App::new("Test".to_string(), tracks, PlayerBackend::Mpv);
+
+        app.select_page(2);
+        assert_eq!(app.selected_index, 2);
+        app.select_page(10);
+        assert_eq!(app.selected_index, 3);
+        app.select_page(-1);
+        assert_eq!(app.selected_index, 2);
+        app.select_page(-10);
+        assert_eq!(app.selected_index, 0);
+
+        app.select_last();
+        assert_eq!(app.selected_index, 3);
+        app.select_first();
+        assert_eq!(app.selected_index, 0);
+
+        // An empty playlist keeps the selection at 0
+        app.replace_tracks(Vec::new());
+        app.select_last();
+        assert_eq!(app.selected_index, 0);
+        app.select_page(5);
+        assert_eq!(app.selected_index, 0);
+    }
+
+    #[test]
+    fn test_markers_are_kept_in_time_order() {
+        let mut app = App::new(
+            "test".to_string(),
+            vec![Track::test("a")],
+            PlayerBackend::Mpv,
+        );
+
+        app.position_secs = 1500.0;
+        app.start_marker();
+        for c in "drop".chars() {
+            app.push_marker_char(c);
+        }
+        app.submit_marker();
+
+        // No label: named after the timestamp
+        app.position_secs = 65.0;
+        app.start_marker();
+        app.submit_marker();
+
+        let markers = app.current_markers();
+        assert_eq!(markers.len(), 2);
+        assert_eq!(markers[0].label, "1:05");
+        assert_eq!(markers[1].label, "drop");
+        assert_eq!(markers[1].secs, 1500.0);
+    }
+
+    #[test]
+    fn test_copy_target_completes_by_id_or_name() {
+        let mut app = App::new(
+            "Test".to_string(),
+            vec![Track::test("a")],
+            PlayerBackend::Mpv,
+        );
+        let candidates = vec![
+            ("PL123".to_string(), "Road Trip".to_string()),
+            ("PL456".to_string(), "Focus".to_string()),
+        ];
+
+        app.start_copy_target(candidates.clone());
+        for c in "foc".chars() {
+            app.push_copy_target_char(c);
+        }
+        app.complete_copy_target();
+        assert_eq!(app.copy_target.as_deref(), Some("PL456"));
+        assert_eq!(app.submit_copy_target().as_deref(), Some("PL456"));
+        assert!(!app.is_choosing_copy_target());
+
+        app.start_copy_target(candidates);
+        for c in "road".chars() {
+            app.push_copy_target_char(c);
+        }
+        assert_eq!(app.submit_copy_target().as_deref(), Some("PL123"));
+    }
+
+    #[test]
+    fn test_playing_track_matches_by_id_over_name() {
+        let mut same_name = Track::test("b");
+        same_name.name = "a".to_string();
+        let mut app = App::new(
+            "Test".to_string(),
+            vec![Track::test("a"), same_name],
+            PlayerBackend::Spotify,
+        );
+        let playing = |id: Option<&str>| PlayingTrack {
+            id: id.map(str::to_string),
+            linked_from: None,
+            name: "a".to_string(),
+        };
+
+        assert_eq!(app.position_of_playing(&playing(Some("b"))), Some(1));
+        assert_eq!(app.position_of_playing(&playing(None)), Some(0));
+
+        app.current_index = 1;
+        assert_eq!(app.position_of_playing(&playing(None)), Some(1));
+        assert_eq!(app.position_of_playing(&playing(Some("x"))), None);
+
+        // A regional copy of "b" matches the id the playlist holds
+        let relinked = PlayingTrack {
+            linked_from: Some("b".to_string()),
+            ..playing(Some("b-regional"))
+        };
+        app.current_index = 0;
+        assert_eq!(app.position_of_playing(&relinked), Some(1));
+    }
+
+    #[test]
+    fn test_empty_app_has_no_tracks_to_index() {
+        let mut app = App::new("Empty".to_string(), Vec::new(), PlayerBackend::Mpv);
+        app.repeat_mode = RepeatMode::All;
+
+        assert!(app.current_track().is_none());
+        assert!(app.next_track().is_none());
+        assert!(!app.is_last_track());
+        assert_eq!(app.duration_secs, 0.0);
+        assert_eq!(app.progress(), 0.0);
+        assert_eq!(app.remaining_secs(), 0.0);
+
+        app.wrap_navigation = true;
+        app.select_next();
+        app.select_prev();
+        app.select_page(10);
+        app.select_last();
+        assert_eq!(app.selected_index, 0);
+        assert!(app.selected_track().is_none());
+    }
+
+    fn by(name: &str, artist: &str) -> Track {
+        Track {
+            artists: vec![artist.to_string()],
+            ..Track::test(name)
+        }
+    }
+
+    #[test]
+    fn test_search_selects_first_match_and_wraps() {
+        let tracks = vec![
+            by("Intro", "Band"),
+            by("Blue Song", "Singer"),
+            by("Outro", "Blue Band"),
+            by("Other", "Someone"),
+        ];
+        let mut app = App::new("Test".to_string(), tracks, PlayerBackend::Mpv);
+
+        app.start_search();
+        for c in "BLUE".chars() {
+            app.push_search_char(c);
+        }
+        // Case-insensitive, by name or artist
+        assert_eq!(app.search_matches, vec![1, 2]);
+        assert_eq!(app.selected_index, 1);
+
+        app.next_search_match();
+        assert_eq!(app.selected_index, 2);
+        app.next_search_match();
+        assert_eq!(app.selected_index, 1);
+        app.prev_search_match();
+        assert_eq!(app.selected_index, 2);
+
+        app.push_search_char('x');
+        assert!(app.search_matches.is_empty());
+        assert_eq!(app.selected_index, 2);
+        app.pop_search_char();
+        assert_eq!(app.search_matches, vec![1, 2]);
+
+        app.cancel_search();
+        assert!(!app.is_searching());
+        assert!(app.search_matches.is_empty());
+    }
+
+    #[test]
+    fn test_seek_clamps_to_track() {
+        let mut app = App::new(
+            "Test".to_string(),
+            vec![Track::test("a")],
+            PlayerBackend::Mpv,
+        );
+        app.duration_secs = 30.0;
+        app.position_secs = 3.0;
+
+        app.start_seeking();
+        app.seek_backward(5.0);
+        assert_eq!(app.get_seek_position(), Some(0.0));
+        app.seek_forward(45.0);
+        assert_eq!(app.get_seek_position(), Some(30.0));
+        assert_eq!(app.seek_progress(), 1.0);
+
+        assert_eq!(app.finish_scrub(1.5), 30.0);
+        assert!(!app.is_seeking());
+        assert_eq!(app.finish_scrub(-1.0), 0.0);
+    }
+
+    #[test]
+    fn test_cycle_repeat() {
+        let mut app = App::new(
+            "Test".to_string(),
+            vec![Track::test("a")],
+            PlayerBackend::Mpv,
+        );
+        assert_eq!(app.repeat_mode, RepeatMode::None);
+        app.cycle_repeat();
+        assert_eq!(app.repeat_mode, RepeatMode::All);
+        app.cycle_repeat();
+        assert_eq!(app.repeat_mode, RepeatMode::One);
+        app.cycle_repeat();
+        assert_eq!(app.repeat_mode, RepeatMode::None);
+    }
+
+    #[test]
+    fn test_lyrics_scroll_and_auto_scroll() {
+        let mut app = App::new(
+            "Test".to_string(),
+            vec![Track::test("a")],
+            PlayerBackend::Mpv,
+        );
+        app.lyrics = Some(Lyrics {
+            plain: Some("one\ntwo\nthree".to_string()),
+            ..Lyrics::default()
+        });
+        assert_eq!(app.lyrics_line_count(), 3);
+        assert!(app.lyrics_auto_scroll);
+
+        // Manual scrolling stops following the song, within the lyrics
+        for _ in 0..5 {
+            app.lyrics_scroll_down(app.lyrics_line_count());
+        }
+        assert_eq!(app.lyrics_scroll, 2);
+        assert!(!app.lyrics_auto_scroll);
+
+        app.lyrics_toggle_auto_scroll();
+        assert!(app.lyrics_auto_scroll);
+        app.lyrics_toggle_auto_scroll();
+        assert!(!app.lyrics_auto_scroll);
+
+        app.reset_lyrics_scroll();
+        assert_eq!(app.lyrics_scroll, 0);
+        assert!(app.lyrics_auto_scroll);
+        app.lyrics_scroll_up();
+        assert_eq!(app.lyrics_scroll, 0);
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_media_commands_bypass_prompts() {
+        let tracks = vec![Track::test("a"), Track::test("b")];
+        let mut app = App::new("Test".to_string(), tracks, PlayerBackend::Mpv);
+        app.start_search();
+
+        assert_eq!(
+            app.media_action(MediaCommand::PlayPause),
+            Some(Action::Pause(true))
+        );
+        // Nothing was typed into the search prompt
+        assert_eq!(app.search_query.as_deref(), Some(""));
+        assert_eq!(app.media_action(MediaCommand::Pause), None);
+        assert_eq!(
+            app.media_action(MediaCommand::Play),
+            Some(Action::Pause(false))
+        );
+        assert_eq!(app.media_action(MediaCommand::Next), Some(Action::Next));
+        assert_eq!(
+            app.media_action(MediaCommand::SeekBy(-5.0)),
+            Some(Action::SeekBy(-5.0))
+        );
+
+        // Positions past the end of the track are ignored
+        assert_eq!(
+            app.media_action(MediaCommand::SeekTo(0.5)),
+            Some(Action::Seek(0.5))
+        );
+        assert_eq!(app.media_action(MediaCommand::SeekTo(5.0)), None);
+    }
+
+    #[test]
+    fn test_handle_key_returns_player_actions() {
+        let tracks = vec![Track::test("a"), Track::test("b"), Track::test("c")];
+        let mut app = App::new("Test".to_string(), tracks, PlayerBackend::Mpv);
+
+        assert_eq!(
+            app.handle_key(key(KeyCode::Char(' ')), 10),
+            Some(Action::Pause(true))
+        );
+        assert!(app.is_paused);
+        assert_eq!(
+            app.handle_key(key(KeyCode::Char('s')), 10),
+            Some(Action::Shuffle(true))
+        );
+        assert_eq!(
+            app.handle_key(key(KeyCode::Char('r')), 10),
+            Some(Action::Repeat(RepeatMode::All))
+        );
+
+        // Enter on the playing track does nothing; elsewhere it plays
+        assert_eq!(app.handle_key(key(KeyCode::Enter), 10), None);
+        assert_eq!(app.handle_key(key(KeyCode::Down), 10), None);
+        assert_eq!(
+            app.handle_key(key(KeyCode::Enter), 10),
+            Some(Action::Play(1))
+        );
+
+        // Typed keys go to the open prompt instead of the player
+        app.handle_key(key(KeyCode::Char('/')), 10);
+        assert_eq!(app.handle_key(key(KeyCode::Char('q')), 10), None);
+        assert_eq!(app.search_query.as_deref(), Some("q"));
+        app.handle_key(key(KeyCode::Esc), 10);
+        assert_eq!(
+            app.handle_key(key(KeyCode::Char('q')), 10),
+            Some(Action::Quit)
+        );
+    }
+
+    #[test]
+    fn test_handle_key_seek_prompt() {
+        let mut app = App::new(
+            "Test".to_string(),
+            vec![Track::test("a")],
+            PlayerBackend::Spotify,
+        );
+        app.duration_secs = 60.0;
+        app.position_secs = 20.0;
+
+        app.handle_key(key(KeyCode::Char('g')), 10);
+        app.handle_key(key(KeyCode::Right), 10);
+        assert_eq!(
+            app.handle_key(key(KeyCode::Enter), 10),
+            Some(Action::Seek(25.0))
+        );
+        assert!(!app.is_seeking());
+    }
+
+    #[test]
+    fn test_tick_and_start_track() {
+        let mut app = App::new(
+            "Test".to_string(),
+            vec![Track::test("a"), Track::test("b")],
+            PlayerBackend::Spotify,
+        );
+        app.tick(0.5);
+        app.tick(5.0);
+        assert_eq!(app.position_secs, 1.0);
+
+        app.is_paused = true;
+        app.position_secs = 0.0;
+        app.tick(0.5);
+        assert_eq!(app.position_secs, 0.0);
+
+        app.show_lyrics = true;
+        app.lyrics_loading = true;
+        app.start_track(1);
+        assert_eq!(app.current_index, 1);
+        assert!(!app.lyrics_loading);
+        assert_eq!(app.lyrics_wanted().map(|t| t.id.as_str()), Some("b"));
+    }
+}
@@ -1,5 +1,5 @@
 mod app;
 mod ui;
 
-pub use app::{App, PlayerBackend};
-pub use ui::Tui;
+pub use app::{Action, App, PlayerBackend};
+pub use ui::{Scrub, Tui};
@@ -1,20 +1,27 @@
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyEvent, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyEvent, KeyEventKind, MouseButton,
+        MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Position, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Gauge, List, ListItem, Paragraph},
-    Frame, Terminal,
+    Frame, Terminal, TerminalOptions, Viewport,
 };
 use std::io::{self, Stdout};
+use std::time::{Duration, Instant};
 
 use super::App;
+use crate::playback::events::RepeatMode;
+use crate::playback::{LyricsKind, MediaCommand, MediaControls};
+use crate::utils::text;
 
 const SAKURA_PINK: Color = Color::Rgb(255, 183, 197);
 const SAKURA_SOFT: Color = Color::Rgb(255, 218, 233);
@@ -25,40 +32,248 @@ const SAKURA_BG: Color = Color::Rgb(30, 30, 35);
 const SAKURA_FG: Color = Color::Rgb(240, 240, 245);
 const SAKURA_DIM: Color = Color::Rgb(120, 120, 130);
 
+/// Terminal columns a track name gets in the playlist pane before it is cut
+const PLAYLIST_NAME_WIDTH: usize = 25;
+
+/// Status symbols drawn in the header
+struct Symbols {
+    playing: &'static str,
+    paused: &'static str,
+    loading: &'static str,
+    shuffle: &'static str,
+    repeat_all: &'static str,
+    repeat_one: &'static str,
+}
+
+const UNICODE_SYMBOLS: Symbols = Symbols {
+    playing: "▶",
+    paused: "⏸",
+    loading: "◌",
+    shuffle: "🔀",
+    repeat_all: "⟳",
+    repeat_one: "🔂",
+};
+
+const ASCII_SYMBOLS: Symbols = Symbols {
+    playing: ">",
+    paused: "||",
+    loading: "..",
+    shuffle: "[S]",
+    repeat_all: "[R]",
+    repeat_one: "[R1]",
+};
+
+fn symbols(app: &App) -> &'static Symbols {
+    if app.ascii_symbols {
+        &ASCII_SYMBOLS
+    } else {
+        &UNICODE_SYMBOLS
+    }
+}
+
+/// Key poll timeout while something is changing on screen
+const ACTIVE_POLL: Duration = Duration::from_millis(100);
+/// Key poll timeout while paused and idle, to cut wakeups
+const IDLE_POLL: Duration = Duration::from_millis(500);
+
+/// A mouse drag on the progress bar, as a fraction of the track
+pub enum Scrub {
+    /// Still dragging
+    Move(f64),
+    /// Button released here
+    Release(f64),
+}
+
 pub struct Tui {
     terminal: Terminal<CrosstermBackend<Stdout>>,
+    /// Minimum time between redraws while playing
+    refresh: Duration,
+    last_draw: Option<Instant>,
+    /// Something changed that should be drawn without waiting for a tick
+    dirty: bool,
+    /// Whether the last `draw_if_needed` saw playback or loading in progress
+    was_active: bool,
+    /// Where the progress bar was last drawn, for mouse scrubbing
+    progress_area: Rect,
+    dragging: bool,
+    scrub: Option<Scrub>,
+    /// Desktop media keys, fed in as key presses and scrubs
+    media: MediaControls,
+    /// One inline line instead of the full-screen layout
+    mini: bool,
 }
 
 impl Tui {
-    pub fn new() -> Result<Self> {
+    /// Take over the terminal, or with `mini` draw a single line in place
+    /// below the prompt without switching to the alternate screen
+    pub fn new(mini: bool) -> Result<Self> {
         enable_raw_mode()?;
         let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen)?;
-        let backend = CrosstermBackend::new(stdout);
-        let terminal = Terminal::new(backend)?;
+        let terminal = if mini {
+            execute!(stdout, EnableMouseCapture)?;
+            Terminal::with_options(
+                CrosstermBackend::new(stdout),
+                TerminalOptions {
+                    viewport: Viewport::Inline(1),
+                },
+            )?
+        } else {
+            execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+            Terminal::new(CrosstermBackend::new(stdout))?
+        };
 
-        Ok(Self { terminal })
+        Ok(Self {
+            terminal,
+            refresh: ACTIVE_POLL,
+            last_draw: None,
+            dirty: true,
+            was_active: true,
+            progress_area: Rect::default(),
+            dragging: false,
+            scrub: None,
+            media: MediaControls::disabled(),
+            mini,
+        })
+    }
+
+    pub fn set_refresh(&mut self, refresh: Duration) {
+        self.refresh = refresh;
+    }
+
+    pub fn set_media(&mut self, media: MediaControls) {
+        self.media = media;
+    }
+
+    /// Redraw on the next `draw_if_needed`, for changes that don't come
+    /// from a key press (lyrics arriving, the playlist file reloading)
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
     }
 
     pub fn draw(&mut self, app: &App) -> Result<()> {
-        self.terminal.draw(|f| render(f, app))?;
+        let mut progress_area = Rect::default();
+        let draw_frame = if self.mini { render_mini } else { render };
+        self.terminal.draw(|f| progress_area = draw_frame(f, app))?;
+        self.progress_area = progress_area;
+        self.last_draw = Some(Instant::now());
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Draw when marked dirty, or on the refresh tick while playback or a
+    /// load is in progress. While paused and idle nothing moves, so the
+    /// screen is left alone.
+    pub fn draw_if_needed(&mut self, app: &App) -> Result<()> {
+        let active = Self::is_active(app);
+        // One more tick after going idle, so the final state is on screen
+        let ticking = active || self.was_active;
+        let due = self
+            .last_draw
+            .is_none_or(|last| last.elapsed() >= self.refresh);
+
+        if self.dirty || (ticking && due) {
+            self.was_active = active;
+            self.draw(app)?;
+        }
         Ok(())
     }
 
-    pub fn poll_key(&self) -> Result<Option<KeyEvent>> {
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
+    fn is_active(app: &App) -> bool {
+        !app.is_paused || app.loading || app.lyrics_loading
+    }
+
+    /// Number of tracks visible in the playlist panel
+    pub fn playlist_page_size(&self) -> usize {
+        // outer margin and panel borders take two rows each
+        self.terminal
+            .size()
+            .map(|size| size.height.saturating_sub(4) as usize)
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    /// A request from a desktop media key or widget, if one came in. Also
+    /// publishes the player state to the desktop, so call it every tick.
+    pub fn poll_media(&mut self, app: &App) -> Option<MediaCommand> {
+        self.media.update(
+            app.current_index,
+            app.current_track(),
+            app.position_secs,
+            app.is_paused,
+        );
+        let command = self.media.try_recv()?;
+        self.dirty = true;
+        Some(command)
+    }
+
+    /// Wait for a key press, briefly while playing and longer while idle.
+    /// Any key press or resize marks the screen dirty.
+    pub fn poll_key(&mut self, app: &App) -> Result<Option<KeyEvent>> {
+        let timeout = if Self::is_active(app) || self.dirty {
+            ACTIVE_POLL
+        } else {
+            IDLE_POLL
+        };
+
+        if event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    self.dirty = true;
                     return Ok(Some(key));
                 }
+                Event::Resize(..) => self.dirty = true,
+                Event::Mouse(mouse) => self.handle_mouse(mouse),
+                _ => {}
             }
         }
         Ok(None)
     }
 
+    /// The latest progress bar drag since the last call
+    pub fn take_scrub(&mut self) -> Option<Scrub> {
+        self.scrub.take()
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let area = self.progress_area;
+        let ratio = if area.width > 1 {
+            let offset = mouse.column.saturating_sub(area.x) as f64;
+            (offset / (area.width - 1) as f64).min(1.0)
+        } else {
+            0.0
+        };
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left)
+                if area.contains(Position::new(mouse.column, mouse.row)) =>
+            {
+                self.dragging = true;
+                self.scrub = Some(Scrub::Move(ratio));
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.dragging => {
+                self.scrub = Some(Scrub::Move(ratio));
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.dragging => {
+                self.dragging = false;
+                self.scrub = Some(Scrub::Release(ratio));
+            }
+            _ => return,
+        }
+        self.dirty = true;
+    }
+
     pub fn restore(&mut self) -> Result<()> {
         disable_raw_mode()?;
-        execute!(self.terminal.backend_mut(), LeaveAlternateScreen)?;
+        if self.mini {
+            self.terminal.clear()?;
+            execute!(self.terminal.backend_mut(), DisableMouseCapture)?;
+        } else {
+            execute!(
+                self.terminal.backend_mut(),
+                LeaveAlternateScreen,
+                DisableMouseCapture
+            )?;
+        }
         self.terminal.show_cursor()?;
         Ok(())
     }
@@ -67,12 +282,21 @@ impl Tui {
 impl Drop for Tui {
     fn drop(&mut self) {
         let _ = disable_raw_mode();
-        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen);
+        if self.mini {
+            let _ = execute!(self.terminal.backend_mut(), DisableMouseCapture);
+        } else {
+            let _ = execute!(
+                self.terminal.backend_mut(),
+                LeaveAlternateScreen,
+                DisableMouseCapture
+            );
+        }
         let _ = self.terminal.show_cursor();
     }
 }
 
-fn render(frame: &mut Frame, app: &App) {
+/// Draw the whole player, returning where the progress bar went
+fn render(frame: &mut Frame, app: &App) -> Rect {
     let area = frame.area();
 
     frame.render_widget(Block::default().style(Style::default().bg(SAKURA_BG)), area);
@@ -104,11 +328,73 @@ fn render(frame: &mut Frame, app: &App) {
     draw_next_up(frame, app, left_chunks[3]);
     draw_controls(frame, app, left_chunks[5]);
 
-    if app.show_lyrics {
+    if app.show_markers {
+        draw_markers(frame, app, main_chunks[1]);
+    } else if app.show_lyrics {
         draw_lyrics(frame, app, main_chunks[1]);
     } else {
         draw_playlist(frame, app, main_chunks[1]);
     }
+
+    left_chunks[2]
+}
+
+/// Draw `--mini` mode: status, title and artists, then the progress bar,
+/// all on one line. Returns where the progress bar went.
+fn render_mini(frame: &mut Frame, app: &App) -> Rect {
+    let area = frame.area();
+
+    frame.render_widget(Block::default().style(Style::default().bg(SAKURA_BG)), area);
+
+    let bar_width = (area.width / 3).clamp(12, 40).min(area.width);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(bar_width)])
+        .split(area);
+
+    let symbols = symbols(app);
+    let status = if app.loading {
+        symbols.loading
+    } else if app.is_paused {
+        symbols.paused
+    } else {
+        symbols.playing
+    };
+
+    let mut line = Line::from(Span::styled(
+        format!("{} ", status),
+        Style::default().fg(SEA_GREEN),
+    ));
+    if let Some(error) = &app.error {
+        line.push_span(Span::styled(
+            error.as_str(),
+            Style::default().fg(Color::Rgb(255, 100, 100)),
+        ));
+    } else if let Some(notice) = &app.notice {
+        line.push_span(Span::styled(
+            notice.as_str(),
+            Style::default().fg(SEA_GREEN),
+        ));
+    } else if let Some(track) = app.current_track() {
+        line.push_span(Span::styled(
+            track.name.as_str(),
+            Style::default().fg(SAKURA_FG).add_modifier(Modifier::BOLD),
+        ));
+        line.push_span(Span::styled(
+            format!(" · {} ", track.artists.join(", ")),
+            Style::default().fg(SEA_GREEN_BRIGHT),
+        ));
+    } else {
+        line.push_span(Span::styled(
+            "Nothing playing",
+            Style::default().fg(SAKURA_DIM),
+        ));
+    }
+
+    frame.render_widget(Paragraph::new(line), chunks[0]);
+    draw_progress(frame, app, chunks[1]);
+
+    chunks[1]
 }
 
 fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
@@ -117,17 +403,28 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
         super::PlayerBackend::Spotify => "spotify",
     };
 
+    let symbols = symbols(app);
     let status = if app.loading {
-        "◌"
+        symbols.loading
     } else if app.is_paused {
-        "⏸"
+        symbols.paused
     } else {
-        "▶"
+        symbols.playing
     };
 
     let status_color = if app.loading { SAKURA_SOFT } else { SEA_GREEN };
 
-    let header = Line::from(vec![
+    let mut modes = Vec::new();
+    if app.shuffle {
+        modes.push(symbols.shuffle);
+    }
+    match app.repeat_mode {
+        RepeatMode::None => {}
+        RepeatMode::All => modes.push(symbols.repeat_all),
+        RepeatMode::One => modes.push(symbols.repeat_one),
+    }
+
+    let mut header = Line::from(vec![
         Span::styled(
             "grit ",
             Style::default()
@@ -143,6 +440,12 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
             Style::default().fg(SAKURA_DIM),
         ),
     ]);
+    if !modes.is_empty() {
+        header.push_span(Span::styled(
+            format!(" {}", modes.join(" ")),
+            Style::default().fg(SEA_GREEN),
+        ));
+    }
 
     let block = Block::default()
         .borders(Borders::BOTTOM)
@@ -161,7 +464,10 @@ fn draw_now_playing(frame: &mut Frame, app: &App, area: Rect) {
             )),
             Line::from(""),
             Line::from(Span::styled(
-                "fetching track",
+                match app.backend {
+                    super::PlayerBackend::Mpv => "fetching track · esc to cancel",
+                    super::PlayerBackend::Spotify => "fetching track",
+                },
                 Style::default().fg(SEA_GREEN_DIM),
             )),
         ]
@@ -186,11 +492,25 @@ fn draw_now_playing(frame: &mut Frame, app: &App, area: Rect) {
             .map(|t| (t.name.clone(), t.artists.join(", ")))
             .unwrap_or(("Nothing playing".into(), String::new()));
 
+        let mut position = if app.tracks.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "  {}/{} · {} left",
+                app.current_index + 1,
+                app.tracks.len(),
+                App::format_time(app.remaining_secs())
+            )
+        };
+        if let Some(volume) = app.volume {
+            position.push_str(&format!(" · vol {}%", volume));
+        }
+
         vec![
-            Line::from(Span::styled(
-                "now playing",
-                Style::default().fg(SEA_GREEN_DIM),
-            )),
+            Line::from(vec![
+                Span::styled("now playing", Style::default().fg(SEA_GREEN_DIM)),
+                Span::styled(position, Style::default().fg(SAKURA_DIM)),
+            ]),
             Line::from(""),
             Line::from(Span::styled(
                 title,
@@ -208,10 +528,14 @@ fn draw_progress(frame: &mut Frame, app: &App, area: Rect) {
         let seek_pos = app.get_seek_position().unwrap_or(0.0);
         let pos = App::format_time(seek_pos);
         let dur = App::format_time(app.duration_secs);
-        let label = format!(
-            "seek: {} / {} (<-/-> to move, enter to confirm, esc to cancel)",
-            pos, dur
-        );
+        let label = if app.scrubbing {
+            format!("seek: {} / {} (release to seek)", pos, dur)
+        } else {
+            format!(
+                "seek: {} / {} (<-/-> to move, enter to confirm, esc to cancel)",
+                pos, dur
+            )
+        };
         let gauge = Gauge::default()
             .gauge_style(Style::default().fg(SAKURA_PINK).bg(Color::Rgb(50, 50, 55)))
             .ratio(app.seek_progress())
@@ -241,13 +565,51 @@ fn draw_progress(frame: &mut Frame, app: &App, area: Rect) {
             .label(Span::styled(label, Style::default().fg(SAKURA_FG)));
 
         frame.render_widget(gauge, area);
+        draw_marker_ticks(frame, app, area);
+    }
+}
+
+/// Mark each marker's position on the progress bar, leaving the label row
+/// alone so the time stays readable
+fn draw_marker_ticks(frame: &mut Frame, app: &App, area: Rect) {
+    if app.duration_secs <= 0.0 || area.width < 2 {
+        return;
+    }
+    let label_row = area.y + area.height / 2;
+    for marker in app.current_markers() {
+        let ratio = (marker.secs / app.duration_secs).clamp(0.0, 1.0);
+        let x = area.x + (ratio * (area.width - 1) as f64).round() as u16;
+        for y in (area.y..area.y + area.height).filter(|&y| y != label_row) {
+            if let Some(cell) = frame.buffer_mut().cell_mut((x, y)) {
+                cell.set_symbol("┃").set_fg(SAKURA_PINK);
+            }
+        }
     }
 }
 
 fn draw_next_up(frame: &mut Frame, app: &App, area: Rect) {
-    use crate::playback::events::RepeatMode;
+    let content = if let Some(queue) = &app.remote_queue {
+        let mut header = String::from("next up");
+        if app.shuffle {
+            header.push_str(" | shuffle");
+        }
+        match app.repeat_mode {
+            RepeatMode::None => {}
+            RepeatMode::All => header.push_str(" | repeat all"),
+            RepeatMode::One => header.push_str(" | repeat one"),
+        }
+
+        let next = queue
+            .first()
+            .map(|(title, artists)| format!("{} - {}", title, artists))
+            .unwrap_or_else(|| "—".into());
 
-    let content = if app.shuffle {
+        vec![
+            Line::from(Span::styled(header, Style::default().fg(SAKURA_DIM))),
+            Line::from(""),
+            Line::from(Span::styled(next, Style::default().fg(SAKURA_DIM))),
+        ]
+    } else if app.shuffle {
         let repeat_text = match app.repeat_mode {
             RepeatMode::None => "",
             RepeatMode::All => " | repeat all",
@@ -324,11 +686,9 @@ fn draw_playlist(frame: &mut Frame, app: &App, area: Rect) {
             let is_match = app.is_search_match(i);
 
             let prefix = if is_current { "▶ " } else { "  " };
-            let name = if track.name.len() > 25 {
-                format!("{}...", &track.name[..22])
-            } else {
-                track.name.clone()
-            };
+            let name = text::truncate(&track.name, PLAYLIST_NAME_WIDTH);
+            let explicit = track.is_explicit();
+            let badge = if explicit { " [E]" } else { "" };
 
             let style = if is_selected {
                 Style::default().fg(SAKURA_BG).bg(SAKURA_PINK)
@@ -340,11 +700,16 @@ fn draw_playlist(frame: &mut Frame, app: &App, area: Rect) {
                 Style::default()
                     .fg(SEA_GREEN_BRIGHT)
                     .add_modifier(Modifier::BOLD)
+            } else if explicit && app.skip_explicit {
+                // Filtered out: playback steps over it
+                Style::default()
+                    .fg(SAKURA_DIM)
+                    .add_modifier(Modifier::CROSSED_OUT)
             } else {
                 Style::default().fg(SAKURA_FG)
             };
 
-            ListItem::new(format!("{}{}", prefix, name)).style(style)
+            ListItem::new(format!("{}{}{}", prefix, name, badge)).style(style)
         })
         .collect();
 
@@ -372,20 +737,52 @@ fn draw_playlist(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(list, area);
 }
 
+fn draw_markers(frame: &mut Frame, app: &App, area: Rect) {
+    let markers = app.current_markers();
+    let items: Vec<ListItem> = if markers.is_empty() {
+        vec![ListItem::new("No markers yet. Press 'b' to add one.")
+            .style(Style::default().fg(SAKURA_DIM))]
+    } else {
+        markers
+            .iter()
+            .enumerate()
+            .map(|(i, marker)| {
+                let style = if i == app.marker_selected {
+                    Style::default().fg(SAKURA_BG).bg(SAKURA_PINK)
+                } else {
+                    Style::default().fg(SAKURA_FG)
+                };
+                ListItem::new(format!(
+                    "{:>8}  {}",
+                    App::format_time(marker.secs),
+                    marker.label
+                ))
+                .style(style)
+            })
+            .collect()
+    };
+
+    let block = Block::default()
+        .title(Span::styled(" markers ", Style::default().fg(SAKURA_PINK)))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(SAKURA_DIM));
+
+    frame.render_widget(List::new(items).block(block), area);
+}
+
 fn draw_lyrics(frame: &mut Frame, app: &App, area: Rect) {
     let visible_height = area.height.saturating_sub(2) as usize;
     let current_idx = app.current_lyric_index();
 
     let auto_indicator = if app.lyrics_auto_scroll { "⟳" } else { "⏸" };
-    let title = if app.lyrics_loading {
+    let title = if let Some(ref query) = app.lyrics_query {
+        format!(" lyrics for: {}_ ", query)
+    } else if app.lyrics_loading {
         " lyrics (loading...) ".to_string()
     } else if let Some(ref lyrics) = app.lyrics {
-        if !lyrics.lines.is_empty() {
-            format!(" lyrics (synced) {} ", auto_indicator)
-        } else if lyrics.plain.is_some() {
-            " lyrics ".to_string()
-        } else {
-            " lyrics (not found) ".to_string()
+        match lyrics.kind() {
+            LyricsKind::Synced => format!(" lyrics ({}) {} ", lyrics.label(), auto_indicator),
+            _ => format!(" lyrics ({}) ", lyrics.label()),
         }
     } else {
         " lyrics ".to_string()
@@ -408,7 +805,11 @@ fn draw_lyrics(frame: &mut Frame, app: &App, area: Rect) {
                     })
                     .collect()
             } else {
-                vec![ListItem::new("No lyrics available").style(Style::default().fg(SAKURA_DIM))]
+                vec![
+                    ListItem::new("No lyrics available").style(Style::default().fg(SAKURA_DIM)),
+                    ListItem::new("Press 'e' to search with a different artist/title")
+                        .style(Style::default().fg(SAKURA_DIM)),
+                ]
             }
         } else {
             let scroll = if app.lyrics_auto_scroll {
@@ -466,6 +867,52 @@ fn draw_controls(frame: &mut Frame, app: &App, area: Rect) {
             Span::styled("[esc]", k),
             Span::styled(" cancel", d),
         ])
+    } else if app.is_editing_lyrics_query() {
+        Line::from(vec![
+            Span::styled("[type]", k),
+            Span::styled(" artist - title  ", d),
+            Span::styled("[enter]", k),
+            Span::styled(" fetch  ", d),
+            Span::styled("[esc]", k),
+            Span::styled(" cancel", d),
+        ])
+    } else if let Some(ref target) = app.copy_target {
+        let matched = app
+            .copy_target_match()
+            .filter(|_| !target.is_empty())
+            .map(|(_, name)| format!(" → {}", name))
+            .unwrap_or_default();
+        Line::from(vec![
+            Span::styled("add to: ", d),
+            Span::styled(format!("{}_", target), Style::default().fg(SAKURA_FG)),
+            Span::styled(format!("{}  ", matched), Style::default().fg(SEA_GREEN)),
+            Span::styled("[tab]", k),
+            Span::styled(" complete  ", d),
+            Span::styled("[enter]", k),
+            Span::styled(" stage  ", d),
+            Span::styled("[esc]", k),
+            Span::styled(" cancel", d),
+        ])
+    } else if let Some((secs, ref label)) = app.marker_draft {
+        Line::from(vec![
+            Span::styled(format!("marker at {}: ", App::format_time(secs)), d),
+            Span::styled(format!("{}_  ", label), Style::default().fg(SAKURA_FG)),
+            Span::styled("[enter]", k),
+            Span::styled(" save  ", d),
+            Span::styled("[esc]", k),
+            Span::styled(" cancel", d),
+        ])
+    } else if app.show_markers && app.notice.is_none() {
+        Line::from(vec![
+            Span::styled("[↑↓]", k),
+            Span::styled(" select  ", d),
+            Span::styled("[enter]", k),
+            Span::styled(" jump  ", d),
+            Span::styled("[b]", k),
+            Span::styled(" add  ", d),
+            Span::styled("[m/esc]", k),
+            Span::styled(" close", d),
+        ])
     } else if app.is_seeking() {
         Line::from(vec![
             Span::styled("[←→]", k),
@@ -483,12 +930,19 @@ fn draw_controls(frame: &mut Frame, app: &App, area: Rect) {
             ),
             Span::styled("[l]", k),
         ])
+    } else if let Some(ref notice) = app.notice {
+        Line::from(Span::styled(
+            notice.as_str(),
+            Style::default().fg(SEA_GREEN),
+        ))
     } else if app.show_lyrics {
         Line::from(vec![
             Span::styled("[↑↓]", k),
             Span::styled(" scroll  ", d),
             Span::styled("[a]", k),
             Span::styled(" auto  ", d),
+            Span::styled("[e]", k),
+            Span::styled(" re-fetch  ", d),
             Span::styled("[n/p]", k),
             Span::styled(" skip  ", d),
             Span::styled("[←→]", k),
@@ -527,3 +981,61 @@ fn draw_controls(frame: &mut Frame, app: &App, area: Rect) {
 
     frame.render_widget(Paragraph::new(controls).block(block), area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::Track;
+    use crate::tui::PlayerBackend;
+    use ratatui::backend::TestBackend;
+
+    fn track(name: &str) -> Track {
+        Track {
+            artists: vec!["アーティスト".to_string()],
+            duration_ms: 180_000,
+            ..Track::test(name)
+        }
+    }
+
+    #[test]
+    fn test_render_multibyte_names() {
+        // Byte 22 of each falls inside a character, which used to panic
+        let tracks = vec![
+            track("夜に駆ける夜に駆ける夜に駆ける"),
+            track("🎵🎶🎵🎶🎵🎶🎵🎶🎵🎶🎵🎶🎵"),
+            track("Ça plane pour moi — édition spéciale"),
+        ];
+        let app = App::new("プレイリスト".to_string(), tracks, PlayerBackend::Mpv);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal
+            .draw(|frame| {
+                render(frame, &app);
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_render_mini_fits_one_line() {
+        let app = App::new(
+            "Mix".to_string(),
+            vec![track("夜に駆ける")],
+            PlayerBackend::Mpv,
+        );
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 1)).unwrap();
+        let mut progress_area = Rect::default();
+        terminal
+            .draw(|frame| progress_area = render_mini(frame, &app))
+            .unwrap();
+
+        assert_eq!(progress_area.height, 1);
+        assert_eq!(progress_area.right(), 60);
+        // Wide characters leave a blank cell behind them
+        let line: String = (0..60)
+            .map(|x| terminal.backend().buffer()[(x, 0)].symbol().to_string())
+            .filter(|symbol| !symbol.trim().is_empty())
+            .collect();
+        assert!(line.contains("夜に駆ける·アーティスト"));
+    }
+}
@@ -0,0 +1,61 @@
+use std::ffi::OsStr;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+const GREEN: &str = "32";
+const RED: &str = "31";
+const YELLOW: &str = "33";
+
+/// Decide once at startup whether output gets ANSI colors. Colors are only
+/// used on a terminal, and never with `--no-color` or a non-empty `NO_COLOR`.
+pub fn configure(no_color: bool) {
+    let env = std::env::var_os("NO_COLOR");
+    let enabled = should_color(no_color, env.as_deref(), std::io::stdout().is_terminal());
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+fn should_color(no_color_flag: bool, no_color_env: Option<&OsStr>, is_tty: bool) -> bool {
+    // https://no-color.org: set and not empty disables color
+    let env_disabled = no_color_env.is_some_and(|v| !v.is_empty());
+    is_tty && !no_color_flag && !env_disabled
+}
+
+fn paint(code: &str, text: &str) -> String {
+    if ENABLED.load(Ordering::Relaxed) {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Added tracks
+pub fn green(text: &str) -> String {
+    paint(GREEN, text)
+}
+
+/// Removed tracks
+pub fn red(text: &str) -> String {
+    paint(RED, text)
+}
+
+/// Moved tracks and commit hashes
+pub fn yellow(text: &str) -> String {
+    paint(YELLOW, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_only_on_tty_without_opt_out() {
+        assert!(should_color(false, None, true));
+        assert!(!should_color(false, None, false));
+        assert!(!should_color(true, None, true));
+        assert!(!should_color(false, Some(OsStr::new("1")), true));
+        // An empty NO_COLOR doesn't count
+        assert!(should_color(false, Some(OsStr::new("")), true));
+    }
+}
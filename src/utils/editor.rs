@@ -0,0 +1,32 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+/// Editor command from `$VISUAL` or `$EDITOR`, falling back to `vi`
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Open `path` in the user's editor and wait for it to exit. The editor
+/// variable may carry arguments, e.g. `code --wait`.
+pub fn edit_file(path: &Path) -> Result<()> {
+    let command = editor_command();
+    let mut parts = command.split_whitespace();
+    let program = parts.next().context("Editor command is empty")?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to launch editor '{}'", command))?;
+
+    if !status.success() {
+        bail!("Editor '{}' exited with {}", command, status);
+    }
+    Ok(())
+}
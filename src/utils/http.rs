@@ -0,0 +1,41 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::state::config::HttpConfig;
+
+const USER_AGENT: &str = concat!("grit/", env!("CARGO_PKG_VERSION"));
+
+static SETTINGS: OnceLock<HttpConfig> = OnceLock::new();
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Set the timeouts from the user config. Must run before the first request;
+/// later calls are ignored.
+pub fn configure(config: &HttpConfig) {
+    let _ = SETTINGS.set(config.clone());
+}
+
+fn settings() -> &'static HttpConfig {
+    SETTINGS.get_or_init(HttpConfig::default)
+}
+
+/// Shared HTTP client, so providers, playback and lyrics reuse one
+/// connection pool. Cloning is cheap (the client is reference counted).
+pub fn client() -> reqwest::Client {
+    CLIENT
+        .get_or_init(|| {
+            let settings = settings();
+            reqwest::Client::builder()
+                .user_agent(USER_AGENT)
+                .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+                .timeout(Duration::from_secs(settings.timeout_secs))
+                .build()
+                .expect("Failed to build HTTP client")
+        })
+        .clone()
+}
+
+/// Tighter per-request timeout for calls made from the TUI loop, so a
+/// flaky network doesn't freeze the UI.
+pub fn interactive_timeout() -> Duration {
+    Duration::from_secs(settings().interactive_timeout_secs)
+}
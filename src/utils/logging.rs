@@ -0,0 +1,36 @@
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use tracing_subscriber::EnvFilter;
+
+/// Set up `tracing` for diagnostics: INFO by default, DEBUG with `--verbose`
+/// (`RUST_LOG` overrides both). Logs go to stderr, or to `log_file` while
+/// the TUI owns the terminal so they don't draw over the alternate screen.
+pub fn init(verbose: bool, log_file: Option<&Path>) -> Result<()> {
+    let level = if verbose { "debug" } else { "info" };
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("grit={}", level)));
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false);
+
+    match log_file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open log file {:?}", path))?;
+            builder
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .init();
+        }
+        None => builder.without_time().with_writer(std::io::stderr).init(),
+    }
+
+    Ok(())
+}
@@ -1 +1,7 @@
+pub mod color;
 pub mod crypto;
+pub mod editor;
+pub mod http;
+pub mod logging;
+pub mod pager;
+pub mod text;
@@ -0,0 +1,79 @@
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Result};
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turn paging off for this run (`--no-pager`)
+pub fn configure(no_pager: bool) {
+    DISABLED.store(no_pager, Ordering::Relaxed);
+}
+
+/// Pager command from `$PAGER`, falling back to `less`. An empty `$PAGER`
+/// or `cat` means no pager, as with git.
+fn pager_command() -> Option<String> {
+    match std::env::var("PAGER") {
+        Ok(value) if value.trim().is_empty() || value.trim() == "cat" => None,
+        Ok(value) => Some(value),
+        Err(_) => Some("less".to_string()),
+    }
+}
+
+/// Only page on a terminal, and only when the output wouldn't fit on screen
+fn should_page(output: &str) -> bool {
+    if DISABLED.load(Ordering::Relaxed) || !io::stdout().is_terminal() {
+        return false;
+    }
+    match crossterm::terminal::size() {
+        Ok((_, rows)) => output.lines().count() >= rows as usize,
+        Err(_) => false,
+    }
+}
+
+/// Print `output`, through the pager when it's longer than the terminal.
+/// Falls back to plain stdout if the pager can't be started.
+pub fn page(output: &str) -> Result<()> {
+    if should_page(output) {
+        if let Some(command) = pager_command() {
+            match run_pager(&command, output) {
+                Ok(()) => return Ok(()),
+                Err(e) => tracing::debug!("Pager '{}' failed: {:#}", command, e),
+            }
+        }
+    }
+
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(output.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn run_pager(command: &str, output: &str) -> Result<()> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().context("Pager command is empty")?;
+
+    let mut cmd = Command::new(program);
+    cmd.args(parts).stdin(Stdio::piped());
+    // Same defaults git uses: quit if it fits, keep colors, don't clear
+    if std::env::var_os("LESS").is_none() {
+        cmd.env("LESS", "FRX");
+    }
+
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to launch pager '{}'", command))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // Quitting the pager early closes the pipe, which isn't an error
+        if let Err(e) = stdin.write_all(output.as_bytes()) {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+    }
+
+    child.wait()?;
+    Ok(())
+}
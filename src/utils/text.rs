@@ -0,0 +1,64 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const ELLIPSIS: &str = "...";
+
+/// Columns `s` takes up in a terminal; CJK and emoji count as two
+pub fn width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+/// Cut `s` to at most `max` columns, ending in `...` when anything was cut.
+/// Never splits a character, unlike slicing by bytes.
+pub fn truncate(s: &str, max: usize) -> String {
+    if width(s) <= max {
+        return s.to_string();
+    }
+
+    let budget = max.saturating_sub(ELLIPSIS.len());
+    let mut out = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > budget {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    out.push_str(&ELLIPSIS[..max.min(ELLIPSIS.len())]);
+    out
+}
+
+/// `s` padded with spaces to `columns` wide, for aligning text that may
+/// contain wide characters (`format!("{:<n}")` counts chars, not columns)
+pub fn pad(s: &str, columns: usize) -> String {
+    let fill = columns.saturating_sub(width(s));
+    format!("{}{}", s, " ".repeat(fill))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_respects_char_and_column_boundaries() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("abcdefghij", 8), "abcde...");
+
+        // Each of these is 3 bytes and 2 columns; a byte slice at 22 would panic
+        let name = "夜に駆ける夜に駆ける夜に駆ける";
+        let cut = truncate(name, 25);
+        assert!(width(&cut) <= 25);
+        assert_eq!(cut, "夜に駆ける夜に駆ける夜...");
+
+        assert_eq!(truncate("café au lait", 7), "café...");
+        assert_eq!(truncate("anything", 2), "..");
+    }
+
+    #[test]
+    fn test_pad_counts_columns() {
+        assert_eq!(pad("ab", 4), "ab  ");
+        assert_eq!(pad("夜", 4), "夜  ");
+        assert_eq!(pad("toolong", 3), "toolong");
+    }
+}